@@ -0,0 +1,40 @@
+//! Parse throughput on ```data/sample.ess```, a synthetic Special Edition save (LZ4 compressed body,
+//! 603 change forms, most of them zlib compressed). Throughput is reported in bytes of the save processed.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use skyrim_savegame::*;
+
+const SAMPLE: &[u8] = include_bytes!("data/sample.ess");
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+
+    group.throughput(Throughput::Bytes(SAMPLE.len() as u64));
+    group.bench_function("parse_save_file", |b| b.iter(|| parse_save_file(SAMPLE.to_vec()).unwrap()));
+
+    // magic (13 bytes), header size (u32) and header
+    let header_len = 17 + u32::from_le_bytes([SAMPLE[13], SAMPLE[14], SAMPLE[15], SAMPLE[16]]) as usize;
+    let header = &SAMPLE[..header_len];
+    group.throughput(Throughput::Bytes(header_len as u64));
+    group.bench_function("parse_header_only", |b| b.iter(|| parse_header_only(header).unwrap()));
+
+    group.throughput(Throughput::Bytes(SAMPLE.len() as u64));
+    group.bench_function("decompress_body", |b| b.iter(|| decompress_body(SAMPLE).unwrap()));
+
+    // the change forms follow the form version, the plugin info, the file location table and two global data tables
+    let save = parse_save_file(SAMPLE.to_vec()).unwrap();
+    let sizes = save.section_sizes;
+    let body = decompress_body(SAMPLE).unwrap();
+    let start = 1 + sizes.plugin_info + sizes.file_location_table + sizes.global_data_table_1 + sizes.global_data_table_2;
+    let change_forms = &body[start..start + sizes.change_forms];
+    let count = save.file_location_table.change_form_count;
+    group.throughput(Throughput::Bytes(change_forms.len() as u64));
+    group.bench_function("read_change_forms", |b| b.iter(|| {
+        read_change_forms(&mut SaveFileReader::from_slice(change_forms), count).unwrap()
+    }));
+
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -1,22 +1,79 @@
 use std::env;
-use skyrim_savegame::parse_save_file;
-use std::fs::File;
+use skyrim_savegame::{parse_header_only, parse_save_file};
+use skyrim_savegame::header::Header;
+use std::fs::{self, File};
 use std::io::Read;
+use std::path::Path;
+use std::time::SystemTime;
 use skyrim_savegame::global_data::GlobalDataType;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let save_file = args.get(1).expect("Please provide the file to parse as arg 1!");
+    let save_file = args.get(1).expect("Please provide the file or directory to parse as arg 1!");
+    if Path::new(save_file).is_dir() {
+        list_directory(Path::new(save_file));
+        return;
+    }
     let mut fh = File::open(save_file).expect("Could not open file.");
     let mut buf: Vec<u8> = Vec::new();
     fh.read_to_end(&mut buf).expect("Could not read file!");
-    let parsed_file = parse_save_file(buf);
+    let parsed_file = parse_save_file(buf).expect("Could not parse save file!");
     //dbg!(parsed_file);
     dbg!(parsed_file.global_data_table_1.into_iter().filter(|x| {
-        if let GlobalDataType::TES(y) = x {
+        if let GlobalDataType::TES(_) = x {
             return true
         }
         false
     }
     ).collect::<Vec<GlobalDataType>>());
-}
\ No newline at end of file
+}
+
+/// Prints a table of the headers of all .ess files in ```dir```, oldest first.
+/// Files that can't be parsed are listed at the end.
+fn list_directory(dir: &Path) {
+    let entries = fs::read_dir(dir).expect("Could not read directory.");
+    let mut saves: Vec<(SystemTime, String, Header)> = Vec::new();
+    let mut failed: Vec<(String, String)> = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_save = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ess"));
+        if !is_save || !path.is_file() {
+            continue;
+        }
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let modified = entry.metadata().and_then(|m| m.modified()).unwrap_or(SystemTime::UNIX_EPOCH);
+        match read_header_of(&path) {
+            Ok(header) => saves.push((modified, file_name, header)),
+            Err(e) => failed.push((file_name, e)),
+        }
+    }
+    saves.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+
+    println!("{:<40} {:<20} {:>5} {:<30} {:>6}  Date", "File", "Name", "Level", "Location", "Save");
+    for (_, file_name, header) in &saves {
+        let date = match header.parse_game_date() {
+            Some(date) => format!("Day {}, {}:{:02}", date.days, date.hours, date.minutes),
+            None => header.game_date.clone(),
+        };
+        println!("{:<40} {:<20} {:>5} {:<30} {:>6}  {}", file_name, header.player_name, header.player_level,
+                 header.player_location, header.save_number, date);
+    }
+    if !failed.is_empty() {
+        println!();
+        println!("Could not parse {} file(s):", failed.len());
+        for (file_name, e) in &failed {
+            println!("{}: {}", file_name, e);
+        }
+    }
+}
+
+/// Reads only the beginning of the file up to the end of the header and parses it.
+fn read_header_of(path: &Path) -> Result<Header, String> {
+    let mut fh = File::open(path).map_err(|e| e.to_string())?;
+    // magic (13 bytes) and header size (u32)
+    let mut buf = vec![0u8; 17];
+    fh.read_exact(&mut buf).map_err(|e| e.to_string())?;
+    let header_size = u32::from_le_bytes([buf[13], buf[14], buf[15], buf[16]]);
+    fh.take(header_size as u64).read_to_end(&mut buf).map_err(|e| e.to_string())?;
+    parse_header_only(&buf).map_err(|e| e.to_string())
+}
@@ -1,23 +1,104 @@
 use crate::reader::*;
 use flate2::read::ZlibDecoder;
-use std::io::Read;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
 use std::fmt;
-use std::convert::TryInto;
-use crate::FormIdType;
+use std::ops::Range;
+use crate::change_form_data::{read_npc_data, read_cell_data, NpcData, CellData, ChangeFormReader};
+use crate::{ChangeFlag, ChangeFlags, ChangeFormType, FormIdType, SaveParseError, SaveWriteError};
+use crate::writer::{write_ref_id, SaveFileWriter};
 
-const CHANGE_FORM_DECODE_ERROR: &str = "Failed to decode compressed change form!";
+/// Oldest change form version with a known data layout (Skyrim 1.0 saves).
+pub const MIN_SUPPORTED_CHANGE_FORM_VERSION: u8 = 57;
+/// Newest change form version with a known data layout (Skyrim 1.9 saves use 74, Special Edition saves 78).
+pub const MAX_SUPPORTED_CHANGE_FORM_VERSION: u8 = 78;
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct ChangeForm {
     pub form_id: FormIdType,
     pub change_flags: u32,
     pub data_type: u8,
     pub version: u8,
-    pub length1: Vec<u8>,
-    pub length2: Vec<u8>,
+    /// The lengths as stored in the save, the data itself is always uncompressed.
+    /// The data as stored, possibly compressed, is available through ```index_change_forms()```.
+    pub length: ChangeFormLength,
     pub data: Vec<u8>,
 }
 
+/// The two length fields of a change form, in the width they are stored with.
+/// The width is given by the upper two bits of the data type, it is kept so an unchanged form
+/// is written back with the same layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeFormLength {
+    U8 { length1: u8, length2: u8 },
+    U16 { length1: u16, length2: u16 },
+    U32 { length1: u32, length2: u32 },
+}
+
+impl ChangeFormLength {
+    /// The lengths in the given width in bytes (1, 2 or 4). Values that don't fit are truncated,
+    /// see ```ChangeFormLength::fitting()```.
+    fn with_width(width: usize, length1: u32, length2: u32) -> ChangeFormLength {
+        match width {
+            1 => ChangeFormLength::U8 { length1: length1 as u8, length2: length2 as u8 },
+            2 => ChangeFormLength::U16 { length1: length1 as u16, length2: length2 as u16 },
+            _ => ChangeFormLength::U32 { length1, length2 },
+        }
+    }
+
+    /// The lengths in ```width```, or in the smallest larger width both fit into.
+    pub fn fitting(width: usize, length1: u32, length2: u32) -> ChangeFormLength {
+        let required = match length1.max(length2) {
+            0..=0xFF => 1,
+            0x100..=0xFFFF => 2,
+            _ => 4,
+        };
+        ChangeFormLength::with_width(width.max(required), length1, length2)
+    }
+
+    /// Width of each length field in bytes.
+    pub fn width(&self) -> usize {
+        match self {
+            ChangeFormLength::U8 { .. } => 1,
+            ChangeFormLength::U16 { .. } => 2,
+            ChangeFormLength::U32 { .. } => 4,
+        }
+    }
+
+    /// Length of the data as stored in the save (length1).
+    pub fn stored_len(&self) -> u32 {
+        match *self {
+            ChangeFormLength::U8 { length1, .. } => length1 as u32,
+            ChangeFormLength::U16 { length1, .. } => length1 as u32,
+            ChangeFormLength::U32 { length1, .. } => length1,
+        }
+    }
+
+    /// Uncompressed length of the data (length2), None if the data is stored uncompressed.
+    pub fn uncompressed_len(&self) -> Option<u32> {
+        let length2 = match *self {
+            ChangeFormLength::U8 { length2, .. } => length2 as u32,
+            ChangeFormLength::U16 { length2, .. } => length2 as u32,
+            ChangeFormLength::U32 { length2, .. } => length2,
+        };
+        Some(length2).filter(|length2| *length2 != 0)
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.uncompressed_len().is_some()
+    }
+
+    /// The bits of the data type specifying the width, see ```length_width()```.
+    fn data_type_bits(&self) -> u8 {
+        match self {
+            ChangeFormLength::U8 { .. } => 0,
+            ChangeFormLength::U16 { .. } => 64,
+            ChangeFormLength::U32 { .. } => 128,
+        }
+    }
+}
+
 impl fmt::Debug for ChangeForm {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ChangeForm")
@@ -25,126 +106,420 @@ impl fmt::Debug for ChangeForm {
             .field("change_flags", &self.change_flags)
             .field("data_type", &self.data_type)
             .field("version", &self.version)
-            .field("length2 (is compressed)", &self.length2)
+            .field("length", &self.length)
             .field("data (length)", &self.data.len())
             .finish()
     }
 }
 
-pub fn read_change_forms(sfr: &mut SaveFileReader, count: u32) -> Vec<ChangeForm> {
+impl ChangeForm {
+    /// The layout of the data blob depends on the change form version.
+    /// Returns false if the version is outside of the range this library knows how to decode.
+    pub fn is_supported_version(&self) -> bool {
+        (MIN_SUPPORTED_CHANGE_FORM_VERSION..=MAX_SUPPORTED_CHANGE_FORM_VERSION).contains(&self.version)
+    }
+
+    /// Bundles this change form with the form version of the save it belongs to (```SaveFile::form_version```).
+    ///
+    /// The layout of the data depends on both: the per-form ```version``` is the version the form was
+    /// last written with and determines the sub-records of the form, while the form version of the save
+    /// determines how the data around it is stored, e.g. the plugins the RefIDs refer to.
+    /// They usually match, but forms carried over from older saves can have an older version.
+    pub fn with_context(&self, form_version: u8) -> ChangeFormContext<'_> {
+        ChangeFormContext {
+            change_form: self,
+            form_version,
+        }
+    }
+
+    /// True if the data is stored zlib compressed in the save.
+    pub fn is_compressed(&self) -> bool {
+        self.length.is_compressed()
+    }
+
+    /// The data as ```write_change_form()``` stores it, compressed again if the form is compressed.
+    /// zlib doesn't guarantee to reproduce the original bytes, compare with
+    /// ```ChangeFormIndexEntry::stored_data()``` to see the bytes the save contains.
+    pub fn stored_data(&self) -> Result<Vec<u8>, SaveWriteError> {
+        if !self.is_compressed() {
+            return Ok(self.data.clone());
+        }
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&self.data)
+            .and_then(|_| encoder.finish())
+            .map_err(|e| SaveWriteError::Compression(e.to_string()))
+    }
+
+    /// Summarizes the metadata of this change form without decoding its data.
+    pub fn describe(&self) -> ChangeFormDescription {
+        let form_type = ChangeFormType::from_data_type(self.data_type);
+        ChangeFormDescription {
+            form_id: self.form_id,
+            form_type,
+            version: self.version,
+            is_compressed: self.is_compressed(),
+            stored_len: self.length.stored_len(),
+            uncompressed_len: self.data.len(),
+            change_flags: ChangeFlags(self.change_flags).set_flags(form_type),
+        }
+    }
+}
+
+/// Human readable summary of a change form, see ```ChangeForm::describe()```.
+#[derive(Clone, Debug)]
+pub struct ChangeFormDescription {
+    pub form_id: FormIdType,
+    pub form_type: ChangeFormType,
+    pub version: u8,
+    /// true if the data was stored zlib compressed (length2 != 0)
+    pub is_compressed: bool,
+    /// size of the data as stored in the save (length1)
+    pub stored_len: u32,
+    /// size of the data after decompression
+    pub uncompressed_len: usize,
+    pub change_flags: Vec<ChangeFlag>,
+}
+
+impl fmt::Display for ChangeFormDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:?} (version {}): ", self.form_type, self.form_id, self.version)?;
+        match self.is_compressed {
+            true => write!(f, "{} bytes compressed, {} bytes uncompressed", self.stored_len, self.uncompressed_len)?,
+            false => write!(f, "{} bytes", self.stored_len)?,
+        }
+        let flags: Vec<String> = self.change_flags.iter()
+            .map(|flag| match flag.name {
+                Some(name) => name.to_string(),
+                None => format!("bit {}", flag.bit),
+            })
+            .collect();
+        write!(f, ", flags [{}]", flags.join(", "))
+    }
+}
+
+/// The decoded data of a change form.
+#[derive(Clone, Debug)]
+pub enum ChangeFormData {
+    /// The data layout of this change form type is not parsed yet, contains the (decompressed) data.
+    Unparsed(Vec<u8>),
+    /// An actor base (NPC_), e.g. the player's skills, or the factions and essential flag of a follower.
+    Npc(Box<NpcData>),
+    /// A cell (CELL), its flags, name and owner. The references in the cell are not part of it.
+    Cell(CellData),
+}
+
+impl ChangeFormData {
+    /// The bytes that were not decoded, all of the data for ```Unparsed```.
+    pub fn remainder(&self) -> &[u8] {
+        match self {
+            ChangeFormData::Unparsed(data) => data,
+            ChangeFormData::Npc(npc) => &npc.remainder,
+            ChangeFormData::Cell(cell) => &cell.remainder,
+        }
+    }
+
+    /// True if all of the data was decoded. Only complete forms can be edited safely, as the
+    /// undecoded bytes of the others can depend on the decoded ones.
+    pub fn is_complete(&self) -> bool {
+        self.remainder().is_empty()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ChangeFormError {
+    /// The change form has a version whose data layout is unknown, decoding it would produce garbage.
+    UnsupportedVersion(u8),
+    /// The upper two bits of the data type, which specify the width of the length fields, are invalid.
+    InvalidLengthType(u8),
+    /// The compressed data could not be decompressed, contains the message of the decompressor.
+    Decompression(String),
+    /// The decompressed data does not have the length specified in the change form.
+    LengthMismatch { expected: u32, actual: usize },
+    /// The data is shorter than its sub-records or contains invalid values.
+    Reader(ReaderError),
+}
+
+impl fmt::Display for ChangeFormError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeFormError::UnsupportedVersion(version) => write!(f, "Unsupported change form version {}", version),
+            ChangeFormError::InvalidLengthType(data_type) => write!(f, "Invalid length type in data type {:#x}", data_type),
+            ChangeFormError::Decompression(message) => write!(f, "Failed to decode compressed change form: {}", message),
+            ChangeFormError::LengthMismatch { expected, actual } => {
+                write!(f, "Decompressed change form has {} bytes, expected {}", actual, expected)
+            }
+            ChangeFormError::Reader(e) => write!(f, "Could not read change form data: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ChangeFormError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ChangeFormError::Reader(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ReaderError> for ChangeFormError {
+    fn from(e: ReaderError) -> Self {
+        ChangeFormError::Reader(e)
+    }
+}
+
+/// A change form together with the form version of its save, see ```ChangeForm::with_context()```.
+#[derive(Clone, Copy, Debug)]
+pub struct ChangeFormContext<'a> {
+    pub change_form: &'a ChangeForm,
+    /// The form version of the save containing the change form.
+    pub form_version: u8,
+}
+
+impl ChangeFormContext<'_> {
+    /// Like ```read_change_form_data()```, but also fails with ```ChangeFormError::UnsupportedVersion```
+    /// if the form version of the save is outside of the known range, as the data would be read
+    /// with the wrong layout.
+    pub fn read_data(&self) -> Result<ChangeFormData, ChangeFormError> {
+        if !(MIN_SUPPORTED_CHANGE_FORM_VERSION..=MAX_SUPPORTED_CHANGE_FORM_VERSION).contains(&self.form_version) {
+            return Err(ChangeFormError::UnsupportedVersion(self.form_version));
+        }
+        read_change_form_data(self.change_form)
+    }
+}
+
+/// Decodes the data blob of a change form.
+/// Fails with ```ChangeFormError::UnsupportedVersion``` if the change form version is unknown,
+/// so callers can skip forms they can't safely decode.
+/// Only the version of the change form is checked, use ```ChangeFormContext::read_data()``` to check
+/// the form version of the save as well.
+pub fn read_change_form_data(change_form: &ChangeForm) -> Result<ChangeFormData, ChangeFormError> {
+    if !change_form.is_supported_version() {
+        return Err(ChangeFormError::UnsupportedVersion(change_form.version));
+    }
+    let mut r = ChangeFormReader::new(change_form);
+    match ChangeFormType::from_data_type(change_form.data_type) {
+        ChangeFormType::NPC => Ok(ChangeFormData::Npc(Box::new(read_npc_data(&mut r)?))),
+        ChangeFormType::CELL => Ok(ChangeFormData::Cell(read_cell_data(&mut r)?)),
+        _ => Ok(ChangeFormData::Unparsed(change_form.data.clone())),
+    }
+}
+
+/// The fixed metadata in front of the data of a change form.
+#[derive(Clone, Copy, Debug)]
+pub struct ChangeFormHeader {
+    pub form_id: FormIdType,
+    pub change_flags: u32,
+    pub data_type: u8,
+    pub version: u8,
+    /// Length of the data as stored in the save.
+    pub length1: u32,
+    /// Uncompressed length of the data, 0 if the data is stored uncompressed.
+    pub length2: u32,
+}
+
+pub fn read_change_form_header(sfr: &mut SaveFileReader) -> Result<ChangeFormHeader, SaveParseError> {
+    let form_id = read_ref_id(sfr)?;
+    let change_flags = sfr.read_u32()?;
+    let data_type = sfr.read_u8()?;
+    let version = sfr.read_u8()?;
+
+    // the upper two bits of the data type determine the width of the two length fields
+    let (length1, length2) = match length_width(data_type) {
+        Some(1) => (sfr.read_u8()? as u32, sfr.read_u8()? as u32),
+        Some(2) => (sfr.read_u16()? as u32, sfr.read_u16()? as u32),
+        Some(_) => (sfr.read_u32()?, sfr.read_u32()?),
+        None => return Err(ChangeFormError::InvalidLengthType(data_type).into()),
+    };
+
+    Ok(ChangeFormHeader {
+        form_id,
+        change_flags,
+        data_type,
+        version,
+        length1,
+        length2,
+    })
+}
+
+/// Width of the length fields in bytes, None if the length bits of the data type are invalid.
+fn length_width(data_type: u8) -> Option<usize> {
+    match data_type & 0b11000000 {
+        0 => Some(1),
+        64 => Some(2),
+        128 => Some(4),
+        _ => None,
+    }
+}
+
+pub fn read_change_forms(sfr: &mut SaveFileReader, count: u32) -> Result<Vec<ChangeForm>, SaveParseError> {
     let mut result: Vec<ChangeForm> = Vec::new();
-    println!("processing {} change forms.", count);
     for _i in 0..count {
-        //println!("handling change_form {}", i);
-        let form_id = read_ref_id(sfr);
-        let change_flags = sfr.read_u32();
-        let data_type = sfr.read_u8();
-        let data_length_val = data_type & 0b11000000;
-        let version = sfr.read_u8();
-
-        match data_length_val {
-            0 => {
-                let length1 = sfr.read_u8();
-                let length2 = sfr.read_u8();
-                match length2 == 0 {
-                    true => {
-                        result.push(ChangeForm {
-                            form_id,
-                            change_flags,
-                            data_type,
-                            version,
-                            length1: vec!(length1),
-                            length2: vec!(length2),
-                            data: sfr.read_bytes_to_vec(length1.into()),
-                        });
-                    }
-                    false => {
-                        let compressed = sfr.read_bytes_to_vec(length1.into());
-                        let mut decoder = ZlibDecoder::new(compressed.as_slice());
-                        let mut data: Vec<u8> = Vec::new();
-                        decoder.read_to_end(&mut data).expect(CHANGE_FORM_DECODE_ERROR);
-                        assert_eq!(data.len(), length2 as usize);
-                        result.push(ChangeForm {
-                            form_id,
-                            change_flags,
-                            data_type,
-                            version,
-                            length1: vec!(length1),
-                            length2: vec!(length2),
-                            data,
-                        });
-                    }
-                };
-            }
-            64 => {
-                let length1 = sfr.read_u16();
-                let length2 = sfr.read_u16();
-                match length2 == 0 {
-                    true => {
-                        result.push(ChangeForm {
-                            form_id,
-                            change_flags,
-                            data_type,
-                            version,
-                            length1: length1.to_le_bytes().to_vec(),
-                            length2: length2.to_le_bytes().to_vec(),
-                            data: sfr.read_bytes_to_vec(length1.into()),
-                        });
-                    }
-                    false => {
-                        let compressed = sfr.read_bytes_to_vec(length1.into());
-                        let mut decoder = ZlibDecoder::new(compressed.as_slice());
-                        let mut data: Vec<u8> = Vec::new();
-                        decoder.read_to_end(&mut data).expect(CHANGE_FORM_DECODE_ERROR);
-                        assert_eq!(data.len(), length2 as usize);
-                        result.push(ChangeForm {
-                            form_id,
-                            change_flags,
-                            data_type,
-                            version,
-                            length1: length1.to_le_bytes().to_vec(),
-                            length2: length2.to_le_bytes().to_vec(),
-                            data,
-                        });
-                    }
-                };
-            }
-            128 => {
-                let length1 = sfr.read_u32();
-                let length2 = sfr.read_u32();
-                match length2 == 0 {
-                    true => {
-                        result.push(ChangeForm {
-                            form_id,
-                            change_flags,
-                            data_type,
-                            version,
-                            length1: length1.to_le_bytes().to_vec(),
-                            length2: length2.to_le_bytes().to_vec(),
-                            data: sfr.read_bytes_to_vec(length1.try_into().expect("length1 value on change form too large.")),
-                        });
-                    }
-                    false => {
-                        let ulength1: usize = length1.try_into().expect("length1 value on change form too large.");
-                        let compressed = sfr.read_bytes_to_vec(ulength1);
-                        let mut decoder = ZlibDecoder::new(compressed.as_slice());
-                        let mut data: Vec<u8> = Vec::new();
-                        decoder.read_to_end(&mut data).expect(CHANGE_FORM_DECODE_ERROR);
-                        assert_eq!(data.len(), length2 as usize);
-                        result.push(ChangeForm {
-                            form_id,
-                            change_flags,
-                            data_type,
-                            version,
-                            length1: length1.to_le_bytes().to_vec(),
-                            length2: length2.to_le_bytes().to_vec(),
-                            data,
-                        });
-                    }
-                };
-            }
-            _ => panic!("length value on change form invalid!")
-        };
+        let header = read_change_form_header(sfr)?;
+        let stored = sfr.read_bytes(header.length1 as usize)?;
+        result.push(build_change_form(&header, stored)?);
+    }
+    Ok(result)
+}
+
+/// Same result as ```read_change_forms()```, but decompresses the data of the change forms on all cores.
+/// The boundaries of the forms are scanned sequentially first, which is cheap compared to the decompression.
+#[cfg(feature = "rayon")]
+pub fn read_change_forms_parallel(sfr: &mut SaveFileReader, count: u32) -> Result<Vec<ChangeForm>, SaveParseError> {
+    use rayon::prelude::*;
+
+    // headers and the position of their data, relative to the current position of sfr
+    let mut forms = Vec::new();
+    let mut scanner = SaveFileReader::from_slice(sfr.remaining());
+    for _i in 0..count {
+        let header = read_change_form_header(&mut scanner)?;
+        let start = scanner.get_index();
+        scanner.read_bytes(header.length1 as usize)?;
+        forms.push((header, start));
+    }
+    let consumed = scanner.get_index();
+
+    let section = sfr.remaining();
+    let result = forms.par_iter()
+        .map(|(header, start)| build_change_form(header, &section[*start..*start + header.length1 as usize]))
+        .collect::<Result<Vec<ChangeForm>, SaveParseError>>()?;
+    sfr.read_bytes(consumed)?;
+    Ok(result)
+}
+
+/// Where reading the change forms continues, see ```read_change_forms_from()``` and ```SaveFile::change_form_cursor()```.
+/// Only holds numbers, so it can be stored together with the uncompressed body (see ```decompress_body()```)
+/// to continue in another process without decompressing the save again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChangeFormCursor {
+    /// The position of the next change form in the uncompressed body.
+    pub offset: usize,
+    /// The number of change forms read so far.
+    pub read: u32,
+    /// The number of change forms in the save, see ```FileLocationTable::change_form_count```.
+    pub count: u32,
+}
+
+impl ChangeFormCursor {
+    /// Whether all change forms have been read.
+    pub fn is_done(&self) -> bool {
+        self.read >= self.count
+    }
+}
+
+/// Reads up to ```max``` change forms at ```cursor``` from the uncompressed ```body``` and moves the cursor behind them.
+/// The cursor is only moved on success, so a failed call can be repeated. Returns no forms once the cursor is done.
+pub fn read_change_forms_from(body: &[u8], cursor: &mut ChangeFormCursor, max: usize) -> Result<Vec<ChangeForm>, SaveParseError> {
+    let mut sfr = SaveFileReader::from_slice(body);
+    sfr.set_index(cursor.offset)?;
+    let count = (cursor.count.saturating_sub(cursor.read) as usize).min(max);
+    let mut result = Vec::with_capacity(count);
+    for _i in 0..count {
+        let header = read_change_form_header(&mut sfr)?;
+        let stored = sfr.read_bytes(header.length1 as usize)?;
+        result.push(build_change_form(&header, stored)?);
+    }
+    cursor.offset = sfr.get_index();
+    cursor.read += count as u32;
+    Ok(result)
+}
+
+/// Creates the change form from its header and data as stored in the save, decompressing it if necessary.
+pub(crate) fn build_change_form(header: &ChangeFormHeader, stored: &[u8]) -> Result<ChangeForm, SaveParseError> {
+    // length2 is the uncompressed length, 0 if the data is stored uncompressed
+    let data = match header.length2 == 0 {
+        true => stored.to_vec(),
+        false => decompress_change_form(stored, header.length2)?,
+    };
+
+    // read_change_form_header already checked the length type
+    let width = length_width(header.data_type).unwrap();
+    Ok(ChangeForm {
+        form_id: header.form_id,
+        change_flags: header.change_flags,
+        data_type: header.data_type,
+        version: header.version,
+        length: ChangeFormLength::with_width(width, header.length1, header.length2),
+        data,
+    })
+}
+
+/// Writes a change form, the counterpart of ```read_change_forms()```.
+/// The data is zlib compressed again if it was stored compressed (length2 != 0). The length fields
+/// keep their width unless the new lengths don't fit, then the length bits of the data type are widened.
+pub fn write_change_form(w: &mut SaveFileWriter, change_form: &ChangeForm) -> Result<(), SaveWriteError> {
+    let is_compressed = change_form.is_compressed();
+    let stored = change_form.stored_data()?;
+    let length1 = stored.len() as u32;
+    let length2 = if is_compressed { change_form.data.len() as u32 } else { 0 };
+
+    let length = ChangeFormLength::fitting(change_form.length.width(), length1, length2);
+    let width = length.width();
+
+    write_ref_id(w, change_form.form_id)?;
+    w.write_u32(change_form.change_flags);
+    w.write_u8(change_form.data_type & 0b00111111 | length.data_type_bits());
+    w.write_u8(change_form.version);
+    w.write_bytes(&length1.to_le_bytes()[..width]);
+    w.write_bytes(&length2.to_le_bytes()[..width]);
+    w.write_bytes(&stored);
+    Ok(())
+}
+
+/// Reads the headers of ```count``` change forms, skipping their data without decompressing it.
+pub fn scan_change_form_headers(sfr: &mut SaveFileReader, count: u32) -> Result<Vec<ChangeFormHeader>, SaveParseError> {
+    let mut result = Vec::new();
+    for _i in 0..count {
+        let header = read_change_form_header(sfr)?;
+        sfr.read_bytes(header.length1 as usize)?;
+        result.push(header);
     }
-    result
-}
\ No newline at end of file
+    Ok(result)
+}
+
+/// A change form located in the uncompressed body, see ```index_change_forms()```.
+#[derive(Clone, Debug)]
+pub struct ChangeFormIndexEntry {
+    pub header: ChangeFormHeader,
+    /// The range of the data as stored in the body, zlib compressed if ```header.length2``` is not 0.
+    pub data_range: Range<usize>,
+}
+
+impl ChangeFormIndexEntry {
+    /// The data exactly as stored in ```body```, the uncompressed body this entry was indexed in.
+    /// Unlike ```ChangeForm::data``` it is not decompressed, e.g. to compare it with ```ChangeForm::stored_data()```.
+    pub fn stored_data<'a>(&self, body: &'a [u8]) -> Result<&'a [u8], ReaderError> {
+        body.get(self.data_range.clone()).ok_or(ReaderError::UnexpectedEnd {
+            index: self.data_range.start,
+            requested: self.data_range.len(),
+            buffer_len: body.len(),
+        })
+    }
+}
+
+/// Like ```scan_change_form_headers()```, but also records where the data of each form is in the buffer of ```sfr```.
+pub fn index_change_form_headers(sfr: &mut SaveFileReader, count: u32) -> Result<Vec<ChangeFormIndexEntry>, SaveParseError> {
+    let mut result = Vec::new();
+    for _i in 0..count {
+        let header = read_change_form_header(sfr)?;
+        let start = sfr.get_index();
+        sfr.read_bytes(header.length1 as usize)?;
+        result.push(ChangeFormIndexEntry { header, data_range: start..sfr.get_index() });
+    }
+    Ok(result)
+}
+
+/// Decompresses the change form of ```entry``` from the uncompressed body it was indexed in.
+pub fn decode_change_form(body: &[u8], entry: &ChangeFormIndexEntry) -> Result<ChangeForm, SaveParseError> {
+    build_change_form(&entry.header, entry.stored_data(body)?)
+}
+
+fn decompress_change_form(compressed: &[u8], uncompressed_len: u32) -> Result<Vec<u8>, ChangeFormError> {
+    let mut decoder = ZlibDecoder::new(compressed);
+    let mut data: Vec<u8> = Vec::new();
+    decoder.read_to_end(&mut data).map_err(|e| ChangeFormError::Decompression(e.to_string()))?;
+    if data.len() != uncompressed_len as usize {
+        return Err(ChangeFormError::LengthMismatch { expected: uncompressed_len, actual: data.len() });
+    }
+    Ok(data)
+}
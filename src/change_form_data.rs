@@ -0,0 +1,613 @@
+//! Typed layouts of change form data.
+//!
+//! The data of a change form is a sequence of sub-records, each present only if the corresponding
+//! change flag is set. The order of the sub-records is fixed per form type and follows community
+//! reverse engineering; sub-records following the last understood one are kept as raw bytes.
+
+use crate::reader::*;
+use crate::{ChangeFlags, ChangeForm, FormIdType};
+use std::convert::TryInto;
+
+/// Names of the 18 skills in the order they are stored in ```NpcSkills```.
+pub const SKILL_NAMES: [&str; 18] = [
+    "OneHanded", "TwoHanded", "Marksman", "Block", "Smithing", "HeavyArmor", "LightArmor", "Pickpocket",
+    "Lockpicking", "Sneak", "Alchemy", "Speechcraft", "Alteration", "Conjuration", "Destruction",
+    "Illusion", "Restoration", "Enchanting",
+];
+
+/// The data of an actor base (NPC_) change form.
+///
+/// The player's skills are stored in the change form of the player's actor base (form id 0x7),
+/// not in the change form of the player reference (ACHR, form id 0x14).
+#[derive(Clone, Debug)]
+pub struct NpcData {
+    /// CHANGE_FORM_FLAGS
+    pub form_flags: Option<ChangeFormFlags>,
+    /// CHANGE_ACTOR_BASE_DATA
+    pub base_data: Option<ActorBaseData>,
+    /// CHANGE_ACTOR_BASE_FACTIONS
+    pub factions: Option<Vec<FactionRank>>,
+    /// CHANGE_ACTOR_BASE_SPELLLIST
+    pub spell_list: Option<SpellList>,
+    /// CHANGE_ACTOR_BASE_AIDATA, the AIDT subrecord of the NPC_ record. Not parsed further.
+    pub ai_data: Option<Vec<u8>>,
+    /// CHANGE_ACTOR_BASE_FULLNAME
+    pub full_name: Option<String>,
+    /// CHANGE_NPC_SKILLS
+    pub skills: Option<NpcSkills>,
+    /// The sub-records following the skills (class, race, face, gender, outfits), not parsed yet.
+    pub remainder: Vec<u8>,
+}
+
+impl NpcData {
+    /// The rank in ```faction```, None if the factions weren't changed or don't contain it.
+    /// A rank of -1 means the actor was removed from the faction.
+    pub fn faction_rank(&self, faction: FormIdType) -> Option<i8> {
+        self.factions.as_ref()?.iter().find(|entry| entry.faction == faction).map(|entry| entry.rank)
+    }
+}
+
+/// The flags of the form itself, present if CHANGE_FORM_FLAGS is set.
+#[derive(Clone, Copy, Debug)]
+pub struct ChangeFormFlags {
+    pub flags: u32,
+    pub unknown: u16,
+}
+
+/// Flag of ```ActorBaseData::flags```: the actor can't be killed, only knocked down.
+pub const ACTOR_BASE_FLAG_ESSENTIAL: u32 = 0x02;
+/// Flag of ```ActorBaseData::flags```: the actor can only be killed by the player.
+pub const ACTOR_BASE_FLAG_PROTECTED: u32 = 0x800;
+
+/// The ACBS subrecord of the NPC_ record.
+#[derive(Clone, Copy, Debug)]
+pub struct ActorBaseData {
+    pub flags: u32,
+    pub magicka_offset: i16,
+    pub stamina_offset: i16,
+    /// The level, or the level multiplier * 1000 if the PCLevelMult flag is set.
+    pub level: u16,
+    pub calc_min_level: u16,
+    pub calc_max_level: u16,
+    pub speed_multiplier: u16,
+    pub disposition_base: i16,
+    pub template_flags: u16,
+    pub health_offset: i16,
+    pub bleedout_override: u16,
+}
+
+impl ActorBaseData {
+    pub fn is_essential(&self) -> bool {
+        self.flags & ACTOR_BASE_FLAG_ESSENTIAL != 0
+    }
+
+    pub fn is_protected(&self) -> bool {
+        self.flags & ACTOR_BASE_FLAG_PROTECTED != 0
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct FactionRank {
+    pub faction: FormIdType,
+    /// -1 if the actor was removed from the faction.
+    pub rank: i8,
+}
+
+impl Readable for FactionRank {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        Ok(FactionRank {
+            faction: read_ref_id(r)?,
+            rank: r.read_u8()? as i8,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SpellList {
+    pub spells: Vec<FormIdType>,
+    pub leveled_spells: Vec<FormIdType>,
+    pub shouts: Vec<FormIdType>,
+}
+
+/// The DNAM subrecord of the NPC_ record.
+#[derive(Clone, Copy, Debug)]
+pub struct NpcSkills {
+    /// Skill values, names in ```SKILL_NAMES```.
+    pub values: [u8; 18],
+    pub offsets: [u8; 18],
+    pub health: u16,
+    pub magicka: u16,
+    pub stamina: u16,
+    pub unused: u16,
+    pub far_away_model_distance: f32,
+    pub geared_up_weapons: u8,
+    pub unused2: [u8; 3],
+}
+
+impl NpcSkills {
+    /// The skill values paired with their names.
+    pub fn named_values(&self) -> impl Iterator<Item = (&'static str, u8)> + '_ {
+        SKILL_NAMES.iter().copied().zip(self.values.iter().copied())
+    }
+}
+
+/// The data of a cell (CELL) change form.
+///
+/// It does not list the references of the cell: the references moved into or placed in a cell have
+/// change forms of their own, this only contains the changes of the cell itself.
+#[derive(Clone, Debug)]
+pub struct CellData {
+    /// CHANGE_FORM_FLAGS
+    pub form_flags: Option<ChangeFormFlags>,
+    /// CHANGE_CELL_FLAGS, the DATA subrecord of the CELL record.
+    pub cell_flags: Option<u16>,
+    /// CHANGE_CELL_FULLNAME
+    pub full_name: Option<String>,
+    /// CHANGE_CELL_OWNERSHIP, the owning faction or NPC.
+    pub ownership: Option<FormIdType>,
+    /// The sub-records following the ownership, not parsed as their layout is not documented:
+    /// CHANGE_CELL_EXTERIOR_SHORT, CHANGE_CELL_EXTERIOR_CHAR, CHANGE_CELL_DETACHTIME and CHANGE_CELL_SEENDATA.
+    pub remainder: Vec<u8>,
+}
+
+/// Reads the sub-records of a change form in their stored order, each only if its change flag is set.
+///
+/// The parsers of ```read_change_form_data()``` are built on it. It can also decode form types this
+/// library doesn't parse yet, the caller calls the methods in the order of the sub-records of the type,
+/// passing the bit of their change flag. Sub-records without a method can be read with ```read_if()```.
+pub struct ChangeFormReader<'a> {
+    r: SaveFileReader<'a>,
+    flags: ChangeFlags,
+}
+
+impl<'a> ChangeFormReader<'a> {
+    /// Reads the (decompressed) data of ```change_form``` according to its change flags.
+    pub fn new(change_form: &'a ChangeForm) -> Self {
+        ChangeFormReader {
+            r: SaveFileReader::from_slice(&change_form.data),
+            flags: ChangeFlags(change_form.change_flags),
+        }
+    }
+
+    pub fn flags(&self) -> ChangeFlags {
+        self.flags
+    }
+
+    /// Reads a sub-record with ```read``` if ```bit``` is set in the change flags.
+    pub fn read_if<T>(&mut self, bit: u8, read: fn(&mut SaveFileReader) -> Result<T, ReaderError>) -> Result<Option<T>, ReaderError> {
+        match self.flags.is_set(bit) {
+            true => Ok(Some(read(&mut self.r)?)),
+            false => Ok(None),
+        }
+    }
+
+    /// CHANGE_FORM_FLAGS, the first sub-record of all form types.
+    pub fn read_form_flags(&mut self) -> Result<Option<ChangeFormFlags>, ReaderError> {
+        self.read_if(0, read_change_form_flags)
+    }
+
+    /// The ACBS subrecord of an actor base, e.g. CHANGE_ACTOR_BASE_DATA.
+    pub fn read_actor_base_data(&mut self, bit: u8) -> Result<Option<ActorBaseData>, ReaderError> {
+        self.read_if(bit, read_actor_base_data)
+    }
+
+    /// A vsval prefixed list of factions and ranks, e.g. CHANGE_ACTOR_BASE_FACTIONS.
+    pub fn read_factions(&mut self, bit: u8) -> Result<Option<Vec<FactionRank>>, ReaderError> {
+        self.read_if(bit, |r| {
+            let count = read_vsval_to_u32(r)?;
+            read_vec(r, count)
+        })
+    }
+
+    /// e.g. CHANGE_ACTOR_BASE_SPELLLIST.
+    pub fn read_spell_list(&mut self, bit: u8) -> Result<Option<SpellList>, ReaderError> {
+        self.read_if(bit, read_spell_list)
+    }
+
+    /// A name, e.g. CHANGE_ACTOR_BASE_FULLNAME or CHANGE_CELL_FULLNAME.
+    pub fn read_full_name(&mut self, bit: u8) -> Result<Option<String>, ReaderError> {
+        self.read_if(bit, |r| Ok(r.read_w_string()?.content))
+    }
+
+    /// A single RefID, e.g. CHANGE_CELL_OWNERSHIP.
+    pub fn read_ref_id(&mut self, bit: u8) -> Result<Option<FormIdType>, ReaderError> {
+        self.read_if(bit, read_ref_id)
+    }
+
+    /// The DNAM subrecord of an actor base, CHANGE_NPC_SKILLS.
+    pub fn read_npc_skills(&mut self, bit: u8) -> Result<Option<NpcSkills>, ReaderError> {
+        self.read_if(bit, read_npc_skills)
+    }
+
+    /// A sub-record of a fixed size that is not parsed further, e.g. CHANGE_ACTOR_BASE_AIDATA.
+    pub fn read_bytes(&mut self, bit: u8, len: usize) -> Result<Option<Vec<u8>>, ReaderError> {
+        match self.flags.is_set(bit) {
+            true => Ok(Some(self.r.read_bytes_to_vec(len)?)),
+            false => Ok(None),
+        }
+    }
+
+    /// The bytes following the sub-records read so far.
+    pub fn remaining(&self) -> &[u8] {
+        self.r.remaining()
+    }
+}
+
+pub(crate) fn read_cell_data(r: &mut ChangeFormReader) -> Result<CellData, ReaderError> {
+    Ok(CellData {
+        form_flags: r.read_form_flags()?,
+        cell_flags: r.read_if(1, |r| r.read_u16())?,
+        full_name: r.read_full_name(2)?,
+        ownership: r.read_ref_id(3)?,
+        remainder: r.remaining().to_vec(),
+    })
+}
+
+pub(crate) fn read_npc_data(r: &mut ChangeFormReader) -> Result<NpcData, ReaderError> {
+    let form_flags = r.read_form_flags()?;
+    let base_data = r.read_actor_base_data(1)?;
+    let factions = r.read_factions(6)?;
+    let spell_list = r.read_spell_list(4)?;
+    let ai_data = r.read_bytes(3, 20)?;
+    let full_name = r.read_full_name(5)?;
+    let skills = r.read_npc_skills(9)?;
+    Ok(NpcData {
+        form_flags,
+        base_data,
+        factions,
+        spell_list,
+        ai_data,
+        full_name,
+        skills,
+        remainder: r.remaining().to_vec(),
+    })
+}
+
+fn read_change_form_flags(r: &mut SaveFileReader) -> Result<ChangeFormFlags, ReaderError> {
+    Ok(ChangeFormFlags {
+        flags: r.read_u32()?,
+        unknown: r.read_u16()?,
+    })
+}
+
+impl Readable for ChangeFormFlags {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        read_change_form_flags(r)
+    }
+}
+
+fn read_actor_base_data(r: &mut SaveFileReader) -> Result<ActorBaseData, ReaderError> {
+    Ok(ActorBaseData {
+        flags: r.read_u32()?,
+        magicka_offset: r.read_u16()? as i16,
+        stamina_offset: r.read_u16()? as i16,
+        level: r.read_u16()?,
+        calc_min_level: r.read_u16()?,
+        calc_max_level: r.read_u16()?,
+        speed_multiplier: r.read_u16()?,
+        disposition_base: r.read_u16()? as i16,
+        template_flags: r.read_u16()?,
+        health_offset: r.read_u16()? as i16,
+        bleedout_override: r.read_u16()?,
+    })
+}
+
+impl Readable for ActorBaseData {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        read_actor_base_data(r)
+    }
+}
+
+fn read_spell_list(r: &mut SaveFileReader) -> Result<SpellList, ReaderError> {
+    let count = read_vsval_to_u32(r)?;
+    let spells = read_ref_ids_into_vec(r, count)?;
+    let count = read_vsval_to_u32(r)?;
+    let leveled_spells = read_ref_ids_into_vec(r, count)?;
+    let count = read_vsval_to_u32(r)?;
+    let shouts = read_ref_ids_into_vec(r, count)?;
+    Ok(SpellList {
+        spells,
+        leveled_spells,
+        shouts,
+    })
+}
+
+impl Readable for SpellList {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        read_spell_list(r)
+    }
+}
+
+fn read_npc_skills(r: &mut SaveFileReader) -> Result<NpcSkills, ReaderError> {
+    let values = read_array(r)?;
+    let offsets = read_array(r)?;
+    Ok(NpcSkills {
+        values,
+        offsets,
+        health: r.read_u16()?,
+        magicka: r.read_u16()?,
+        stamina: r.read_u16()?,
+        unused: r.read_u16()?,
+        far_away_model_distance: r.read_f32()?,
+        geared_up_weapons: r.read_u8()?,
+        unused2: r.read_bytes(3)?.try_into().unwrap(),
+    })
+}
+
+impl Readable for NpcSkills {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        read_npc_skills(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{read_change_form_data, write_ref_id, ChangeFormData, ChangeFormError, ChangeFormLength, CountEncoding, SaveFileWriter, MAX_SUPPORTED_CHANGE_FORM_VERSION};
+
+    /// The data type of an uncompressed NPC_ change form with 8 bit lengths.
+    const NPC_DATA_TYPE: u8 = 9;
+    /// The data type of an uncompressed CELL change form with 8 bit lengths.
+    const CELL_DATA_TYPE: u8 = 6;
+
+    const FORM_FLAGS: u32 = 1 << 0;
+    const BASE_DATA: u32 = 1 << 1;
+    const AI_DATA: u32 = 1 << 3;
+    const SPELL_LIST: u32 = 1 << 4;
+    const FULL_NAME: u32 = 1 << 5;
+    const FACTIONS: u32 = 1 << 6;
+    const SKILLS: u32 = 1 << 9;
+    const CELL_FLAGS: u32 = 1 << 1;
+    const CELL_FULL_NAME: u32 = 1 << 2;
+    const CELL_OWNERSHIP: u32 = 1 << 3;
+    const CELL_DETACH_TIME: u32 = 1 << 30;
+
+    const FOLLOWER_FACTION: FormIdType = FormIdType::Default(0x5C84E);
+    const MARRIAGE_FACTION: FormIdType = FormIdType::Default(0x19809);
+    const SPELL: FormIdType = FormIdType::Default(0x12FCD);
+    const SHOUT: FormIdType = FormIdType::Default(0x13E09);
+
+    fn change_form(form_type: u8, change_flags: u32, data: Vec<u8>) -> ChangeForm {
+        ChangeForm {
+            form_id: FormIdType::Default(0x13BBD),
+            change_flags,
+            data_type: form_type,
+            version: MAX_SUPPORTED_CHANGE_FORM_VERSION,
+            length: ChangeFormLength::U8 { length1: data.len() as u8, length2: 0 },
+            data,
+        }
+    }
+
+    fn read_npc(change_flags: u32, data: Vec<u8>) -> Result<NpcData, ChangeFormError> {
+        match read_change_form_data(&change_form(NPC_DATA_TYPE, change_flags, data))? {
+            ChangeFormData::Npc(npc) => Ok(*npc),
+            data => panic!("expected NPC_ data, got {:?}", data),
+        }
+    }
+
+    fn read_cell(change_flags: u32, data: Vec<u8>) -> Result<CellData, ChangeFormError> {
+        match read_change_form_data(&change_form(CELL_DATA_TYPE, change_flags, data))? {
+            ChangeFormData::Cell(cell) => Ok(cell),
+            data => panic!("expected CELL data, got {:?}", data),
+        }
+    }
+
+    fn write_base_data(w: &mut SaveFileWriter, flags: u32) {
+        w.write_u32(flags);
+        // magicka and stamina offset, level, calc min and max level, speed multiplier,
+        // disposition base, template flags, health offset and bleedout override
+        for value in [5, 10, 12, 1, 81, 100, 35, 0, 0xFFF6, 0] {
+            w.write_u16(value);
+        }
+    }
+
+    fn write_factions(w: &mut SaveFileWriter, factions: &[(FormIdType, i8)]) {
+        w.write_count(factions.len(), CountEncoding::VSVal).unwrap();
+        for (faction, rank) in factions {
+            write_ref_id(w, *faction).unwrap();
+            w.write_u8(*rank as u8);
+        }
+    }
+
+    fn write_spell_list(w: &mut SaveFileWriter) {
+        for list in [&[SPELL][..], &[], &[SHOUT]] {
+            w.write_count(list.len(), CountEncoding::VSVal).unwrap();
+            for form_id in list {
+                write_ref_id(w, *form_id).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn sub_records_are_read_in_their_stored_order() {
+        let mut w = SaveFileWriter::new();
+        w.write_u32(0x20);
+        w.write_u16(7);
+        write_base_data(&mut w, ACTOR_BASE_FLAG_ESSENTIAL);
+        // the factions are stored before the spell list and the AI data, although their flag is higher
+        write_factions(&mut w, &[(FOLLOWER_FACTION, 0)]);
+        write_spell_list(&mut w);
+        w.write_bytes(&[0xAA; 20]);
+        w.write_w_string("Lydia").unwrap();
+        w.write_bytes(&[1, 2, 3]);
+        let npc = read_npc(FORM_FLAGS | BASE_DATA | FACTIONS | SPELL_LIST | AI_DATA | FULL_NAME, w.get_buffer()).unwrap();
+
+        let form_flags = npc.form_flags.unwrap();
+        assert_eq!((form_flags.flags, form_flags.unknown), (0x20, 7));
+        let base_data = npc.base_data.unwrap();
+        assert_eq!((base_data.magicka_offset, base_data.level, base_data.calc_max_level), (5, 12, 81));
+        assert_eq!((base_data.health_offset, base_data.bleedout_override), (-10, 0));
+        assert_eq!(npc.faction_rank(FOLLOWER_FACTION), Some(0));
+        let spell_list = npc.spell_list.unwrap();
+        assert_eq!((spell_list.spells, spell_list.leveled_spells, spell_list.shouts), (vec![SPELL], vec![], vec![SHOUT]));
+        assert_eq!(npc.ai_data, Some(vec![0xAA; 20]));
+        assert_eq!(npc.full_name.as_deref(), Some("Lydia"));
+        assert!(npc.skills.is_none());
+        assert_eq!(npc.remainder, [1, 2, 3]);
+    }
+
+    #[test]
+    fn sub_records_without_their_flag_are_skipped() {
+        let mut w = SaveFileWriter::new();
+        write_factions(&mut w, &[(FOLLOWER_FACTION, 1), (MARRIAGE_FACTION, -1)]);
+        w.write_w_string("Lydia").unwrap();
+        let npc = read_npc(FACTIONS | FULL_NAME, w.get_buffer()).unwrap();
+
+        assert!(npc.form_flags.is_none() && npc.base_data.is_none() && npc.spell_list.is_none() && npc.ai_data.is_none());
+        assert_eq!(npc.factions.as_ref().unwrap().len(), 2);
+        assert_eq!(npc.faction_rank(FOLLOWER_FACTION), Some(1));
+        assert_eq!(npc.faction_rank(MARRIAGE_FACTION), Some(-1));
+        assert_eq!(npc.faction_rank(SPELL), None);
+        assert_eq!(npc.full_name.as_deref(), Some("Lydia"));
+        assert!(npc.remainder.is_empty());
+
+        let npc = read_npc(0, vec![1, 2, 3]).unwrap();
+        assert!(npc.factions.is_none() && npc.full_name.is_none());
+        assert_eq!(npc.faction_rank(FOLLOWER_FACTION), None);
+        assert_eq!(npc.remainder, [1, 2, 3]);
+    }
+
+    fn write_skills(w: &mut SaveFileWriter) {
+        let values: Vec<u8> = (15..33).collect();
+        w.write_bytes(&values);
+        w.write_bytes(&[0; 17]);
+        w.write_u8(5);
+        for value in [250, 120, 180, 0] {
+            w.write_u16(value);
+        }
+        w.write_f32(0.5);
+        w.write_u8(1);
+        w.write_bytes(&[0; 3]);
+    }
+
+    #[test]
+    fn skills_follow_the_full_name() {
+        let mut w = SaveFileWriter::new();
+        w.write_w_string("Prisoner").unwrap();
+        write_skills(&mut w);
+        w.write_bytes(&[9, 9]);
+        let npc = read_npc(FULL_NAME | SKILLS, w.get_buffer()).unwrap();
+
+        let skills = npc.skills.unwrap();
+        assert_eq!(skills.values[0], 15);
+        assert_eq!(skills.values[17], 32);
+        assert_eq!(skills.offsets, {
+            let mut offsets = [0; 18];
+            offsets[17] = 5;
+            offsets
+        });
+        assert_eq!((skills.health, skills.magicka, skills.stamina, skills.unused), (250, 120, 180, 0));
+        assert_eq!((skills.far_away_model_distance, skills.geared_up_weapons), (0.5, 1));
+        assert_eq!(skills.named_values().next(), Some(("OneHanded", 15)));
+        assert_eq!(skills.named_values().find(|(name, _)| *name == "Sneak"), Some(("Sneak", 24)));
+        assert_eq!(skills.named_values().last(), Some(("Enchanting", 32)));
+        assert_eq!(npc.remainder, [9, 9]);
+    }
+
+    #[test]
+    fn skills_are_only_read_with_their_flag() {
+        let mut w = SaveFileWriter::new();
+        write_skills(&mut w);
+        let data = w.get_buffer();
+        assert_eq!(data.len(), 52);
+
+        let npc = read_npc(0, data.clone()).unwrap();
+        assert!(npc.skills.is_none());
+        assert_eq!(npc.remainder, data);
+
+        assert!(read_npc(SKILLS, data).unwrap().remainder.is_empty());
+        assert!(matches!(read_npc(SKILLS, vec![0; 51]), Err(ChangeFormError::Reader(ReaderError::UnexpectedEnd { .. }))));
+    }
+
+    #[test]
+    fn essential_and_protected_flags() {
+        for (flags, essential, protected) in [
+            (0, false, false),
+            (ACTOR_BASE_FLAG_ESSENTIAL, true, false),
+            (ACTOR_BASE_FLAG_PROTECTED, false, true),
+            (ACTOR_BASE_FLAG_ESSENTIAL | ACTOR_BASE_FLAG_PROTECTED | 1, true, true),
+        ] {
+            let mut w = SaveFileWriter::new();
+            write_base_data(&mut w, flags);
+            let base_data = read_npc(BASE_DATA, w.get_buffer()).unwrap().base_data.unwrap();
+            assert_eq!((base_data.is_essential(), base_data.is_protected()), (essential, protected), "flags {:#x}", flags);
+        }
+    }
+
+    #[test]
+    fn truncated_sub_record_is_an_error() {
+        let mut w = SaveFileWriter::new();
+        write_base_data(&mut w, 0);
+        let mut data = w.get_buffer();
+        data.pop();
+        assert!(matches!(read_npc(BASE_DATA, data), Err(ChangeFormError::Reader(ReaderError::UnexpectedEnd { .. }))));
+
+        // the count promises two factions, the data holds one
+        let mut w = SaveFileWriter::new();
+        w.write_count(2, CountEncoding::VSVal).unwrap();
+        write_ref_id(&mut w, FOLLOWER_FACTION).unwrap();
+        w.write_u8(0);
+        assert!(matches!(read_npc(FACTIONS, w.get_buffer()), Err(ChangeFormError::Reader(ReaderError::UnexpectedEnd { .. }))));
+
+        // a set flag without data
+        assert!(matches!(read_npc(FULL_NAME, Vec::new()), Err(ChangeFormError::Reader(ReaderError::UnexpectedEnd { .. }))));
+    }
+
+    #[test]
+    fn change_form_reader_only_consumes_set_flags() {
+        let form = change_form(NPC_DATA_TYPE, BASE_DATA | FACTIONS, {
+            let mut w = SaveFileWriter::new();
+            write_base_data(&mut w, 0);
+            write_factions(&mut w, &[(FOLLOWER_FACTION, 2)]);
+            w.get_buffer()
+        });
+        let mut r = ChangeFormReader::new(&form);
+        assert!(r.flags().is_set(1) && !r.flags().is_set(0));
+        assert!(r.read_form_flags().unwrap().is_none());
+        assert_eq!(r.remaining().len(), form.data.len());
+        assert!(r.read_actor_base_data(1).unwrap().is_some());
+        assert!(r.read_bytes(3, 20).unwrap().is_none());
+        assert_eq!(r.read_factions(6).unwrap().unwrap()[0].rank, 2);
+        assert!(r.remaining().is_empty());
+    }
+
+    #[test]
+    fn cell_sub_records_are_read_in_their_stored_order() {
+        let mut w = SaveFileWriter::new();
+        w.write_u32(0x20);
+        w.write_u16(0);
+        w.write_u16(0x0201);
+        w.write_w_string("Breezehome").unwrap();
+        write_ref_id(&mut w, FOLLOWER_FACTION).unwrap();
+        w.write_u32(1234);
+        let cell = read_cell(FORM_FLAGS | CELL_FLAGS | CELL_FULL_NAME | CELL_OWNERSHIP | CELL_DETACH_TIME, w.get_buffer()).unwrap();
+
+        assert_eq!(cell.form_flags.unwrap().flags, 0x20);
+        assert_eq!(cell.cell_flags, Some(0x0201));
+        assert_eq!(cell.full_name.as_deref(), Some("Breezehome"));
+        assert_eq!(cell.ownership, Some(FOLLOWER_FACTION));
+        // the detach time is not decoded
+        assert_eq!(cell.remainder, 1234u32.to_le_bytes());
+    }
+
+    #[test]
+    fn cell_sub_records_without_their_flag_are_skipped() {
+        let mut w = SaveFileWriter::new();
+        write_ref_id(&mut w, MARRIAGE_FACTION).unwrap();
+        let cell = read_cell(CELL_OWNERSHIP, w.get_buffer()).unwrap();
+        assert!(cell.form_flags.is_none() && cell.cell_flags.is_none() && cell.full_name.is_none());
+        assert_eq!(cell.ownership, Some(MARRIAGE_FACTION));
+        assert!(cell.remainder.is_empty());
+
+        let cell = read_cell(0, vec![1, 2]).unwrap();
+        assert!(cell.ownership.is_none());
+        assert_eq!(cell.remainder, [1, 2]);
+
+        // the name is cut off
+        let mut w = SaveFileWriter::new();
+        w.write_w_string("Breezehome").unwrap();
+        let mut data = w.get_buffer();
+        data.truncate(5);
+        assert!(matches!(read_cell(CELL_FULL_NAME, data), Err(ChangeFormError::Reader(ReaderError::UnexpectedEnd { .. }))));
+    }
+}
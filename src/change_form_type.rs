@@ -0,0 +1,378 @@
+//! The record types of change forms and the meaning of their change flags.
+
+use std::fmt;
+
+/// The record type of a change form, stored in the lower 6 bits of ```ChangeForm.data_type```.
+/// The variants are in the order of their stored values, ```REFR``` is 0 and ```ENCH``` is 48, as listed on UESP.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChangeFormType {
+    /// A placed object.
+    REFR,
+    /// A placed actor.
+    ACHR,
+    /// A placed missile projectile.
+    PMIS,
+    /// A placed grenade projectile.
+    PGRE,
+    /// A placed beam projectile.
+    PBEA,
+    /// A placed flame projectile.
+    PFLA,
+    /// A cell.
+    CELL,
+    /// A dialogue response.
+    INFO,
+    /// A quest.
+    QUST,
+    /// An actor base, the record signature is "NPC_".
+    NPC,
+    /// An activator.
+    ACTI,
+    /// A talking activator.
+    TACT,
+    /// An armor.
+    ARMO,
+    /// A book or scroll.
+    BOOK,
+    /// A container base object.
+    CONT,
+    /// A door base object.
+    DOOR,
+    /// An ingredient.
+    INGR,
+    /// A light.
+    LIGH,
+    /// A miscellaneous item.
+    MISC,
+    /// An alchemical apparatus.
+    APPA,
+    /// A static object.
+    STAT,
+    /// A moveable static object.
+    MSTT,
+    /// A furniture.
+    FURN,
+    /// A weapon.
+    WEAP,
+    /// An ammunition.
+    AMMO,
+    /// A key.
+    KEYM,
+    /// A potion, poison or food.
+    ALCH,
+    /// An idle marker.
+    IDLM,
+    /// A note.
+    NOTE,
+    /// An encounter zone.
+    ECZN,
+    /// A class.
+    CLAS,
+    /// A faction.
+    FACT,
+    /// An AI package.
+    PACK,
+    /// A navigation mesh.
+    NAVM,
+    /// A word of power.
+    WOOP,
+    /// A magic effect.
+    MGEF,
+    /// A story manager quest node.
+    SMQN,
+    /// A scene.
+    SCEN,
+    /// A location.
+    LCTN,
+    /// A relationship.
+    RELA,
+    /// A placed hazard.
+    PHZD,
+    /// A placed barrier projectile.
+    PBAR,
+    /// A placed cone projectile, e.g. of a shout.
+    PCON,
+    /// A form list.
+    FLST,
+    /// A leveled actor list.
+    LVLN,
+    /// A leveled item list.
+    LVLI,
+    /// A leveled spell list.
+    LVSP,
+    /// A placed arrow projectile.
+    PARW,
+    /// An enchantment.
+    ENCH,
+    /// A value above 48, not used by the game.
+    Unknown(u8),
+}
+
+impl ChangeFormType {
+    /// Reads the type from the ```data_type``` of a change form, ignoring the length bits.
+    pub fn from_data_type(data_type: u8) -> ChangeFormType {
+        match data_type & 0b00111111 {
+            0 => ChangeFormType::REFR,
+            1 => ChangeFormType::ACHR,
+            2 => ChangeFormType::PMIS,
+            3 => ChangeFormType::PGRE,
+            4 => ChangeFormType::PBEA,
+            5 => ChangeFormType::PFLA,
+            6 => ChangeFormType::CELL,
+            7 => ChangeFormType::INFO,
+            8 => ChangeFormType::QUST,
+            9 => ChangeFormType::NPC,
+            10 => ChangeFormType::ACTI,
+            11 => ChangeFormType::TACT,
+            12 => ChangeFormType::ARMO,
+            13 => ChangeFormType::BOOK,
+            14 => ChangeFormType::CONT,
+            15 => ChangeFormType::DOOR,
+            16 => ChangeFormType::INGR,
+            17 => ChangeFormType::LIGH,
+            18 => ChangeFormType::MISC,
+            19 => ChangeFormType::APPA,
+            20 => ChangeFormType::STAT,
+            21 => ChangeFormType::MSTT,
+            22 => ChangeFormType::FURN,
+            23 => ChangeFormType::WEAP,
+            24 => ChangeFormType::AMMO,
+            25 => ChangeFormType::KEYM,
+            26 => ChangeFormType::ALCH,
+            27 => ChangeFormType::IDLM,
+            28 => ChangeFormType::NOTE,
+            29 => ChangeFormType::ECZN,
+            30 => ChangeFormType::CLAS,
+            31 => ChangeFormType::FACT,
+            32 => ChangeFormType::PACK,
+            33 => ChangeFormType::NAVM,
+            34 => ChangeFormType::WOOP,
+            35 => ChangeFormType::MGEF,
+            36 => ChangeFormType::SMQN,
+            37 => ChangeFormType::SCEN,
+            38 => ChangeFormType::LCTN,
+            39 => ChangeFormType::RELA,
+            40 => ChangeFormType::PHZD,
+            41 => ChangeFormType::PBAR,
+            42 => ChangeFormType::PCON,
+            43 => ChangeFormType::FLST,
+            44 => ChangeFormType::LVLN,
+            45 => ChangeFormType::LVLI,
+            46 => ChangeFormType::LVSP,
+            47 => ChangeFormType::PARW,
+            48 => ChangeFormType::ENCH,
+            x => ChangeFormType::Unknown(x),
+        }
+    }
+
+    /// The 4 character record signature, e.g. "NPC_". "????" for unknown types.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChangeFormType::REFR => "REFR",
+            ChangeFormType::ACHR => "ACHR",
+            ChangeFormType::PMIS => "PMIS",
+            ChangeFormType::PGRE => "PGRE",
+            ChangeFormType::PBEA => "PBEA",
+            ChangeFormType::PFLA => "PFLA",
+            ChangeFormType::CELL => "CELL",
+            ChangeFormType::INFO => "INFO",
+            ChangeFormType::QUST => "QUST",
+            ChangeFormType::NPC => "NPC_",
+            ChangeFormType::ACTI => "ACTI",
+            ChangeFormType::TACT => "TACT",
+            ChangeFormType::ARMO => "ARMO",
+            ChangeFormType::BOOK => "BOOK",
+            ChangeFormType::CONT => "CONT",
+            ChangeFormType::DOOR => "DOOR",
+            ChangeFormType::INGR => "INGR",
+            ChangeFormType::LIGH => "LIGH",
+            ChangeFormType::MISC => "MISC",
+            ChangeFormType::APPA => "APPA",
+            ChangeFormType::STAT => "STAT",
+            ChangeFormType::MSTT => "MSTT",
+            ChangeFormType::FURN => "FURN",
+            ChangeFormType::WEAP => "WEAP",
+            ChangeFormType::AMMO => "AMMO",
+            ChangeFormType::KEYM => "KEYM",
+            ChangeFormType::ALCH => "ALCH",
+            ChangeFormType::IDLM => "IDLM",
+            ChangeFormType::NOTE => "NOTE",
+            ChangeFormType::ECZN => "ECZN",
+            ChangeFormType::CLAS => "CLAS",
+            ChangeFormType::FACT => "FACT",
+            ChangeFormType::PACK => "PACK",
+            ChangeFormType::NAVM => "NAVM",
+            ChangeFormType::WOOP => "WOOP",
+            ChangeFormType::MGEF => "MGEF",
+            ChangeFormType::SMQN => "SMQN",
+            ChangeFormType::SCEN => "SCEN",
+            ChangeFormType::LCTN => "LCTN",
+            ChangeFormType::RELA => "RELA",
+            ChangeFormType::PHZD => "PHZD",
+            ChangeFormType::PBAR => "PBAR",
+            ChangeFormType::PCON => "PCON",
+            ChangeFormType::FLST => "FLST",
+            ChangeFormType::LVLN => "LVLN",
+            ChangeFormType::LVLI => "LVLI",
+            ChangeFormType::LVSP => "LVSP",
+            ChangeFormType::PARW => "PARW",
+            ChangeFormType::ENCH => "ENCH",
+            ChangeFormType::Unknown(_) => "????",
+        }
+    }
+
+    /// True for the types that are placed references in a cell (as opposed to base objects).
+    pub fn is_reference(&self) -> bool {
+        matches!(self,
+            ChangeFormType::REFR | ChangeFormType::ACHR | ChangeFormType::PMIS | ChangeFormType::PGRE
+            | ChangeFormType::PBEA | ChangeFormType::PFLA | ChangeFormType::PHZD | ChangeFormType::PBAR
+            | ChangeFormType::PCON | ChangeFormType::PARW)
+    }
+}
+
+impl fmt::Display for ChangeFormType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The change flags of a change form, indicating which parts of the form were changed and are
+/// therefore present in its data. The meaning of a bit depends on the form type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChangeFlags(pub u32);
+
+/// A single set bit of the change flags, with its name if known for the form type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChangeFlag {
+    pub bit: u8,
+    pub name: Option<&'static str>,
+}
+
+impl ChangeFlags {
+    pub fn is_set(&self, bit: u8) -> bool {
+        bit < 32 && self.0 & (1 << bit) != 0
+    }
+
+    /// All set bits in ascending order, named according to ```form_type```.
+    pub fn set_flags(&self, form_type: ChangeFormType) -> Vec<ChangeFlag> {
+        (0..32)
+            .filter(|bit| self.is_set(*bit))
+            .map(|bit| ChangeFlag { bit, name: change_flag_name(form_type, bit) })
+            .collect()
+    }
+}
+
+/// Names of the change flags, as far as they are known.
+fn change_flag_name(form_type: ChangeFormType, bit: u8) -> Option<&'static str> {
+    if bit == 0 {
+        return Some("CHANGE_FORM_FLAGS");
+    }
+    if form_type == ChangeFormType::ACHR {
+        let name = match bit {
+            10 => Some("CHANGE_ACTOR_LIFESTATE"),
+            11 => Some("CHANGE_ACTOR_EXTRA_PACKAGE_DATA"),
+            12 => Some("CHANGE_ACTOR_EXTRA_MERCHANT_CONTAINER"),
+            17 => Some("CHANGE_ACTOR_EXTRA_DISMEMBERED_LIMBS"),
+            18 => Some("CHANGE_ACTOR_LEVELED_ACTOR"),
+            19 => Some("CHANGE_ACTOR_DISPOSITION_MODIFIERS"),
+            20 => Some("CHANGE_ACTOR_TEMP_MODIFIERS"),
+            21 => Some("CHANGE_ACTOR_DAMAGE_MODIFIERS"),
+            22 => Some("CHANGE_ACTOR_OVERRIDE_MODIFIERS"),
+            23 => Some("CHANGE_ACTOR_PERMANENT_MODIFIERS"),
+            _ => None,
+        };
+        if name.is_some() {
+            return name;
+        }
+    }
+    if form_type.is_reference() {
+        return match bit {
+            1 => Some("CHANGE_REFR_MOVE"),
+            2 => Some("CHANGE_REFR_HAVOK_MOVE"),
+            3 => Some("CHANGE_REFR_CELL_CHANGED"),
+            4 => Some("CHANGE_REFR_SCALE"),
+            5 => Some("CHANGE_REFR_INVENTORY"),
+            6 => Some("CHANGE_REFR_EXTRA_OWNERSHIP"),
+            7 => Some("CHANGE_REFR_BASEOBJECT"),
+            10 => Some("CHANGE_OBJECT_EXTRA_ITEM_DATA"),
+            11 => Some("CHANGE_OBJECT_EXTRA_AMMO"),
+            12 => Some("CHANGE_OBJECT_EXTRA_LOCK"),
+            21 => Some("CHANGE_OBJECT_EMPTY"),
+            22 => Some("CHANGE_OBJECT_OPEN_DEFAULT_STATE"),
+            23 => Some("CHANGE_OBJECT_OPEN_STATE"),
+            25 => Some("CHANGE_REFR_PROMOTED"),
+            26 => Some("CHANGE_REFR_EXTRA_ACTIVATING_CHILDREN"),
+            27 => Some("CHANGE_REFR_LEVELED_INVENTORY"),
+            28 => Some("CHANGE_REFR_ANIMATION"),
+            29 => Some("CHANGE_REFR_EXTRA_ENCOUNTER_ZONE"),
+            30 => Some("CHANGE_REFR_EXTRA_CREATED_ONLY"),
+            31 => Some("CHANGE_REFR_EXTRA_GAME_ONLY"),
+            _ => None,
+        };
+    }
+    match form_type {
+        ChangeFormType::NPC => match bit {
+            1 => Some("CHANGE_ACTOR_BASE_DATA"),
+            2 => Some("CHANGE_ACTOR_BASE_ATTRIBUTES"),
+            3 => Some("CHANGE_ACTOR_BASE_AIDATA"),
+            4 => Some("CHANGE_ACTOR_BASE_SPELLLIST"),
+            5 => Some("CHANGE_ACTOR_BASE_FULLNAME"),
+            6 => Some("CHANGE_ACTOR_BASE_FACTIONS"),
+            9 => Some("CHANGE_NPC_SKILLS"),
+            10 => Some("CHANGE_NPC_CLASS"),
+            11 => Some("CHANGE_NPC_FACE"),
+            12 => Some("CHANGE_NPC_DEFAULT_OUTFIT"),
+            13 => Some("CHANGE_NPC_SLEEP_OUTFIT"),
+            24 => Some("CHANGE_NPC_GENDER"),
+            25 => Some("CHANGE_NPC_RACE"),
+            _ => None,
+        },
+        ChangeFormType::CELL => match bit {
+            1 => Some("CHANGE_CELL_FLAGS"),
+            2 => Some("CHANGE_CELL_FULLNAME"),
+            3 => Some("CHANGE_CELL_OWNERSHIP"),
+            28 => Some("CHANGE_CELL_EXTERIOR_SHORT"),
+            29 => Some("CHANGE_CELL_EXTERIOR_CHAR"),
+            30 => Some("CHANGE_CELL_DETACHTIME"),
+            31 => Some("CHANGE_CELL_SEENDATA"),
+            _ => None,
+        },
+        ChangeFormType::QUST => match bit {
+            1 => Some("CHANGE_QUEST_FLAGS"),
+            2 => Some("CHANGE_QUEST_SCRIPT_DELAY"),
+            26 => Some("CHANGE_QUEST_ALREADY_RUN"),
+            27 => Some("CHANGE_QUEST_INSTANCES"),
+            28 => Some("CHANGE_QUEST_RUNDATA"),
+            29 => Some("CHANGE_QUEST_OBJECTIVES"),
+            30 => Some("CHANGE_QUEST_SCRIPT"),
+            31 => Some("CHANGE_QUEST_STAGES"),
+            _ => None,
+        },
+        ChangeFormType::FACT => match bit {
+            1 => Some("CHANGE_FACTION_FLAGS"),
+            2 => Some("CHANGE_FACTION_REACTIONS"),
+            31 => Some("CHANGE_FACTION_CRIME_COUNTS"),
+            _ => None,
+        },
+        ChangeFormType::BOOK => match bit {
+            1 => Some("CHANGE_BASE_OBJECT_VALUE"),
+            2 => Some("CHANGE_BASE_OBJECT_FULLNAME"),
+            5 => Some("CHANGE_BOOK_TEACHES"),
+            6 => Some("CHANGE_BOOK_READ"),
+            _ => None,
+        },
+        ChangeFormType::FLST => match bit {
+            31 => Some("CHANGE_FORM_LIST_ADDED_FORM"),
+            _ => None,
+        },
+        ChangeFormType::LVLN | ChangeFormType::LVLI | ChangeFormType::LVSP => match bit {
+            31 => Some("CHANGE_LEVELED_LIST_ADDED_OBJECT"),
+            _ => None,
+        },
+        _ => match bit {
+            1 => Some("CHANGE_BASE_OBJECT_VALUE"),
+            2 => Some("CHANGE_BASE_OBJECT_FULLNAME"),
+            _ => None,
+        },
+    }
+}
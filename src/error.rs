@@ -0,0 +1,253 @@
+use crate::change_form::ChangeFormError;
+use crate::fundamental_types::{FormIdType, VSVal};
+use crate::reader::ReaderError;
+use crate::writer::CountEncoding;
+use std::fmt;
+
+/// Errors that can occur while parsing a save file.
+#[derive(Clone, Debug)]
+pub enum SaveParseError {
+    /// The file does not start with "TESV_SAVEGAME", it is either corrupted or not a Skyrim save.
+    InvalidMagic,
+    /// The screenshot dimensions in the header result in a size that overflows or exceeds the file.
+    InvalidScreenshotDimensions { width: u32, height: u32 },
+    /// The header specifies a compression type that is unknown or not supported.
+    UnsupportedCompression(u16),
+    /// The body could not be decompressed, contains the message of the decompressor.
+    BodyDecompression(String),
+    /// The uncompressed body is larger than ```ParseOptions::max_body_size```.
+    BodyTooLarge { requested: usize, limit: usize },
+    /// The plugins don't fit into the plugin info section of ```plugin_info_size``` bytes,
+    /// the plugin count is corrupted.
+    InvalidPluginCount { count: u32, plugin_info_size: u32 },
+    /// A section does not start at the offset the file location table specifies, an earlier
+    /// section was read with the wrong size.
+    SectionOffsetMismatch { section: &'static str, expected: u32, actual: u32 },
+    /// Reading went past the end of a buffer or encountered invalid data.
+    Reader(ReaderError),
+    /// A change form could not be read.
+    ChangeForm(ChangeFormError),
+}
+
+impl fmt::Display for SaveParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveParseError::InvalidMagic => f.write_str("File invalid or corrupted, could not read magic"),
+            SaveParseError::InvalidScreenshotDimensions { width, height } => {
+                write!(f, "Invalid screenshot dimensions {}x{}", width, height)
+            }
+            SaveParseError::UnsupportedCompression(compression_type) => {
+                write!(f, "Encountered unspecified/unsupported compression type {}", compression_type)
+            }
+            SaveParseError::BodyDecompression(message) => write!(f, "Could not decompress body: {}", message),
+            SaveParseError::BodyTooLarge { requested, limit } => {
+                write!(f, "Body of {} bytes exceeds the limit of {} bytes", requested, limit)
+            }
+            SaveParseError::InvalidPluginCount { count, plugin_info_size } => {
+                write!(f, "{} plugins don't fit into the plugin info of {} bytes", count, plugin_info_size)
+            }
+            SaveParseError::SectionOffsetMismatch { section, expected, actual } => {
+                write!(f, "The {} starts at offset {}, but should start at {}", section, actual, expected)
+            }
+            SaveParseError::Reader(e) => write!(f, "Could not read save file: {}", e),
+            SaveParseError::ChangeForm(e) => write!(f, "Could not read change form: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SaveParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SaveParseError::Reader(e) => Some(e),
+            SaveParseError::ChangeForm(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ReaderError> for SaveParseError {
+    fn from(e: ReaderError) -> Self {
+        SaveParseError::Reader(e)
+    }
+}
+
+impl From<ChangeFormError> for SaveParseError {
+    fn from(e: ChangeFormError) -> Self {
+        SaveParseError::ChangeForm(e)
+    }
+}
+
+/// Errors that can occur while writing a save file.
+#[derive(Clone, Debug)]
+pub enum SaveWriteError {
+    /// A string is longer than the 65535 bytes its u16 length prefix can express, contains the length.
+    StringTooLong(usize),
+    /// The value of a vsval does not fit into the width of its variant.
+    VSValOutOfRange(VSVal),
+    /// The screenshot data does not have the size its dimensions imply.
+    ScreenshotSizeMismatch { expected: usize, actual: usize },
+    /// An array is too long for the encoding of its count.
+    CountOutOfRange { count: usize, encoding: CountEncoding },
+    /// The value of the form id does not fit into the 22 bits of a RefID.
+    FormIdOutOfRange(FormIdType),
+    /// Writing this type of global data is not supported (yet), contains the type id.
+    UnsupportedGlobalData(u32),
+    /// A section was modified, but writing it back would not reproduce the unmodified original.
+    /// Contains the name of the section.
+    NotReproducible(&'static str),
+    /// The original bytes passed in could not be parsed.
+    InvalidOriginal(Box<SaveParseError>),
+    /// The data could not be compressed, contains the message of the compressor.
+    Compression(String),
+    /// The save was parsed with ```ParseOptions::max_change_forms``` and doesn't hold all of its change forms,
+    /// see ```ParseWarning::TruncatedChangeForms```.
+    TruncatedChangeForms { read: u32, count: u32 },
+}
+
+impl fmt::Display for SaveWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveWriteError::StringTooLong(length) => write!(f, "String of {} bytes is too long to be written", length),
+            SaveWriteError::VSValOutOfRange(value) => write!(f, "Value of {:?} does not fit its width", value),
+            SaveWriteError::ScreenshotSizeMismatch { expected, actual } => {
+                write!(f, "Screenshot has {} bytes, but its dimensions require {}", actual, expected)
+            }
+            SaveWriteError::CountOutOfRange { count, encoding } => {
+                write!(f, "Count {} does not fit into a {:?}", count, encoding)
+            }
+            SaveWriteError::FormIdOutOfRange(form_id) => write!(f, "Form id {:?} can't be written as RefID", form_id),
+            SaveWriteError::UnsupportedGlobalData(data_type) => {
+                write!(f, "Writing global data of type {} is not supported", data_type)
+            }
+            SaveWriteError::NotReproducible(section) => {
+                write!(f, "The {} can't be written back without changing data that wasn't modified", section)
+            }
+            SaveWriteError::InvalidOriginal(e) => write!(f, "Could not parse the original save: {}", e),
+            SaveWriteError::Compression(message) => write!(f, "Could not compress: {}", message),
+            SaveWriteError::TruncatedChangeForms { read, count } => {
+                write!(f, "Only {} of the {} change forms were read, the save can't be written", read, count)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SaveWriteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SaveWriteError::InvalidOriginal(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// A problem found while parsing that didn't stop the parse, but may make the result unreliable.
+/// Collected in ```SaveFile::warnings```.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseWarning {
+    /// The form version of the body is outside of ```KNOWN_FORM_VERSIONS```, the layout of the body
+    /// may have changed, e.g. by a newer patch of the game.
+    UnknownFormVersion(u8),
+    /// The data of a global data entry could not be parsed and was kept as
+    /// ```GlobalDataType::UnknownType```. Contains the type id and the error.
+    UnparsedGlobalData { data_type: u32, message: String },
+    /// A global data entry has a type this library doesn't know, it was kept as ```GlobalDataType::UnknownType```.
+    UnknownGlobalDataType(u32),
+    /// A section doesn't start at its offset from the file location table, see ```SaveParseError::SectionOffsetMismatch```.
+    SectionOffsetMismatch { section: &'static str, expected: u32, actual: u32 },
+    /// The body continues behind the last table, contains the number of bytes, see ```SaveFile::trailing_bytes```.
+    TrailingBytes(usize),
+    /// Only the first ```read``` of the ```count``` change forms were read, see ```ParseOptions::max_change_forms```.
+    TruncatedChangeForms { read: u32, count: u32 },
+    /// The vsval at ```index``` of its section has the invalid size indicator 3, it was read as 0.
+    InvalidVSVal { index: usize },
+    /// The string at ```index``` of its section is not valid UTF-8, it was replaced with a placeholder.
+    InvalidString { index: usize },
+    /// The string at ```index``` of its section declares ```length``` bytes, but was clamped to the ```clamped```
+    /// remaining bytes by ```StringMode::Lossy```.
+    ClampedString { index: usize, length: u16, clamped: u16 },
+    /// A field has a value that isn't known to be used by the game, see ```ReaderError::UnknownValue```.
+    UnknownValue { field: &'static str, value: u32 },
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseWarning::UnknownFormVersion(form_version) => {
+                write!(f, "The save uses form version {}, whose layout is not known", form_version)
+            }
+            ParseWarning::UnparsedGlobalData { data_type, message } => {
+                write!(f, "Could not parse global data of type {}, keeping it unparsed: {}", data_type, message)
+            }
+            ParseWarning::UnknownGlobalDataType(data_type) => write!(f, "Found unknown global data type {}", data_type),
+            ParseWarning::SectionOffsetMismatch { section, expected, actual } => {
+                write!(f, "The {} starts at offset {}, but the file location table says {}", section, actual, expected)
+            }
+            ParseWarning::TrailingBytes(len) => write!(f, "Found {} bytes behind the last table of the body", len),
+            ParseWarning::TruncatedChangeForms { read, count } => {
+                write!(f, "Read only {} of the {} change forms", read, count)
+            }
+            ParseWarning::InvalidVSVal { index } => write!(f, "Invalid vsval at index {}, read as 0", index),
+            ParseWarning::InvalidString { index } => write!(f, "Invalid string at index {}, replaced", index),
+            ParseWarning::ClampedString { index, length, clamped } => {
+                write!(f, "String at index {} of {} bytes exceeds its section, clamped to {} bytes", index, length, clamped)
+            }
+            ParseWarning::UnknownValue { field, value } => write!(f, "Unknown value {} of {}", value, field),
+        }
+    }
+}
+
+/// Errors of the methods editing a ```SaveFile```.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EditError {
+    /// The save has no plugin of this name.
+    PluginNotFound(String),
+    /// The save already has a plugin of this name, renaming another one to it would list it twice.
+    DuplicatePlugin(String),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::PluginNotFound(name) => write!(f, "The save has no plugin {}", name),
+            EditError::DuplicatePlugin(name) => write!(f, "The save already has a plugin {}", name),
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+/// An inconsistency in a ```SaveFile```, found by ```SaveFile::validate()```.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationError {
+    /// A count in the file location table does not match the number of entries of its table.
+    CountMismatch { table: &'static str, count: u32, entries: usize },
+    /// A RefID refers to an index past the end of the form id array.
+    FormIdIndexOutOfRange { index: u32, form_id_array_len: usize },
+    /// The screenshot data does not have the size its dimensions imply.
+    ScreenshotSizeMismatch { expected: usize, actual: usize },
+    /// The screenshot dimensions differ from the ones in the header.
+    ScreenshotDimensionMismatch { header: (u32, u32), screenshot: (u32, u32) },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::CountMismatch { table, count, entries } => {
+                write!(f, "The file location table specifies {} entries for the {}, but it has {}", count, table, entries)
+            }
+            ValidationError::FormIdIndexOutOfRange { index, form_id_array_len } => {
+                write!(f, "Form id index {} is out of range for the form id array of length {}", index, form_id_array_len)
+            }
+            ValidationError::ScreenshotSizeMismatch { expected, actual } => {
+                write!(f, "Screenshot has {} bytes, but its dimensions require {}", actual, expected)
+            }
+            ValidationError::ScreenshotDimensionMismatch { header, screenshot } => write!(
+                f,
+                "Screenshot is {}x{}, but the header specifies {}x{}",
+                screenshot.0, screenshot.1, header.0, header.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
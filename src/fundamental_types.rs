@@ -1,23 +1,89 @@
-#[derive(Clone, Debug)]
+use std::convert::TryFrom;
+use std::fmt;
+
+/// A variable size value. The variant determines the encoded width: 1, 2 or 3 bytes,
+/// of which 6, 14 or 22 bits hold the value.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum VSVal {
     U8(u8),
     U16(u16),
     U32(u32),
 }
 
-#[derive(Clone, Debug)]
+impl VSVal {
+    /// The vsval with the smallest encoding that can hold ```value```.
+    /// Values of 2^22 and above can't be encoded at all, they are returned as ```U32``` and
+    /// rejected by ```SaveFileWriter::write_vsval()```.
+    pub fn smallest(value: u32) -> VSVal {
+        match value {
+            0..=0x3F => VSVal::U8(value as u8),
+            0x40..=0x3FFF => VSVal::U16(value as u16),
+            _ => VSVal::U32(value),
+        }
+    }
+
+    /// The encoded width in bytes.
+    pub fn width(&self) -> usize {
+        match self {
+            VSVal::U8(_) => 1,
+            VSVal::U16(_) => 2,
+            VSVal::U32(_) => 3,
+        }
+    }
+}
+
+impl From<VSVal> for u32 {
+    fn from(value: VSVal) -> Self {
+        match value {
+            VSVal::U8(x) => x as u32,
+            VSVal::U16(x) => x as u32,
+            VSVal::U32(x) => x,
+        }
+    }
+}
+
+/// Creates a vsval of the given width in bytes (1, 2 or 3) from a value.
+impl TryFrom<(u32, usize)> for VSVal {
+    type Error = VSValRangeError;
+
+    fn try_from((value, width): (u32, usize)) -> Result<Self, Self::Error> {
+        match width {
+            1 if value < 1 << 6 => Ok(VSVal::U8(value as u8)),
+            2 if value < 1 << 14 => Ok(VSVal::U16(value as u16)),
+            3 if value < 1 << 22 => Ok(VSVal::U32(value)),
+            _ => Err(VSValRangeError { value, width }),
+        }
+    }
+}
+
+/// The value does not fit into a vsval of the requested width, or the width is not 1, 2 or 3.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VSValRangeError {
+    pub value: u32,
+    pub width: usize,
+}
+
+impl fmt::Display for VSValRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Value {} can't be encoded as vsval of {} bytes", self.value, self.width)
+    }
+}
+
+impl std::error::Error for VSValRangeError {}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct FileTime {
     pub dw_low_date_time: u32,
     pub dw_high_date_time: u32,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct WString {
     pub length: u16,
     pub content: String,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 /// The actual RefId data. Use ```get_form_id()``` to get a RefIdType that actually represents the data.
 pub struct RefId {
     pub byte0: u8,
@@ -52,10 +118,51 @@ impl RefId {
     pub fn get_parsed_id(&self) -> u32 {
         ((self.byte0 as u32) & 0b00111111) << 16 ^ (self.byte1 as u32) << 8 ^ (self.byte2 as u32)
     }
+
+    /// Encodes a full 32-bit form id, the inverse of ```FormIdType::resolve()```.
+    /// Ids contained in ```form_id_array``` are stored as index (+1), ids with the plugin index 0xFF
+    /// as created and all others as default. 0 is always stored as 0.
+    /// Fails if the index or the id does not fit into the 22 bits of a RefID.
+    pub fn from_form_id(form_id: u32, form_id_array: &[u32]) -> Result<RefId, RefIdError> {
+        if form_id == 0 {
+            return Ok(RefId { byte0: 0, byte1: 0, byte2: 0 });
+        }
+        let (kind, value) = match form_id_array.iter().position(|id| *id == form_id) {
+            Some(index) => (0, index as u32 + 1),
+            None if form_id & CREATED_FORM_ID_PREFIX == CREATED_FORM_ID_PREFIX => (2, form_id & !CREATED_FORM_ID_PREFIX),
+            None => (1, form_id),
+        };
+        if value >= 1 << 22 {
+            return Err(RefIdError { form_id });
+        }
+        Ok(RefId {
+            byte0: (kind << 6) | (value >> 16) as u8,
+            byte1: (value >> 8) as u8,
+            byte2: value as u8,
+        })
+    }
+}
+
+/// The form id can't be encoded as RefID, as its index or value does not fit into 22 bits.
+/// Form ids of plugins other than Skyrim.esm have to be contained in the form id array.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RefIdError {
+    pub form_id: u32,
 }
 
+impl fmt::Display for RefIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Form id {:08X} can't be encoded as RefID", self.form_id)
+    }
+}
+
+impl std::error::Error for RefIdError {}
+
+/// Plugin index 0xFF of objects created during the game, which only exist in the save.
+pub const CREATED_FORM_ID_PREFIX: u32 = 0xFF000000;
+
 /// The different types of formId that can be stored in a RefID.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum FormIdType {
     /// An index into the File.formIDArray.
     /// If the index value of 0 is given, the formID is 0x00000000, else, index into the array using value - 1.
@@ -67,4 +174,18 @@ pub enum FormIdType {
     Created(u32),
     /// ???
     Unknown(u32),
+}
+
+impl FormIdType {
+    /// Resolves the form id to the full 32-bit form id, looking up indices in ```form_id_array```.
+    /// Created ids get the plugin index 0xFF, i.e. 0xFFxxxxxx.
+    /// Returns None for out-of-bounds indices and form id types that cannot be resolved.
+    pub fn resolve(&self, form_id_array: &[u32]) -> Option<u32> {
+        match *self {
+            FormIdType::Index(index) => form_id_array.get(index as usize).copied(),
+            FormIdType::Default(id) => Some(id),
+            FormIdType::Created(id) => Some(CREATED_FORM_ID_PREFIX | id),
+            FormIdType::Unknown(_) => None,
+        }
+    }
 }
\ No newline at end of file
@@ -1,71 +1,217 @@
-/// All types used in the global data tables
+//! All types used in the global data tables
 
-use crate::SaveFileReader;
+use crate::{ParseOptions, ParseWarning, SaveFile, SaveFileReader, SaveFileWriter, SaveWriteError};
+use crate::writer::{write_ref_id, CountEncoding};
 use crate::fundamental_types::*;
 use std::convert::TryInto;
-use anyhow::Error;
-use crate::reader::{read_ref_id, read_vsval_to_u32, read_ref_ids_into_vec, read_into_vec, read_u32s_into_vec};
+use std::collections::HashSet;
+use crate::reader::{RecoveryStrategy, Readable, read_vec, read_ref_id, read_vsval_to_u32, read_ref_ids_into_vec, read_into_vec, read_u32s_into_vec, ReaderError};
 use std::fmt::{Debug, Formatter, Display};
 
 trait Parse {
-    fn parse(r: &mut SaveFileReader) -> GlobalDataType;
+    fn parse(r: &mut SaveFileReader) -> Result<GlobalDataType, ReaderError>;
 }
 
 /// Reads and parses global data into a Vec beginning at the current index of the provided SaveFileReader.
 /// This method relies on there actually being a global data structure at the indicated position.
-/// It currently may panic on finding another structure due to checked buffer overflows.
-pub fn read_global_data(r: &mut SaveFileReader, count: u32) -> Vec<GlobalDataType> {
-    read_into_vec(r, count, |sfr| {
-        let data_type = sfr.read_u32();
-        let length = sfr.read_u32();
-        let data: Vec<u8> = sfr.read_bytes_to_vec(length as usize);
-        read_global_data_type(data_type, length, data).unwrap()
-    })
+///
+/// Each entry's data is parsed in isolation, as its length is known beforehand.
+/// Entries of an unknown type and entries whose data fails to parse are handled according to
+/// ```options.recovery```: ```RecoveryStrategy::Strict``` fails, the other strategies store them as
+/// ```GlobalDataType::UnknownType``` and continue with the next entry. Warnings are recorded in ```r```,
+/// see ```SaveFileReader::take_warnings()```.
+pub fn read_global_data(r: &mut SaveFileReader, count: u32, options: &ParseOptions) -> Result<Vec<GlobalDataType>, ReaderError> {
+    let mut warnings = Vec::new();
+    let entries = read_global_data_with_warnings(r, count, options, &mut warnings)?;
+    warnings.into_iter().for_each(|warning| r.warn(warning));
+    Ok(entries)
+}
+
+/// Like ```read_global_data()```, but collects the warnings of ```RecoveryStrategy::Lenient``` in ```warnings```.
+pub(crate) fn read_global_data_with_warnings(r: &mut SaveFileReader, count: u32, options: &ParseOptions, warnings: &mut Vec<ParseWarning>) -> Result<Vec<GlobalDataType>, ReaderError> {
+    let mut entries = Vec::new();
+    let string_mode = r.string_mode();
+    for _i in 0..count {
+        let index = r.get_index();
+        let data_type = r.read_u32()?;
+        let length = r.read_u32()?;
+        let data = r.read_bytes(length as usize)?;
+        let mut data_reader = SaveFileReader::from_slice(data).with_string_mode(string_mode).with_recovery(options.recovery);
+        let result = read_global_data_type(data_type, length, &mut data_reader);
+        warnings.append(&mut data_reader.take_warnings());
+        let (entry, warning) = match result {
+            Ok(entry @ GlobalDataType::UnknownType { .. }) => match options.recovery {
+                RecoveryStrategy::Strict => return Err(ReaderError::UnknownDataType { index, data_type }),
+                _ => (entry, ParseWarning::UnknownGlobalDataType(data_type)),
+            },
+            Ok(entry) => {
+                entries.push(entry);
+                continue;
+            }
+            Err(e) => match options.recovery {
+                RecoveryStrategy::Strict => return Err(e),
+                _ => (GlobalDataType::UnknownType { data_type, data: data.to_vec() },
+                      ParseWarning::UnparsedGlobalData { data_type, message: e.to_string() }),
+            },
+        };
+        if options.recovery == RecoveryStrategy::Lenient {
+            warnings.push(warning);
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
 }
 
-fn read_global_data_type(data_type: u32, _data_length: u32, data: Vec<u8>) -> Result<GlobalDataType, Error> {
-    let mut r = SaveFileReader::new(data);
+/// Reads ```count``` global data entries, parsing only those of type ```type_id``` and skipping the others by their length.
+pub(crate) fn read_global_data_of_type(r: &mut SaveFileReader, count: u32, type_id: u32) -> Result<Vec<GlobalDataType>, ReaderError> {
+    let mut entries = Vec::new();
+    let (string_mode, recovery) = (r.string_mode(), r.recovery());
+    for _i in 0..count {
+        let data_type = r.read_u32()?;
+        let length = r.read_u32()?;
+        let data = r.read_bytes(length as usize)?;
+        if data_type == type_id {
+            let mut data_reader = SaveFileReader::from_slice(data).with_string_mode(string_mode).with_recovery(recovery);
+            entries.push(read_global_data_type(data_type, length, &mut data_reader)?);
+        }
+    }
+    Ok(entries)
+}
+
+/// Advances past ```count``` global data entries without parsing them.
+pub(crate) fn skip_global_data(r: &mut SaveFileReader, count: u32) -> Result<(), ReaderError> {
+    for _i in 0..count {
+        let _data_type = r.read_u32()?;
+        let length = r.read_u32()?;
+        r.read_bytes(length as usize)?;
+    }
+    Ok(())
+}
+
+/// Writes the data of a global data entry, without the type and length in front of it.
+/// Only entries whose layout is read completely can be written, the others fail with
+/// ```SaveWriteError::UnsupportedGlobalData```.
+pub fn write_global_data_type(w: &mut SaveFileWriter, data: &GlobalDataType) -> Result<(), SaveWriteError> {
+    match data {
+        GlobalDataType::MiscStats(stats) => {
+            w.write_count(stats.len(), CountEncoding::U32)?;
+            for stat in stats {
+                w.write_w_string(&stat.name)?;
+                w.write_u8(match stat.category {
+                    MiscStatCategory::General => 0,
+                    MiscStatCategory::Quest => 1,
+                    MiscStatCategory::Combat => 2,
+                    MiscStatCategory::Magic => 3,
+                    MiscStatCategory::Crafting => 4,
+                    MiscStatCategory::Crime => 5,
+                    MiscStatCategory::DLCStats => 6,
+                    // the original value is not kept
+                    MiscStatCategory::Error => return Err(SaveWriteError::UnsupportedGlobalData(data.type_id())),
+                });
+                w.write_u32(stat.value);
+            }
+        }
+        GlobalDataType::PlayerLocation(location) => {
+            w.write_u32(location.next_object_id);
+            write_ref_id(w, location.world_space_1)?;
+            w.write_i32(location.coor_x);
+            w.write_i32(location.coor_y);
+            write_ref_id(w, location.world_space_2)?;
+            w.write_f32(location.pos_x);
+            w.write_f32(location.pos_y);
+            w.write_f32(location.pos_z);
+            w.write_bytes(&location.unk);
+        }
+        GlobalDataType::Effects(effects) => {
+            w.write_count(effects.image_space_modifiers.len(), CountEncoding::VSVal)?;
+            for effect in &effects.image_space_modifiers {
+                w.write_f32(effect.strength);
+                w.write_f32(effect.timestamp);
+                w.write_u32(effect.unknown);
+                write_ref_id(w, effect.effect_id)?;
+            }
+            w.write_f32(effects.unknown1);
+            w.write_f32(effects.unknown2);
+            w.write_bytes(&effects.remainder);
+        }
+        GlobalDataType::GlobalVariables(variables) => {
+            w.write_count(variables.len(), CountEncoding::VSVal)?;
+            for variable in variables {
+                write_ref_id(w, variable.form_id)?;
+                w.write_f32(variable.value);
+            }
+        }
+        GlobalDataType::StoryTeller(value) => w.write_u8(*value as u8),
+        GlobalDataType::PlayerControls((u1, u2, u3, u4, u5)) => {
+            w.write_u8(*u1);
+            w.write_u8(*u2);
+            w.write_u8(*u3);
+            w.write_u16(*u4);
+            w.write_u8(*u5);
+        }
+        GlobalDataType::MenuControls(controls) => {
+            w.write_u8(controls.u0);
+            w.write_u8(controls.u1);
+        }
+        GlobalDataType::Timer(timer) => {
+            w.write_u8(timer.u0);
+            w.write_u8(timer.u1);
+            w.write_bytes(&timer.remainder);
+        }
+        GlobalDataType::MenuTopicManager((u1, u2)) => {
+            write_ref_id(w, *u1)?;
+            write_ref_id(w, *u2)?;
+        }
+        GlobalDataType::Combat(raw)
+        | GlobalDataType::Unknown104(raw)
+        | GlobalDataType::TempEffects(raw)
+        | GlobalDataType::Papyrus(raw)
+        | GlobalDataType::SynchronizedAnimations(raw)
+        | GlobalDataType::UnknownType { data: raw, .. } => w.write_bytes(raw),
+        GlobalDataType::Main => {}
+        _ => return Err(SaveWriteError::UnsupportedGlobalData(data.type_id())),
+    }
+    Ok(())
+}
 
+fn read_global_data_type(data_type: u32, _data_length: u32, r: &mut SaveFileReader) -> Result<GlobalDataType, ReaderError> {
     match data_type {
-        0 => Ok(MiscStats::parse(&mut r)),
-        1 => Ok(GlobalDataType::PlayerLocation(read_player_location(&mut r))),
-        2 => Ok(GlobalDataType::TES(read_tes(&mut r))),
-        3 => Ok(GlobalDataType::GlobalVariables(read_global_variables(&mut r))),
-        4 => Ok(GlobalDataType::CreatedObjects(read_created_objects(&mut r))),
-        5 => Ok(GlobalDataType::Effects(read_effects(&mut r))),
-        6 => Ok(GlobalDataType::Weather(read_weather(&mut r))),
-        7 => Ok(GlobalDataType::Audio(read_audio(&mut r))),
-        8 => Ok(GlobalDataType::SkyCells(read_sky_cells(&mut r))),
-        100 => Ok(GlobalDataType::ProcessLists(read_process_lists(&mut r))),
-        101 => Ok(GlobalDataType::Combat(r.get_buffer())),
-        102 => Ok(GlobalDataType::Interface(read_interface(&mut r))),
-        103 => Ok(ActorCauses::parse(&mut r)),
-        104 => Ok(GlobalDataType::Unknown104(r.get_buffer())),
-        105 => Ok(DetectionManagerUnknown0::parse(&mut r)),
-        106 => Ok(LocationMetaDataUnknown0::parse(&mut r)),
-        107 => Ok(QuestStaticData::parse(&mut r)),
-        108 => Ok(GlobalDataType::StoryTeller(r.read_u8() != 0)),
-        109 => Ok(MagicFavorites::parse(&mut r)),
-        110 => Ok(GlobalDataType::PlayerControls((r.read_u8(), r.read_u8(), r.read_u8(), r.read_u16(), r.read_u8()))),
-        111 => Ok(StoryEventManager::parse(&mut r)),
-        112 => Ok(IngredientsCombined::parse(&mut r)),
-        113 => Ok(GlobalDataType::MenuControls((r.read_u8(), r.read_u8()))),
-        114 => Ok(GlobalDataType::MenuTopicManager((read_ref_id(&mut r), read_ref_id(&mut r)))),
-        1000 => Ok(GlobalDataType::TempEffects(r.get_buffer())),
-        1001 => Ok(GlobalDataType::Papyrus(r.get_buffer())),
-        1002 => Ok(AnimObject::parse(&mut r)),
-        1003 => Ok(GlobalDataType::Timer((r.read_u8(), r.read_u8()))),
-        1004 => Ok(GlobalDataType::SynchronizedAnimations(r.get_buffer())),
+        0 => MiscStats::parse(r),
+        1 => Ok(GlobalDataType::PlayerLocation(read_player_location(r)?)),
+        2 => Ok(GlobalDataType::TES(read_tes(r)?)),
+        3 => Ok(GlobalDataType::GlobalVariables(read_global_variables(r)?)),
+        4 => Ok(GlobalDataType::CreatedObjects(read_created_objects(r)?)),
+        5 => Ok(GlobalDataType::Effects(read_effects(r)?)),
+        6 => Ok(GlobalDataType::Weather(read_weather(r)?)),
+        7 => Ok(GlobalDataType::Audio(read_audio(r)?)),
+        8 => Ok(GlobalDataType::SkyCells(read_sky_cells(r)?)),
+        100 => Ok(GlobalDataType::ProcessLists(read_process_lists(r)?)),
+        101 => Ok(GlobalDataType::Combat(r.remaining().to_vec())),
+        102 => Ok(GlobalDataType::Interface(read_interface(r)?)),
+        103 => ActorCauses::parse(r),
+        104 => Ok(GlobalDataType::Unknown104(r.remaining().to_vec())),
+        105 => DetectionManagerUnknown0::parse(r),
+        106 => LocationMetaDataUnknown0::parse(r),
+        107 => QuestStaticData::parse(r),
+        108 => Ok(GlobalDataType::StoryTeller(r.read_u8()? != 0)),
+        109 => MagicFavorites::parse(r),
+        110 => Ok(GlobalDataType::PlayerControls((r.read_u8()?, r.read_u8()?, r.read_u8()?, r.read_u16()?, r.read_u8()?))),
+        111 => StoryEventManager::parse(r),
+        112 => IngredientsCombined::parse(r),
+        113 => Ok(GlobalDataType::MenuControls(MenuControls { u0: r.read_u8()?, u1: r.read_u8()? })),
+        114 => Ok(GlobalDataType::MenuTopicManager((read_ref_id(r)?, read_ref_id(r)?))),
+        1000 => Ok(GlobalDataType::TempEffects(r.remaining().to_vec())),
+        1001 => Ok(GlobalDataType::Papyrus(r.remaining().to_vec())),
+        1002 => AnimObject::parse(r),
+        1003 => Ok(GlobalDataType::Timer(Timer { u0: r.read_u8()?, u1: r.read_u8()?, remainder: r.remaining().to_vec() })),
+        1004 => Ok(GlobalDataType::SynchronizedAnimations(r.remaining().to_vec())),
         1005 => Ok(GlobalDataType::Main),
-        _ => {
-            println!("Found unknown global data type!");
-            Ok(GlobalDataType::Main)
-        }
+        _ => Ok(GlobalDataType::UnknownType { data_type, data: r.remaining().to_vec() }),
     }
 }
 
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum GlobalDataType {
     MiscStats(Vec<MiscStats>),
     PlayerLocation(PlayerLocation),
@@ -81,6 +227,7 @@ pub enum GlobalDataType {
     Combat(Vec<u8>),
     Interface(Interface),
     ActorCauses(ActorCauses),
+    /// Currently not parsed, see ```ref_id_outline()```.
     Unknown104(Vec<u8>),
     DetectionManager(Vec<DetectionManagerUnknown0>),
     LocationMetaData(Vec<LocationMetaDataUnknown0>),
@@ -92,7 +239,7 @@ pub enum GlobalDataType {
     StoryEventManager(StoryEventManager),
     /// Pairs of failed ingredient combinations in alchemy.
     IngredientShared(Vec<IngredientsCombined>),
-    MenuControls((u8, u8)),
+    MenuControls(MenuControls),
     MenuTopicManager((FormIdType, FormIdType)),
     /// Currently not parsed, as this is a very complicated data structure with almost no known information
     TempEffects(Vec<u8>),
@@ -100,22 +247,178 @@ pub enum GlobalDataType {
     Papyrus(Vec<u8>),
     /// Array with currently active actor reference + animation combo? Haven't yet determined when these are saved.
     AnimObjects(Vec<AnimObject>),
-    /// no further known information
-    Timer((u8, u8)),
-    /// uesp hasn't even got a page for that
+    Timer(Timer),
+    /// uesp hasn't even got a page for that, see ```ref_id_outline()```.
     SynchronizedAnimations(Vec<u8>),
     /// Always empty, not read by skyrim due to bug
     Main,
+    /// A global data entry of an unknown type, or one that could not be parsed and was recovered,
+    /// see ```RecoveryStrategy```.
+    /// Contains the raw entry data.
+    UnknownType { data_type: u32, data: Vec<u8> },
+}
+
+impl GlobalDataType {
+    /// The type id this entry is stored with, see ```read_global_data()```.
+    pub fn type_id(&self) -> u32 {
+        match self {
+            GlobalDataType::MiscStats(_) => 0,
+            GlobalDataType::PlayerLocation(_) => 1,
+            GlobalDataType::TES(_) => 2,
+            GlobalDataType::GlobalVariables(_) => 3,
+            GlobalDataType::CreatedObjects(_) => 4,
+            GlobalDataType::Effects(_) => 5,
+            GlobalDataType::Weather(_) => 6,
+            GlobalDataType::Audio(_) => 7,
+            GlobalDataType::SkyCells(_) => 8,
+            GlobalDataType::ProcessLists(_) => 100,
+            GlobalDataType::Combat(_) => 101,
+            GlobalDataType::Interface(_) => 102,
+            GlobalDataType::ActorCauses(_) => 103,
+            GlobalDataType::Unknown104(_) => 104,
+            GlobalDataType::DetectionManager(_) => 105,
+            GlobalDataType::LocationMetaData(_) => 106,
+            GlobalDataType::QuestStaticData(_) => 107,
+            GlobalDataType::StoryTeller(_) => 108,
+            GlobalDataType::MagicFavorites(_) => 109,
+            GlobalDataType::PlayerControls(_) => 110,
+            GlobalDataType::StoryEventManager(_) => 111,
+            GlobalDataType::IngredientShared(_) => 112,
+            GlobalDataType::MenuControls(_) => 113,
+            GlobalDataType::MenuTopicManager(_) => 114,
+            GlobalDataType::TempEffects(_) => 1000,
+            GlobalDataType::Papyrus(_) => 1001,
+            GlobalDataType::AnimObjects(_) => 1002,
+            GlobalDataType::Timer(_) => 1003,
+            GlobalDataType::SynchronizedAnimations(_) => 1004,
+            GlobalDataType::Main => 1005,
+            GlobalDataType::UnknownType { data_type, .. } => *data_type,
+        }
+    }
+
+    /// All ref ids contained in this entry, in the order they appear in the data.
+    pub fn ref_ids(&self) -> Vec<FormIdType> {
+        let mut ids = Vec::new();
+        match self {
+            GlobalDataType::PlayerLocation(l) => ids.extend([l.world_space_1, l.world_space_2]),
+            GlobalDataType::TES(tes) => {
+                ids.extend(tes.u1.iter().map(|u| u.form_id));
+                ids.extend(&tes.u2);
+                ids.extend(&tes.u3);
+            }
+            GlobalDataType::GlobalVariables(vars) => ids.extend(vars.iter().map(|v| v.form_id)),
+            GlobalDataType::CreatedObjects(c) => {
+                for enchantment in c.weapon_ench_table.iter()
+                    .chain(&c.armour_ench_table)
+                    .chain(&c.potion_table)
+                    .chain(&c.poison_table) {
+                    ids.push(enchantment.ref_id);
+                    ids.extend(enchantment.effects.iter().map(|e| e.effect_id));
+                }
+            }
+            GlobalDataType::Effects(e) => ids.extend(e.image_space_modifiers.iter().map(|m| m.effect_id)),
+            GlobalDataType::Weather(w) => ids.extend([
+                w.climate, w.weather, w.prev_weather, w.unk_weather_1, w.unk_weather_2, w.regn_weather
+            ]),
+            GlobalDataType::Audio(a) => {
+                ids.push(a.unknown);
+                ids.extend(&a.tracks);
+                ids.push(a.bgm);
+            }
+            GlobalDataType::SkyCells(cells) => cells.iter().for_each(|c| ids.extend([c.u1, c.u2])),
+            GlobalDataType::ProcessLists(p) => {
+                for crime in &p.all_crimes {
+                    ids.extend([crime.victim_id, crime.criminal_id, crime.item_base_id, crime.ownership_id]);
+                    ids.extend(&crime.witnesses);
+                    ids.push(crime.crime_faction_id);
+                }
+            }
+            GlobalDataType::Interface(i) => {
+                ids.extend(&i.last_used_weapons);
+                ids.extend(&i.last_used_spells);
+                ids.extend(&i.last_used_shouts);
+            }
+            GlobalDataType::ActorCauses(a) => ids.extend(a.unknown.iter().map(|u| u.actor_id)),
+            GlobalDataType::DetectionManager(d) => ids.extend(d.iter().map(|u| u.u0)),
+            GlobalDataType::LocationMetaData(l) => ids.extend(l.iter().map(|u| u.u0)),
+            GlobalDataType::QuestStaticData(q) => {
+                for item in q.u0.iter().chain(&q.u1) {
+                    ids.extend(item.quest_run_data_item_3_data.iter().filter_map(|d| match d {
+                        QuestRunDataItem3DataType::RefId(id) => Some(*id),
+                        QuestRunDataItem3DataType::U32(_) => None,
+                    }));
+                }
+                ids.extend(&q.u2);
+                ids.extend(&q.u3);
+                ids.extend(&q.u4);
+                ids.extend(q.u5.iter().map(|u| u.unk0_0));
+            }
+            GlobalDataType::MagicFavorites(m) => {
+                ids.extend(&m.favorited_magics);
+                ids.extend(&m.magic_hot_keys);
+            }
+            GlobalDataType::IngredientShared(i) => i.iter().for_each(|i| ids.extend([i.ingredient0, i.ingredient1])),
+            GlobalDataType::MenuTopicManager((a, b)) => ids.extend([*a, *b]),
+            GlobalDataType::AnimObjects(a) => a.iter().for_each(|a| ids.extend([a.achr, a.anim])),
+            GlobalDataType::MiscStats(_)
+            | GlobalDataType::Combat(_)
+            | GlobalDataType::Unknown104(_)
+            | GlobalDataType::StoryTeller(_)
+            | GlobalDataType::PlayerControls(_)
+            | GlobalDataType::StoryEventManager(_)
+            | GlobalDataType::MenuControls(_)
+            | GlobalDataType::TempEffects(_)
+            | GlobalDataType::Papyrus(_)
+            | GlobalDataType::Timer(_)
+            | GlobalDataType::SynchronizedAnimations(_)
+            | GlobalDataType::Main
+            | GlobalDataType::UnknownType { .. } => {}
+        }
+        ids
+    }
+
+    /// A shallow parse of ```Unknown104``` and ```SynchronizedAnimations```, whose layout is unknown.
+    /// Both seem to start with a vsval count followed by a RefID per entry, this reads that list
+    /// without interpreting the rest. Returns None for other types and if the data is too short
+    /// for the pattern, the raw data stays the authoritative content.
+    pub fn ref_id_outline(&self) -> Option<RefIdOutline> {
+        match self {
+            GlobalDataType::Unknown104(raw) | GlobalDataType::SynchronizedAnimations(raw) => read_ref_id_outline(raw),
+            _ => None,
+        }
+    }
+}
+
+/// The leading list of RefIDs of an unparsed entry, see ```GlobalDataType::ref_id_outline()```.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RefIdOutline {
+    pub ref_ids: Vec<FormIdType>,
+    /// The data following the list.
+    pub remainder: Vec<u8>,
 }
 
-#[derive(Clone, Debug)]
+fn read_ref_id_outline(raw: &[u8]) -> Option<RefIdOutline> {
+    // an invalid count means the data doesn't start with a list
+    let mut r = SaveFileReader::from_slice(raw).with_recovery(RecoveryStrategy::Strict);
+    let count = read_vsval_to_u32(&mut r).ok()?;
+    if count as usize * 3 > r.remaining().len() {
+        return None;
+    }
+    let ref_ids = read_ref_ids_into_vec(&mut r, count).ok()?;
+    Some(RefIdOutline {
+        ref_ids,
+        remainder: r.remaining().to_vec(),
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct MiscStats {
     pub name: String,
     pub category: MiscStatCategory,
     pub value: u32,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum MiscStatCategory {
     General,
     Quest,
@@ -141,13 +444,13 @@ impl Display for MiscStatCategory {
 }
 
 impl Parse for MiscStats {
-    fn parse(r: &mut SaveFileReader) -> GlobalDataType {
-        let count = r.read_u32();
+    fn parse(r: &mut SaveFileReader) -> Result<GlobalDataType, ReaderError> {
+        let count = r.read_u32()?;
         let mut results = Vec::new();
         for _i in 0..count {
             results.push(MiscStats {
-                name: r.read_w_string().content,
-                category: match r.read_u8() {
+                name: r.read_w_string()?.content,
+                category: match r.read_u8()? {
                     0 => MiscStatCategory::General,
                     1 => MiscStatCategory::Quest,
                     2 => MiscStatCategory::Combat,
@@ -157,14 +460,14 @@ impl Parse for MiscStats {
                     6 => MiscStatCategory::DLCStats,
                     _ => MiscStatCategory::Error
                 },
-                value: r.read_u32(),
+                value: r.read_u32()?,
             });
         }
-        GlobalDataType::MiscStats(results)
+        Ok(GlobalDataType::MiscStats(results))
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct PlayerLocation {
     /// Number of next savegame specific object id, i.e. FFxxxxxx.
     pub next_object_id: u32,
@@ -188,29 +491,35 @@ pub struct PlayerLocation {
     pub unk: Vec<u8>,
 }
 
-fn read_player_location(r: &mut SaveFileReader) -> PlayerLocation {
-    PlayerLocation {
-        next_object_id: r.read_u32(),
-        world_space_1: read_ref_id(r),
-        coor_x: r.read_i32(),
-        coor_y: r.read_i32(),
-        world_space_2: read_ref_id(r),
-        pos_x: r.read_f32(),
-        pos_y: r.read_f32(),
-        pos_z: r.read_f32(),
-        /// we dont know what it is and it seems to be absent in some versions.
+fn read_player_location(r: &mut SaveFileReader) -> Result<PlayerLocation, ReaderError> {
+    Ok(PlayerLocation {
+        next_object_id: r.read_u32()?,
+        world_space_1: read_ref_id(r)?,
+        coor_x: r.read_i32()?,
+        coor_y: r.read_i32()?,
+        world_space_2: read_ref_id(r)?,
+        pos_x: r.read_f32()?,
+        pos_y: r.read_f32()?,
+        pos_z: r.read_f32()?,
+        // we dont know what it is and it seems to be absent in some versions.
         unk: vec![],
+    })
+}
+
+impl Readable for PlayerLocation {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        read_player_location(r)
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct TES {
     pub u1: Vec<TESUnknown0>,
     pub u2: Vec<FormIdType>,
     pub u3: Vec<FormIdType>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct TESUnknown0 {
     pub form_id: FormIdType,
     pub unknown: u16,
@@ -226,47 +535,56 @@ impl Debug for TES {
     }
 }
 
-fn read_tes(r: &mut SaveFileReader) -> TES {
+fn read_tes(r: &mut SaveFileReader) -> Result<TES, ReaderError> {
     let mut u1 = Vec::new();
-    for _i in 0..read_vsval_to_u32(r) {
+    for _i in 0..read_vsval_to_u32(r)? {
         u1.push(TESUnknown0 {
-            form_id: read_ref_id(r),
-            unknown: r.read_u16(),
+            form_id: read_ref_id(r)?,
+            unknown: r.read_u16()?,
         })
     }
     let mut u2 = Vec::new();
-    for _i in 0..r.read_u32() * 2 {
-        u2.push(read_ref_id(r))
+    for _i in 0..r.read_u32()? * 2 {
+        u2.push(read_ref_id(r)?)
     }
     let mut u3 = Vec::new();
-    for _i in 0..read_vsval_to_u32(r) {
-        u3.push(read_ref_id(r))
+    for _i in 0..read_vsval_to_u32(r)? {
+        u3.push(read_ref_id(r)?)
     }
-    TES {
+    Ok(TES {
         u1,
         u2,
         u3,
+    })
+}
+
+impl Readable for TES {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        read_tes(r)
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct GlobalVariable {
     pub form_id: FormIdType,
     pub value: f32,
 }
 
-fn read_global_variables(r: &mut SaveFileReader) -> Vec<GlobalVariable> {
-    let mut vec = Vec::new();
-    for _i in 0..read_vsval_to_u32(r) {
-        vec.push(GlobalVariable {
-            form_id: read_ref_id(r),
-            value: r.read_f32(),
-        });
+impl Readable for GlobalVariable {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        Ok(GlobalVariable {
+            form_id: read_ref_id(r)?,
+            value: r.read_f32()?,
+        })
     }
-    vec
 }
 
-#[derive(Clone, Debug)]
+fn read_global_variables(r: &mut SaveFileReader) -> Result<Vec<GlobalVariable>, ReaderError> {
+    let count = read_vsval_to_u32(r)?;
+    read_vec(r, count)
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct CreatedObjects {
     /// List of all created enchantments that are/were applied to weapons.
     pub weapon_ench_table: Vec<Enchantment>,
@@ -279,25 +597,85 @@ pub struct CreatedObjects {
     pub poison_table: Vec<Enchantment>,
 }
 
-fn read_created_objects(r: &mut SaveFileReader) -> CreatedObjects {
-    let weapon_ench_table_count = read_vsval_to_u32(r);
-    let weapon_ench_table = read_enchantments(r, weapon_ench_table_count);
-    let armour_ench_table_count = read_vsval_to_u32(r);
-    let armour_ench_table = read_enchantments(r, armour_ench_table_count);
-    let potion_table_count = read_vsval_to_u32(r);
-    let potion_table = read_enchantments(r, potion_table_count);
-    let poison_table_count = read_vsval_to_u32(r);
-    let poison_table = read_enchantments(r, poison_table_count);
+impl CreatedObjects {
+    /// Lists the created items of all four tables with their form ids and the ones of their effects
+    /// resolved with the form id array of ```save```. Ids that can't be resolved are None.
+    pub fn catalog(&self, save: &SaveFile) -> Vec<CreatedItemSummary> {
+        let tables = [
+            (CreatedItemKind::WeaponEnchantment, &self.weapon_ench_table),
+            (CreatedItemKind::ArmourEnchantment, &self.armour_ench_table),
+            (CreatedItemKind::Potion, &self.potion_table),
+            (CreatedItemKind::Poison, &self.poison_table),
+        ];
+        tables.iter()
+            .flat_map(|(kind, table)| table.iter().map(move |item| (*kind, item)))
+            .map(|(kind, item)| CreatedItemSummary {
+                kind,
+                form_id: save.resolve_form_id(item.ref_id),
+                times_used: item.times_used,
+                effects: item.effects.iter().map(|effect| CreatedEffectSummary {
+                    effect_id: save.resolve_form_id(effect.effect_id),
+                    magnitude: effect.info.magnitude,
+                    duration: effect.info.duration,
+                    area: effect.info.area,
+                }).collect(),
+            })
+            .collect()
+    }
+}
+
+/// The table of ```CreatedObjects``` a created item is stored in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CreatedItemKind {
+    WeaponEnchantment,
+    ArmourEnchantment,
+    Potion,
+    Poison,
+}
+
+/// A created item with resolved form ids, see ```CreatedObjects::catalog()```.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreatedItemSummary {
+    pub kind: CreatedItemKind,
+    pub form_id: Option<u32>,
+    pub times_used: u32,
+    pub effects: Vec<CreatedEffectSummary>,
+}
+
+/// An effect of a created item, with the form id of its base effect resolved.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CreatedEffectSummary {
+    pub effect_id: Option<u32>,
+    pub magnitude: f32,
+    pub duration: u32,
+    pub area: u32,
+}
+
+fn read_created_objects(r: &mut SaveFileReader) -> Result<CreatedObjects, ReaderError> {
+    let weapon_ench_table_count = read_vsval_to_u32(r)?;
+    let weapon_ench_table = read_enchantments(r, weapon_ench_table_count)?;
+    let armour_ench_table_count = read_vsval_to_u32(r)?;
+    let armour_ench_table = read_enchantments(r, armour_ench_table_count)?;
+    let potion_table_count = read_vsval_to_u32(r)?;
+    let potion_table = read_enchantments(r, potion_table_count)?;
+    let poison_table_count = read_vsval_to_u32(r)?;
+    let poison_table = read_enchantments(r, poison_table_count)?;
 
-    CreatedObjects {
+    Ok(CreatedObjects {
         weapon_ench_table,
         armour_ench_table,
         potion_table,
         poison_table,
+    })
+}
+
+impl Readable for CreatedObjects {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        read_created_objects(r)
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Enchantment {
     /// FormID of the enchantment. I've only seen created types, no default or array types.
     pub ref_id: FormIdType,
@@ -308,23 +686,24 @@ pub struct Enchantment {
     pub effects: Vec<MagicEffect>,
 }
 
-fn read_enchantments(r: &mut SaveFileReader, count: u32) -> Vec<Enchantment> {
-    let mut enchantments = Vec::new();
-    for _i in 0..count {
-        let ref_id = read_ref_id(r);
-        let times_used = r.read_u32();
-        let effects_count = read_vsval_to_u32(r);
-        let effects = read_magic_effects(r, effects_count);
-        enchantments.push(Enchantment {
+impl Readable for Enchantment {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        let ref_id = read_ref_id(r)?;
+        let times_used = r.read_u32()?;
+        let effects_count = read_vsval_to_u32(r)?;
+        Ok(Enchantment {
             ref_id,
             times_used,
-            effects,
-        });
+            effects: read_vec(r, effects_count)?,
+        })
     }
-    enchantments
 }
 
-#[derive(Clone, Debug)]
+fn read_enchantments(r: &mut SaveFileReader, count: u32) -> Result<Vec<Enchantment>, ReaderError> {
+    read_vec(r, count)
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct MagicEffect {
     pub effect_id: FormIdType,
     pub info: EnchInfo,
@@ -332,57 +711,69 @@ pub struct MagicEffect {
     pub price: f32,
 }
 
-fn read_magic_effects(r: &mut SaveFileReader, count: u32) -> Vec<MagicEffect> {
-    read_into_vec(
-        r,
-        count,
-        |r|
-            MagicEffect {
-                effect_id: read_ref_id(r),
-                info: EnchInfo {
-                    magnitude: r.read_f32(),
-                    duration: r.read_u32(),
-                    area: r.read_u32(),
-                },
-                price: r.read_f32(),
-            })
+impl Readable for MagicEffect {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        Ok(MagicEffect {
+            effect_id: read_ref_id(r)?,
+            info: EnchInfo {
+                magnitude: r.read_f32()?,
+                duration: r.read_u32()?,
+                area: r.read_u32()?,
+            },
+            price: r.read_f32()?,
+        })
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct EnchInfo {
     pub magnitude: f32,
     pub duration: u32,
     pub area: u32,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Effects {
     pub image_space_modifiers: Vec<Effect>,
+    /// Meaning unknown, UESP lists it as unknown. Possibly a global strength or fade parameter
+    /// applied to the whole imagespace modifier stack, this is not confirmed.
     pub unknown1: f32,
+    /// Meaning unknown, see ```unknown1```.
     pub unknown2: f32,
+    /// Bytes following ```unknown2``` in the entry, not parsed.
+    /// Kept so the entry can be written back unchanged.
+    pub remainder: Vec<u8>,
 }
 
-fn read_effects(r: &mut SaveFileReader) -> Effects {
-    let image_space_modifiers_length = read_vsval_to_u32(r);
+fn read_effects(r: &mut SaveFileReader) -> Result<Effects, ReaderError> {
+    let image_space_modifiers_length = read_vsval_to_u32(r)?;
     let mut image_space_modifiers = Vec::new();
     for _i in 0..image_space_modifiers_length {
         image_space_modifiers.push({
             Effect {
-                strength: r.read_f32(),
-                timestamp: r.read_f32(),
-                unknown: r.read_u32(),
-                effect_id: read_ref_id(r),
+                strength: r.read_f32()?,
+                timestamp: r.read_f32()?,
+                unknown: r.read_u32()?,
+                effect_id: read_ref_id(r)?,
             }
         });
     }
-    Effects {
+    Ok(Effects {
         image_space_modifiers,
-        unknown1: r.read_f32(),
-        unknown2: r.read_f32(),
+        unknown1: r.read_f32()?,
+        unknown2: r.read_f32()?,
+        // the entry is read from its own buffer, so this is only the rest of the entry
+        remainder: r.remaining().to_vec(),
+    })
+}
+
+impl Readable for Effects {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        read_effects(r)
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Effect {
     /// Value from 0 to 1 (0 is no effect, 1 is full effect)
     pub strength: f32,
@@ -393,7 +784,7 @@ pub struct Effect {
     pub effect_id: FormIdType,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Weather {
     pub climate: FormIdType,
     pub weather: FormIdType,
@@ -408,49 +799,72 @@ pub struct Weather {
     pub beg_time: f32,
     /// A value from 0.0 to 1.0 describing how far in the current weather has transitioned
     pub weather_pct: f32,
+    // The meaning of u1 to u8 is not known yet, the names match the fields of the UESP page.
+    // The comments give the offset of the field in the weather global data entry.
+    /// Unknown, offset 30.
     pub u1: u32,
+    /// Unknown, offset 34.
     pub u2: u32,
+    /// Unknown, offset 38.
     pub u3: u32,
+    /// Unknown, offset 42.
     pub u4: u32,
+    /// Unknown, offset 46.
     pub u5: u32,
+    /// Unknown, offset 50.
     pub u6: u32,
+    /// Unknown, offset 54.
     pub u7: f32,
+    /// Unknown, offset 58.
     pub u8: u32,
+    /// See ```WeatherFlags```, offset 62.
     pub flags: u8,
-    /// Unresearched format. Only present if flags has bit 0 set.
-    pub u9: Option<String>,
-    /// Unresearched format. Only present if flags has bit 1 set.
-    pub u10: Option<String>,
-}
-
-fn read_weather(r: &mut SaveFileReader) -> Weather {
-    let climate = read_ref_id(r);
-    let weather = read_ref_id(r);
-    let prev_weather = read_ref_id(r);
-    let unk_weather_1 = read_ref_id(r);
-    let unk_weather_2 = read_ref_id(r);
-    let regn_weather = read_ref_id(r);
-    let cur_time = r.read_f32();
-    let beg_time = r.read_f32();
-    let weather_pct = r.read_f32();
-    let u1 = r.read_u32();
-    let u2 = r.read_u32();
-    let u3 = r.read_u32();
-    let u4 = r.read_u32();
-    let u5 = r.read_u32();
-    let u6 = r.read_u32();
-    let u7 = r.read_f32();
-    let u8 = r.read_u32();
-    let flags = r.read_u8();
-    let mut u9 = None;
-    let mut u10 = None;
-    if flags & 0b10000000 == 0b10000000 {
-        u9 = Some("Unbekannter Datentyp".to_string())
-    }
-    if flags & 0b01000000 == 0b01000000 {
-        u10 = Some("Unbekannter Datentyp".to_string())
-    }
-    Weather {
+    /// The bytes following the flags, kept as read since their format is not researched.
+    /// They hold u9 if ```WeatherFlags::HAS_U9``` is set and u10 if ```WeatherFlags::HAS_U10``` is set.
+    pub remainder: Vec<u8>,
+}
+
+impl Weather {
+    pub fn flags_typed(&self) -> WeatherFlags {
+        WeatherFlags(self.flags)
+    }
+}
+
+/// The flags of ```Weather```, which determine the presence of the optional fields.
+/// UESP counts these bits from the most significant bit, so "bit 0" is 0x80.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WeatherFlags(pub u8);
+
+impl WeatherFlags {
+    pub const HAS_U9: WeatherFlags = WeatherFlags(0b10000000);
+    pub const HAS_U10: WeatherFlags = WeatherFlags(0b01000000);
+
+    /// True if all bits of ```other``` are set.
+    pub fn contains(&self, other: WeatherFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+fn read_weather(r: &mut SaveFileReader) -> Result<Weather, ReaderError> {
+    let climate = read_ref_id(r)?;
+    let weather = read_ref_id(r)?;
+    let prev_weather = read_ref_id(r)?;
+    let unk_weather_1 = read_ref_id(r)?;
+    let unk_weather_2 = read_ref_id(r)?;
+    let regn_weather = read_ref_id(r)?;
+    let cur_time = r.read_f32()?;
+    let beg_time = r.read_f32()?;
+    let weather_pct = r.read_f32()?;
+    let u1 = r.read_u32()?;
+    let u2 = r.read_u32()?;
+    let u3 = r.read_u32()?;
+    let u4 = r.read_u32()?;
+    let u5 = r.read_u32()?;
+    let u6 = r.read_u32()?;
+    let u7 = r.read_f32()?;
+    let u8 = r.read_u32()?;
+    let flags = r.read_u8()?;
+    Ok(Weather {
         climate,
         weather,
         prev_weather,
@@ -469,12 +883,17 @@ fn read_weather(r: &mut SaveFileReader) -> Weather {
         u7,
         u8,
         flags,
-        u9,
-        u10,
+        remainder: r.remaining().to_vec(),
+    })
+}
+
+impl Readable for Weather {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        read_weather(r)
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Audio {
     /// Only the UIActivateFail sound descriptor has been observed here.
     pub unknown: FormIdType,
@@ -484,62 +903,87 @@ pub struct Audio {
     pub bgm: FormIdType,
 }
 
-pub fn read_audio(r: &mut SaveFileReader) -> Audio {
-    let unknown = read_ref_id(r);
-    let tracks_count = read_vsval_to_u32(r);
-    let tracks = read_ref_ids_into_vec(r, tracks_count);
-    let bgm = read_ref_id(r);
-    Audio {
+impl Audio {
+    /// The full form ids of the MUST records in ```tracks```. Tracks that cannot be resolved are left out.
+    pub fn playing_tracks(&self, save: &SaveFile) -> Vec<u32> {
+        self.tracks.iter().filter_map(|track| save.resolve_form_id(*track)).collect()
+    }
+}
+
+pub fn read_audio(r: &mut SaveFileReader) -> Result<Audio, ReaderError> {
+    let unknown = read_ref_id(r)?;
+    let tracks_count = read_vsval_to_u32(r)?;
+    let tracks = read_ref_ids_into_vec(r, tracks_count)?;
+    let bgm = read_ref_id(r)?;
+    Ok(Audio {
         unknown,
         tracks,
         bgm,
+    })
+}
+
+impl Readable for Audio {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        read_audio(r)
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct SkyCellUnknown0 {
     pub u1: FormIdType,
     pub u2: FormIdType,
 }
 
-fn read_sky_cells(r: &mut SaveFileReader) -> Vec<SkyCellUnknown0> {
-    let count = read_vsval_to_u32(r);
-    read_into_vec(r, count, |r| SkyCellUnknown0 {
-        u1: read_ref_id(r),
-        u2: read_ref_id(r),
-    })
+fn read_sky_cells(r: &mut SaveFileReader) -> Result<Vec<SkyCellUnknown0>, ReaderError> {
+    let count = read_vsval_to_u32(r)?;
+    read_into_vec(r, count, |r| Ok(SkyCellUnknown0 {
+        u1: read_ref_id(r)?,
+        u2: read_ref_id(r)?,
+    }))
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ProcessLists {
+    /// Meaning unknown, UESP lists the three floats as unknown. They are possibly update timers of
+    /// the process lists, which decide when actors are processed, this is not confirmed.
     pub u1: f32,
+    /// Meaning unknown, see ```u1```.
     pub u2: f32,
+    /// Meaning unknown, see ```u1```.
     pub u3: f32,
     /// This value is assigned to the next process
     pub next_num: u32,
     /// Crimes grouped according with their type (see below)
     pub all_crimes: Vec<Crime>,
-}
-
-fn read_process_lists(r: &mut SaveFileReader) -> ProcessLists {
-    let u1 = r.read_f32();
-    let u2 = r.read_f32();
-    let u3 = r.read_f32();
-    let next_num = r.read_u32();
-    let crime_type_count = read_vsval_to_u32(r);
-    let all_crimes = read_into_vec(r, crime_type_count, |r| {
-        read_crime(r)
-    });
-    ProcessLists {
+    /// Bytes following the crimes in the entry, not parsed.
+    /// Kept so the entry can be written back unchanged.
+    pub remainder: Vec<u8>,
+}
+
+fn read_process_lists(r: &mut SaveFileReader) -> Result<ProcessLists, ReaderError> {
+    let u1 = r.read_f32()?;
+    let u2 = r.read_f32()?;
+    let u3 = r.read_f32()?;
+    let next_num = r.read_u32()?;
+    let crime_type_count = read_vsval_to_u32(r)?;
+    let all_crimes = read_into_vec(r, crime_type_count, read_crime)?;
+    Ok(ProcessLists {
         u1,
         u2,
         u3,
         next_num,
         all_crimes,
+        remainder: r.remaining().to_vec(),
+    })
+}
+
+impl Readable for ProcessLists {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        read_process_lists(r)
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Crime {
     pub witness_num: u32,
     pub crime_type: CrimeType,
@@ -569,33 +1013,34 @@ pub struct Crime {
     pub u4: u16,
 }
 
-fn read_crime(r: &mut SaveFileReader) -> Crime {
-    let witness_num = r.read_u32();
-    let crime_type = convert_to_crime_type(r.read_u32());
-    let u1 = r.read_u8();
-    let quantity = r.read_u32();
-    let serial_num = r.read_u32();
-    let u2 = r.read_u8();
-    let u3 = r.read_u32();
-    let elapsed_time = r.read_f32();
-    let victim_id = read_ref_id(r);
-    let criminal_id = read_ref_id(r);
-    let item_base_id = read_ref_id(r);
-    let ownership_id = read_ref_id(r);
-    let count = read_vsval_to_u32(r);
-    let witnesses = read_into_vec(r, count, |r| read_ref_id(r));
-    let bounty = r.read_u32();
-    let crime_faction_id = read_ref_id(r);
-    let is_cleared = match r.read_u8() {
+fn read_crime(r: &mut SaveFileReader) -> Result<Crime, ReaderError> {
+    let witness_num = r.read_u32()?;
+    let crime_type = read_crime_type(r)?;
+    let u1 = r.read_u8()?;
+    let quantity = r.read_u32()?;
+    let serial_num = r.read_u32()?;
+    let u2 = r.read_u8()?;
+    let u3 = r.read_u32()?;
+    let elapsed_time = r.read_f32()?;
+    let victim_id = read_ref_id(r)?;
+    let criminal_id = read_ref_id(r)?;
+    let item_base_id = read_ref_id(r)?;
+    let ownership_id = read_ref_id(r)?;
+    let count = read_vsval_to_u32(r)?;
+    let witnesses = read_into_vec(r, count, read_ref_id)?;
+    let bounty = r.read_u32()?;
+    let crime_faction_id = read_ref_id(r)?;
+    let index = r.get_index();
+    let is_cleared = match r.read_u8()? {
         0 => false,
         1 => true,
-        _ => {
-            println!("Found new value for isCleared on crime! Please report that and attach your savegame.");
+        value => {
+            r.recover_unknown_value(index, "isCleared of a crime", value as u32)?;
             true
         }
     };
-    let u4 = r.read_u16();
-    Crime {
+    let u4 = r.read_u16()?;
+    Ok(Crime {
         witness_num,
         crime_type,
         u1,
@@ -613,10 +1058,16 @@ fn read_crime(r: &mut SaveFileReader) -> Crime {
         crime_faction_id,
         is_cleared,
         u4,
+    })
+}
+
+impl Readable for Crime {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        read_crime(r)
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum CrimeType {
     Theft,
     Pickpocketing,
@@ -628,8 +1079,9 @@ pub enum CrimeType {
     Error,
 }
 
-fn convert_to_crime_type(num: u32) -> CrimeType {
-    match num {
+fn read_crime_type(r: &mut SaveFileReader) -> Result<CrimeType, ReaderError> {
+    let index = r.get_index();
+    Ok(match r.read_u32()? {
         0 => CrimeType::Theft,
         1 => CrimeType::Pickpocketing,
         2 => CrimeType::Trespassing,
@@ -637,14 +1089,14 @@ fn convert_to_crime_type(num: u32) -> CrimeType {
         4 => CrimeType::Murder,
         5 => CrimeType::Unknown5,
         6 => CrimeType::Lycanthropy,
-        _ => {
-            println!("Encountered unknown crimeType");
+        value => {
+            r.recover_unknown_value(index, "crimeType", value)?;
             CrimeType::Error
         }
-    }
+    })
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Interface {
     /// - 0xEC - HelpLockpickingShort
     /// - 0xEE - HelpSmithingShort
@@ -663,7 +1115,7 @@ pub struct Interface {
     /// - 0x100 - HelpFollowerCommandTutorial
     /// - 0x102 - HelpFavoritesPCShort
     ///
-    ///etc.
+    ///etc. See ```HelpMessage``` and ```shown_help_messages()```.
     pub shown_help_msg: Vec<u32>,
     pub u0: u8,
     pub last_used_weapons: Vec<FormIdType>,
@@ -675,20 +1127,97 @@ pub struct Interface {
     pub u2: Option<InterfaceUnknown0>,
 }
 
-fn read_interface(r: &mut SaveFileReader) -> Interface {
-    let shown_help_message_count = r.read_u32();
-    let shown_help_msg = read_u32s_into_vec(r, shown_help_message_count);
-    let u0 = r.read_u8();
-    let last_used_weapons_count = read_vsval_to_u32(r);
-    let last_used_weapons = read_ref_ids_into_vec(r, last_used_weapons_count);
-    let last_used_spells_count = read_vsval_to_u32(r);
-    let last_used_spells = read_ref_ids_into_vec(r, last_used_spells_count);
-    let last_used_shouts_count = read_vsval_to_u32(r);
-    let last_used_shouts = read_ref_ids_into_vec(r, last_used_shouts_count);
-    let u1 = r.read_u8();
+impl Interface {
+    /// The shown help messages, with the known ids mapped to their names.
+    pub fn shown_help_messages(&self) -> Vec<HelpMessage> {
+        self.shown_help_msg.iter().map(|id| HelpMessage::from(*id)).collect()
+    }
+}
+
+/// A tutorial help message that can be marked as shown in ```Interface```.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HelpMessage {
+    HelpLockpickingShort,
+    HelpSmithingShort,
+    HelpCookingPots,
+    HelpSmeltingShort,
+    HelpTanningShort,
+    HelpEnchantingShort,
+    HelpGrindstoneShort,
+    HelpArmorBenchShort,
+    HelpAlchemyShort,
+    HelpBarterShortPC,
+    HelpLevelingShort,
+    HelpWorldMapShortPC,
+    HelpJournalShortPC,
+    HelpJailTutorial,
+    HelpFollowerCommandTutorial,
+    HelpFavoritesPCShort,
+    Unknown(u32),
+}
+
+impl From<u32> for HelpMessage {
+    fn from(id: u32) -> Self {
+        match id {
+            0xEC => HelpMessage::HelpLockpickingShort,
+            0xEE => HelpMessage::HelpSmithingShort,
+            0xEF => HelpMessage::HelpCookingPots,
+            0xF0 => HelpMessage::HelpSmeltingShort,
+            0xF1 => HelpMessage::HelpTanningShort,
+            0xF3 => HelpMessage::HelpEnchantingShort,
+            0xF4 => HelpMessage::HelpGrindstoneShort,
+            0xF5 => HelpMessage::HelpArmorBenchShort,
+            0xF6 => HelpMessage::HelpAlchemyShort,
+            0xF7 => HelpMessage::HelpBarterShortPC,
+            0xF9 => HelpMessage::HelpLevelingShort,
+            0xFA => HelpMessage::HelpWorldMapShortPC,
+            0xFB => HelpMessage::HelpJournalShortPC,
+            0xFF => HelpMessage::HelpJailTutorial,
+            0x100 => HelpMessage::HelpFollowerCommandTutorial,
+            0x102 => HelpMessage::HelpFavoritesPCShort,
+            _ => HelpMessage::Unknown(id),
+        }
+    }
+}
+
+impl From<HelpMessage> for u32 {
+    fn from(message: HelpMessage) -> Self {
+        match message {
+            HelpMessage::HelpLockpickingShort => 0xEC,
+            HelpMessage::HelpSmithingShort => 0xEE,
+            HelpMessage::HelpCookingPots => 0xEF,
+            HelpMessage::HelpSmeltingShort => 0xF0,
+            HelpMessage::HelpTanningShort => 0xF1,
+            HelpMessage::HelpEnchantingShort => 0xF3,
+            HelpMessage::HelpGrindstoneShort => 0xF4,
+            HelpMessage::HelpArmorBenchShort => 0xF5,
+            HelpMessage::HelpAlchemyShort => 0xF6,
+            HelpMessage::HelpBarterShortPC => 0xF7,
+            HelpMessage::HelpLevelingShort => 0xF9,
+            HelpMessage::HelpWorldMapShortPC => 0xFA,
+            HelpMessage::HelpJournalShortPC => 0xFB,
+            HelpMessage::HelpJailTutorial => 0xFF,
+            HelpMessage::HelpFollowerCommandTutorial => 0x100,
+            HelpMessage::HelpFavoritesPCShort => 0x102,
+            HelpMessage::Unknown(id) => id,
+        }
+    }
+}
+
+fn read_interface(r: &mut SaveFileReader) -> Result<Interface, ReaderError> {
+    let shown_help_message_count = r.read_u32()?;
+    let shown_help_msg = read_u32s_into_vec(r, shown_help_message_count)?;
+    let u0 = r.read_u8()?;
+    let last_used_weapons_count = read_vsval_to_u32(r)?;
+    let last_used_weapons = read_ref_ids_into_vec(r, last_used_weapons_count)?;
+    let last_used_spells_count = read_vsval_to_u32(r)?;
+    let last_used_spells = read_ref_ids_into_vec(r, last_used_spells_count)?;
+    let last_used_shouts_count = read_vsval_to_u32(r)?;
+    let last_used_shouts = read_ref_ids_into_vec(r, last_used_shouts_count)?;
+    let u1 = r.read_u8()?;
     // This value is only there sometimes. Rather not risk overflowing the buffer.
     let u2 = None;
-    Interface {
+    Ok(Interface {
         shown_help_msg,
         u0,
         last_used_weapons,
@@ -696,54 +1225,60 @@ fn read_interface(r: &mut SaveFileReader) -> Interface {
         last_used_shouts,
         u1,
         u2,
+    })
+}
+
+impl Readable for Interface {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        read_interface(r)
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct InterfaceUnknown0 {
     pub unknown_0_0: Vec<InterfaceUnknown0_0>,
     pub unknown1: Vec<String>,
     pub unknown2: u32,
 }
 
-
-#[derive(Clone, Debug)]
+/// An entry of the last menu state in ```Interface::u2```, the meaning of the fields is unknown.
+#[derive(Clone, Debug, PartialEq)]
 pub struct InterfaceUnknown0_0 {
-    u0: String,
-    u1: String,
-    u2: u32,
-    u3: u32,
-    u4: u32,
-    u5: u32,
+    pub u0: String,
+    pub u1: String,
+    pub u2: u32,
+    pub u3: u32,
+    pub u4: u32,
+    pub u5: u32,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ActorCauses {
-    next_num: u32,
-    unknown: Vec<ActorCausesUnknown0>,
+    pub next_num: u32,
+    pub unknown: Vec<ActorCausesUnknown0>,
 }
 
 impl Parse for ActorCauses {
-    fn parse(r: &mut SaveFileReader) -> GlobalDataType {
-        let next_num = r.read_u32();
-        let count = read_vsval_to_u32(r);
+    fn parse(r: &mut SaveFileReader) -> Result<GlobalDataType, ReaderError> {
+        let next_num = r.read_u32()?;
+        let count = read_vsval_to_u32(r)?;
         let unknown = read_into_vec(r, count, |r| {
-            ActorCausesUnknown0 {
-                x: r.read_f32(),
-                y: r.read_f32(),
-                z: r.read_f32(),
-                serial_num: r.read_u32(),
-                actor_id: read_ref_id(r),
-            }
-        });
-        GlobalDataType::ActorCauses(ActorCauses {
+            Ok(ActorCausesUnknown0 {
+                x: r.read_f32()?,
+                y: r.read_f32()?,
+                z: r.read_f32()?,
+                serial_num: r.read_u32()?,
+                actor_id: read_ref_id(r)?,
+            })
+        })?;
+        Ok(GlobalDataType::ActorCauses(ActorCauses {
             next_num,
             unknown,
-        })
+        }))
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ActorCausesUnknown0 {
     pub x: f32,
     pub y: f32,
@@ -752,7 +1287,7 @@ pub struct ActorCausesUnknown0 {
     pub actor_id: FormIdType,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct DetectionManagerUnknown0 {
     pub u0: FormIdType,
     pub u1: u32,
@@ -760,41 +1295,41 @@ pub struct DetectionManagerUnknown0 {
 }
 
 impl Parse for DetectionManagerUnknown0 {
-    fn parse(r: &mut SaveFileReader) -> GlobalDataType {
-        let count = read_vsval_to_u32(r);
-        GlobalDataType::DetectionManager(
+    fn parse(r: &mut SaveFileReader) -> Result<GlobalDataType, ReaderError> {
+        let count = read_vsval_to_u32(r)?;
+        Ok(GlobalDataType::DetectionManager(
             read_into_vec(
                 r,
                 count,
-                |r| DetectionManagerUnknown0 {
-                    u0: read_ref_id(r),
-                    u1: r.read_u32(),
-                    u2: r.read_u32(),
-                }))
+                |r| Ok(DetectionManagerUnknown0 {
+                    u0: read_ref_id(r)?,
+                    u1: r.read_u32()?,
+                    u2: r.read_u32()?,
+                }))?))
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct LocationMetaDataUnknown0 {
     pub u0: FormIdType,
     pub u1: u32,
 }
 
 impl Parse for LocationMetaDataUnknown0 {
-    fn parse(r: &mut SaveFileReader) -> GlobalDataType {
-        let count = read_vsval_to_u32(r);
-        GlobalDataType::LocationMetaData(
+    fn parse(r: &mut SaveFileReader) -> Result<GlobalDataType, ReaderError> {
+        let count = read_vsval_to_u32(r)?;
+        Ok(GlobalDataType::LocationMetaData(
             read_into_vec(
                 r,
                 count,
-                |r| LocationMetaDataUnknown0 {
-                    u0: read_ref_id(r),
-                    u1: r.read_u32(),
-                }))
+                |r| Ok(LocationMetaDataUnknown0 {
+                    u0: read_ref_id(r)?,
+                    u1: r.read_u32()?,
+                }))?))
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct QuestStaticData {
     pub u0: Vec<QuestRunDataItem3>,
     pub u1: Vec<QuestRunDataItem3>,
@@ -802,26 +1337,37 @@ pub struct QuestStaticData {
     pub u3: Vec<FormIdType>,
     pub u4: Vec<FormIdType>,
     pub u5: Vec<QuestStaticDataUnknown0>,
+    /// Meaning unknown, UESP lists it as an unknown u8. See ```u6_bool()``` to read it as a flag.
     pub u6: u8,
+    /// Bytes following ```u6``` in the entry, not parsed.
+    /// Kept so the entry can be written back unchanged.
+    pub remainder: Vec<u8>,
+}
+
+impl QuestStaticData {
+    /// ```u6``` as flag, any value other than 0 is true.
+    pub fn u6_bool(&self) -> bool {
+        self.u6 != 0
+    }
 }
 
 impl Parse for QuestStaticData {
-    fn parse(r: &mut SaveFileReader) -> GlobalDataType {
-        let count = r.read_u32();
-        let u0 = read_into_vec(r, count, |r| read_quest_run_data_item_3(r));
-        let count1 = r.read_u32();
-        let u1 = read_into_vec(r, count1, |r| read_quest_run_data_item_3(r));
-        let count2 = r.read_u32();
-        let u2 = read_ref_ids_into_vec(r, count2);
-        let count3 = r.read_u32();
-        let u3 = read_ref_ids_into_vec(r, count3);
-        let count4 = r.read_u32();
-        let u4 = read_ref_ids_into_vec(r, count4);
-        let count5 = read_vsval_to_u32(r);
-        let u5 = read_into_vec(r, count5, |r| read_quest_static_data_unknown_0(r));
-        let u6 = r.read_u8();
-
-        GlobalDataType::QuestStaticData(QuestStaticData {
+    fn parse(r: &mut SaveFileReader) -> Result<GlobalDataType, ReaderError> {
+        let count = r.read_u32()?;
+        let u0 = read_into_vec(r, count, read_quest_run_data_item_3)?;
+        let count1 = r.read_u32()?;
+        let u1 = read_into_vec(r, count1, read_quest_run_data_item_3)?;
+        let count2 = r.read_u32()?;
+        let u2 = read_ref_ids_into_vec(r, count2)?;
+        let count3 = r.read_u32()?;
+        let u3 = read_ref_ids_into_vec(r, count3)?;
+        let count4 = r.read_u32()?;
+        let u4 = read_ref_ids_into_vec(r, count4)?;
+        let count5 = read_vsval_to_u32(r)?;
+        let u5 = read_into_vec(r, count5, read_quest_static_data_unknown_0)?;
+        let u6 = r.read_u8()?;
+
+        Ok(GlobalDataType::QuestStaticData(QuestStaticData {
             u0,
             u1,
             u2,
@@ -829,95 +1375,112 @@ impl Parse for QuestStaticData {
             u4,
             u5,
             u6,
-        })
+            // the entry is read from its own buffer, so this is only the rest of the entry
+            remainder: r.remaining().to_vec(),
+        }))
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct QuestRunDataItem3 {
     pub u1: u32,
     pub u2: f32,
     pub quest_run_data_item_3_data: Vec<QuestRunDataItem3DataType>,
 }
 
-fn read_quest_run_data_item_3(r: &mut SaveFileReader) -> QuestRunDataItem3 {
-    let u1 = r.read_u32();
-    let u2 = r.read_f32();
-    let count = r.read_u32();
-    let quest_run_data_item_3_data = read_into_vec(r, count, |r| read_quest_run_data_item_3_data_type(r));
-    QuestRunDataItem3 {
+fn read_quest_run_data_item_3(r: &mut SaveFileReader) -> Result<QuestRunDataItem3, ReaderError> {
+    let u1 = r.read_u32()?;
+    let u2 = r.read_f32()?;
+    let count = r.read_u32()?;
+    let quest_run_data_item_3_data = read_into_vec(r, count, read_quest_run_data_item_3_data_type)?;
+    Ok(QuestRunDataItem3 {
         u1,
         u2,
         quest_run_data_item_3_data,
-    }
+    })
 }
 
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum QuestRunDataItem3DataType {
     RefId(FormIdType),
     U32(u32),
 }
 
-fn read_quest_run_data_item_3_data_type(r: &mut SaveFileReader) -> QuestRunDataItem3DataType {
-    let data_type = r.read_u32();
+/// Only the types 1 to 4 are documented. The width of any other type (including 0) is unknown, so
+/// guessing it would silently desync the rest of the quest data. Instead this fails with
+/// ```ReaderError::UnknownDataType```; unless ```ParseOptions.recovery``` is strict the whole quest static data
+/// is then kept as raw bytes in ```GlobalDataType::UnknownType```.
+fn read_quest_run_data_item_3_data_type(r: &mut SaveFileReader) -> Result<QuestRunDataItem3DataType, ReaderError> {
+    let index = r.get_index();
+    let data_type = r.read_u32()?;
     match data_type {
-        3 => QuestRunDataItem3DataType::U32(r.read_u32()),
-        1 | 2 | 4 => QuestRunDataItem3DataType::RefId(read_ref_id(r)),
-        _ => {
-            println!("Encountered unknown questrundataitem3 type. Assuming refId");
-            QuestRunDataItem3DataType::RefId(read_ref_id(r))
-        }
+        3 => Ok(QuestRunDataItem3DataType::U32(r.read_u32()?)),
+        1 | 2 | 4 => Ok(QuestRunDataItem3DataType::RefId(read_ref_id(r)?)),
+        _ => Err(ReaderError::UnknownDataType { index, data_type }),
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct QuestStaticDataUnknown0 {
     pub unk0_0: FormIdType,
     pub u1: Vec<QuestStaticDataUnknown1>,
 }
 
-fn read_quest_static_data_unknown_0(r: &mut SaveFileReader) -> QuestStaticDataUnknown0 {
-    let unk0_0 = read_ref_id(r);
-    let count = read_vsval_to_u32(r);
-    let u1 = read_into_vec(r, count, |r| QuestStaticDataUnknown1 {
-        unk_1_0: r.read_u32(),
-        unk_1_1: r.read_u32(),
-    });
-    QuestStaticDataUnknown0 {
+fn read_quest_static_data_unknown_0(r: &mut SaveFileReader) -> Result<QuestStaticDataUnknown0, ReaderError> {
+    let unk0_0 = read_ref_id(r)?;
+    let count = read_vsval_to_u32(r)?;
+    let u1 = read_into_vec(r, count, |r| Ok(QuestStaticDataUnknown1 {
+        unk_1_0: r.read_u32()?,
+        unk_1_1: r.read_u32()?,
+    }))?;
+    Ok(QuestStaticDataUnknown0 {
         unk0_0,
         u1,
-    }
+    })
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct QuestStaticDataUnknown1 {
     pub unk_1_0: u32,
     pub unk_1_1: u32,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct MagicFavorites {
     /// Spells, shouts, abilities etc.
     pub favorited_magics: Vec<FormIdType>,
-    /// Hotkey corresponds to the position of magic in this array
+    /// Hotkey corresponds to the position of magic in this array.
+    /// The format stores no explicit slot numbers, use ```hot_key_slots()``` to get the mapping.
     pub magic_hot_keys: Vec<FormIdType>,
 }
 
+impl MagicFavorites {
+    /// Pairs each hotkey slot with the magic bound to it.
+    /// Slots are 0-based, so slot 0 is bound to hotkey 1. The slot is the index in ```magic_hot_keys```,
+    /// whose length is read from the save and not limited to the 8 hotkeys of the game.
+    pub fn hot_key_slots(&self) -> Vec<(usize, FormIdType)> {
+        self.magic_hot_keys.iter()
+            .enumerate()
+            .map(|(slot, magic)| (slot, *magic))
+            .collect()
+    }
+}
+
 impl Parse for MagicFavorites {
-    fn parse(r: &mut SaveFileReader) -> GlobalDataType {
-        let count0 = read_vsval_to_u32(r);
-        let favorited_magics = read_ref_ids_into_vec(r, count0);
-        let count1 = read_vsval_to_u32(r);
-        let magic_hot_keys = read_ref_ids_into_vec(r, count1);
-        GlobalDataType::MagicFavorites(MagicFavorites {
+    fn parse(r: &mut SaveFileReader) -> Result<GlobalDataType, ReaderError> {
+        let count0 = read_vsval_to_u32(r)?;
+        let favorited_magics = read_ref_ids_into_vec(r, count0)?;
+        let count1 = read_vsval_to_u32(r)?;
+        let magic_hot_keys = read_ref_ids_into_vec(r, count1)?;
+        Ok(GlobalDataType::MagicFavorites(MagicFavorites {
             favorited_magics,
             magic_hot_keys,
-        })
+        }))
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct StoryEventManager {
     pub u0: u32,
     /// Unknown format. Possibly the same as unk0 and unk1 in Quest Static Data
@@ -926,41 +1489,79 @@ pub struct StoryEventManager {
 }
 
 impl Parse for StoryEventManager {
-    fn parse(r: &mut SaveFileReader) -> GlobalDataType {
-        let u0 = r.read_u32();
-        let count = read_vsval_to_u32(r);
+    fn parse(r: &mut SaveFileReader) -> Result<GlobalDataType, ReaderError> {
+        let u0 = r.read_u32()?;
+        let count = read_vsval_to_u32(r)?;
 
-        GlobalDataType::StoryEventManager(StoryEventManager {
+        Ok(GlobalDataType::StoryEventManager(StoryEventManager {
             u0,
             u1: Vec::with_capacity(match count.try_into() {
                 Ok(x) => x,
-                Err(_) => usize::max_value()
+                Err(_) => usize::MAX
             }),
-        })
+        }))
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct IngredientsCombined {
     pub ingredient0: FormIdType,
     pub ingredient1: FormIdType,
 }
 
+impl IngredientsCombined {
+    /// The pair ordered so that (A, B) and (B, A) are equal.
+    pub fn normalized(&self) -> (FormIdType, FormIdType) {
+        match self.ingredient0 <= self.ingredient1 {
+            true => (self.ingredient0, self.ingredient1),
+            false => (self.ingredient1, self.ingredient0),
+        }
+    }
+
+    /// All combinations in ```data``` as normalized pairs, see ```normalized()```.
+    pub fn all(data: &[IngredientsCombined]) -> HashSet<(FormIdType, FormIdType)> {
+        data.iter().map(|combination| combination.normalized()).collect()
+    }
+
+    /// True if the two ingredients have been combined, in either order.
+    /// The ids are compared as stored, so both have to be of the same ```FormIdType``` variant as in the save.
+    pub fn contains(data: &[IngredientsCombined], ingredient0: FormIdType, ingredient1: FormIdType) -> bool {
+        let pair = IngredientsCombined { ingredient0, ingredient1 }.normalized();
+        data.iter().any(|combination| combination.normalized() == pair)
+    }
+}
+
 impl Parse for IngredientsCombined {
-    fn parse(r: &mut SaveFileReader) -> GlobalDataType {
-        let count = r.read_u32();
-        GlobalDataType::IngredientShared(
+    fn parse(r: &mut SaveFileReader) -> Result<GlobalDataType, ReaderError> {
+        let count = r.read_u32()?;
+        Ok(GlobalDataType::IngredientShared(
             read_into_vec(
                 r,
                 count,
-                |r| IngredientsCombined {
-                    ingredient0: read_ref_id(r),
-                    ingredient1: read_ref_id(r),
-                }))
+                |r| Ok(IngredientsCombined {
+                    ingredient0: read_ref_id(r)?,
+                    ingredient1: read_ref_id(r)?,
+                }))?))
     }
 }
 
-#[derive(Clone, Debug)]
+/// Global data type 113. Nothing is known about the two bytes, UESP lists them as unknown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MenuControls {
+    pub u0: u8,
+    pub u1: u8,
+}
+
+/// Global data type 1003. UESP lists the two bytes as unknown, their relation to timers is only suggested by the name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Timer {
+    pub u0: u8,
+    pub u1: u8,
+    /// Any bytes behind the two known ones, kept so the entry is written back unchanged.
+    pub remainder: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct AnimObject {
     /// RefID pointing to an actor reference.
     pub achr: FormIdType,
@@ -971,12 +1572,132 @@ pub struct AnimObject {
 }
 
 impl Parse for AnimObject {
-    fn parse(r: &mut SaveFileReader) -> GlobalDataType {
-        let count = r.read_u32();
-        GlobalDataType::AnimObjects(read_into_vec(r, count, |r| AnimObject {
-            achr: read_ref_id(r),
-            anim: read_ref_id(r),
-            u1: r.read_u8(),
-        }))
+    fn parse(r: &mut SaveFileReader) -> Result<GlobalDataType, ReaderError> {
+        let count = r.read_u32()?;
+        Ok(GlobalDataType::AnimObjects(read_into_vec(r, count, |r| Ok(AnimObject {
+            achr: read_ref_id(r)?,
+            anim: read_ref_id(r)?,
+            u1: r.read_u8()?,
+        }))?))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A crime with the given type and isCleared value, no witnesses and null references.
+    fn crime_bytes(crime_type: u32, is_cleared: u8) -> Vec<u8> {
+        let mut w = SaveFileWriter::new();
+        w.write_u32(0);
+        w.write_u32(crime_type);
+        w.write_u8(0);
+        w.write_u32(1);
+        w.write_u32(7);
+        w.write_u8(0);
+        w.write_u32(0);
+        w.write_f32(1.5);
+        for _ in 0..4 {
+            write_ref_id(&mut w, FormIdType::Index(0)).unwrap();
+        }
+        w.write_count(0, CountEncoding::VSVal).unwrap();
+        w.write_u32(40);
+        write_ref_id(&mut w, FormIdType::Index(0)).unwrap();
+        w.write_u8(is_cleared);
+        w.write_u16(0);
+        w.get_buffer()
     }
-}
\ No newline at end of file
+
+    fn read(bytes: &[u8], recovery: RecoveryStrategy) -> (Result<Crime, ReaderError>, Vec<ParseWarning>) {
+        let mut r = SaveFileReader::from_slice(bytes).with_recovery(recovery);
+        let crime = read_crime(&mut r);
+        (crime, r.take_warnings())
+    }
+
+    #[test]
+    fn ref_id_outline_needs_a_valid_count() {
+        let mut w = SaveFileWriter::new();
+        w.write_count(1, CountEncoding::VSVal).unwrap();
+        write_ref_id(&mut w, FormIdType::Index(5)).unwrap();
+        w.write_u8(9);
+        let outline = GlobalDataType::Unknown104(w.get_buffer()).ref_id_outline().unwrap();
+        assert_eq!(outline.ref_ids, vec![FormIdType::Index(5)]);
+        assert_eq!(outline.remainder, vec![9]);
+        assert_eq!(GlobalDataType::Unknown104(vec![0b11, 0, 0, 0]).ref_id_outline(), None);
+    }
+
+    #[test]
+    fn hot_key_slots_do_not_wrap() {
+        let favorites = MagicFavorites { favorited_magics: Vec::new(), magic_hot_keys: vec![FormIdType::Index(1); 300] };
+        let slots = favorites.hot_key_slots();
+        assert_eq!(slots[0], (0, FormIdType::Index(1)));
+        assert_eq!(slots[299].0, 299);
+    }
+
+    #[test]
+    fn reads_known_crimes_without_warnings() {
+        let (crime, warnings) = read(&crime_bytes(4, 1), RecoveryStrategy::Lenient);
+        let crime = crime.unwrap();
+        assert_eq!(crime.crime_type, CrimeType::Murder);
+        assert!(crime.is_cleared);
+        assert_eq!(crime.bounty, 40);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn unknown_is_cleared_is_handled_by_the_recovery_strategy() {
+        let bytes = crime_bytes(0, 2);
+        assert!(matches!(read(&bytes, RecoveryStrategy::Strict).0, Err(ReaderError::UnknownValue { value: 2, .. })));
+
+        let (crime, warnings) = read(&bytes, RecoveryStrategy::Lenient);
+        assert!(crime.unwrap().is_cleared);
+        assert_eq!(warnings, vec![ParseWarning::UnknownValue { field: "isCleared of a crime", value: 2 }]);
+
+        let (crime, warnings) = read(&bytes, RecoveryStrategy::Silent);
+        assert!(crime.is_ok());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn unknown_crime_type_is_handled_by_the_recovery_strategy() {
+        let bytes = crime_bytes(9, 0);
+        assert!(matches!(read(&bytes, RecoveryStrategy::Strict).0, Err(ReaderError::UnknownValue { index: 4, value: 9, .. })));
+
+        let (crime, warnings) = read(&bytes, RecoveryStrategy::Lenient);
+        assert_eq!(crime.unwrap().crime_type, CrimeType::Error);
+        assert_eq!(warnings, vec![ParseWarning::UnknownValue { field: "crimeType", value: 9 }]);
+
+        let (crime, warnings) = read(&bytes, RecoveryStrategy::Silent);
+        assert!(crime.is_ok());
+        assert!(warnings.is_empty());
+    }
+
+    fn weather_bytes(flags: u8, remainder: &[u8]) -> Vec<u8> {
+        let mut w = SaveFileWriter::new();
+        for id in [0x81A, 0x10A241, 0, 0, 0, 0] {
+            write_ref_id(&mut w, FormIdType::Default(id)).unwrap();
+        }
+        for value in [13.5, 12.0, 1.0] {
+            w.write_f32(value);
+        }
+        for _ in 0..6 {
+            w.write_u32(0);
+        }
+        w.write_f32(0.0);
+        w.write_u32(0);
+        w.write_u8(flags);
+        w.write_bytes(remainder);
+        w.get_buffer()
+    }
+
+    #[test]
+    fn weather_keeps_the_flagged_fields_as_bytes() {
+        let weather = read_weather(&mut SaveFileReader::from_slice(&weather_bytes(0, &[]))).unwrap();
+        assert_eq!((weather.weather, weather.cur_time), (FormIdType::Default(0x10A241), 13.5));
+        assert!(weather.remainder.is_empty());
+
+        let flags = WeatherFlags::HAS_U9.0 | WeatherFlags::HAS_U10.0;
+        let weather = read_weather(&mut SaveFileReader::from_slice(&weather_bytes(flags, &[1, 2, 3, 4, 5]))).unwrap();
+        assert!(weather.flags_typed().contains(WeatherFlags::HAS_U9) && weather.flags_typed().contains(WeatherFlags::HAS_U10));
+        assert_eq!(weather.remainder, [1, 2, 3, 4, 5]);
+    }
+}
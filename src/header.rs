@@ -1,10 +1,59 @@
 use crate::fundamental_types::FileTime;
-use crate::reader::{SaveFileReader, read_filetime};
+use crate::reader::{SaveFileReader, read_filetime, ReaderError, Readable};
+use crate::writer::{SaveFileWriter, write_filetime};
+use crate::SaveWriteError;
 use std::fmt::{Display, Formatter};
+use std::path::Path;
 
-#[derive(Clone, Debug)]
+/// The first save version containing the ```compression_type``` field (Skyrim Special Edition).
+/// Older saves are always uncompressed.
+pub const COMPRESSION_TYPE_MIN_VERSION: u32 = 12;
+
+/// The newest save version written by the original Skyrim (Legendary Edition).
+pub const LEGENDARY_EDITION_MAX_VERSION: u32 = 9;
+
+/// The oldest save version written by a released version of Skyrim, see ```read_header()```.
+pub const OLDEST_KNOWN_VERSION: u32 = 7;
+
+/// The edition of Skyrim a save was made with.
+///
+/// - ```LegendaryEdition```: version <= 9 (```LEGENDARY_EDITION_MAX_VERSION```). These saves have no
+///   compression type and no body lengths in front of the body, which is always uncompressed.
+/// - ```SpecialEdition```: version >= 12 (```COMPRESSION_TYPE_MIN_VERSION```) with compression type
+///   0 (none), 1 (zlib) or 2 (LZ4). The game writes LZ4, but uncompressed saves exist as well.
+/// - ```VirtualReality```: Skyrim VR, which writes the same format as the Special Edition it is based on.
+///   Its saves can't be told apart by the header, only by ```SKYRIM_VR_MASTER``` in the plugin list,
+///   so only ```SaveFile::edition()``` returns it. VR predates light plugins, its saves have a form version
+///   below ```LIGHT_PLUGIN_MIN_FORM_VERSION``` and no light plugin list.
+/// - ```Unknown```: versions 10 and 11 and unknown compression types.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkyrimEdition {
+    LegendaryEdition,
+    SpecialEdition,
+    VirtualReality,
+    Unknown,
+}
+
+/// The master file Skyrim VR loads after Skyrim.esm and the official DLCs, see ```SkyrimEdition```.
+pub const SKYRIM_VR_MASTER: &str = "SkyrimVR.esm";
+
+/// How a save was made, see ```Header::save_kind()```.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SaveKind {
+    Manual,
+    Autosave,
+    Quicksave,
+    /// Made when quitting the game.
+    Exitsave,
+    /// Not named by the game's conventions, e.g. renamed by a save manager.
+    Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Header {
     pub version: u32,
+    /// Counts up with every save of a playthrough. Ordering the saves of one character by it gives the
+    /// order they were made in, unlike file times, which change when the files are copied.
     pub save_number: u32,
     pub player_name: String,
     pub player_level: u32,
@@ -17,49 +66,319 @@ pub struct Header {
     pub filetime: FileTime,
     pub shot_width: u32,
     pub shot_height: u32,
+    /// 0 for saves without the field, see ```COMPRESSION_TYPE_MIN_VERSION```.
     pub compression_type: u16,
 }
 
-#[derive(Clone, Copy, Debug)]
+/// The in-game time of a save, parsed from ```Header::game_date```.
+/// Ordering compares days first, so saves can be sorted by in-game progression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GameDate {
+    pub days: u32,
+    pub hours: u8,
+    pub minutes: u8,
+}
+
+impl Header {
+    /// Parses ```game_date``` in any language, e.g. "Day 12, 14:30", "Day 12, 2:30 PM", "Tag 12, 14:30"
+    /// or the numeric "012.14.30": the three numbers are taken as day, hour and minute in this order,
+    /// whatever words are around them. Returns None if there aren't exactly three numbers or they are out of range.
+    pub fn parse_game_date(&self) -> Option<GameDate> {
+        parse_game_date(&self.game_date)
+    }
+
+    /// The race of the player, parsed from ```player_race_editor_id```.
+    /// The vampire variants (e.g. "NordRaceVampire") map to their base race.
+    pub fn race(&self) -> PlayerRace {
+        PlayerRace::from_editor_id(&self.player_race_editor_id)
+    }
+
+    /// Classifies the save by its file name, as the kind of a save is not stored in the save itself.
+    /// ```file_name``` may be a path. Manual saves are named "Save<number>..." by the game, the number
+    /// has to match ```save_number```, otherwise the file was renamed and the kind is unknown.
+    pub fn save_kind(&self, file_name: &str) -> SaveKind {
+        let name = Path::new(file_name).file_name().and_then(|name| name.to_str()).unwrap_or(file_name);
+        let name = name.to_ascii_lowercase();
+        if name.starts_with("autosave") {
+            return SaveKind::Autosave;
+        }
+        if name.starts_with("quicksave") {
+            return SaveKind::Quicksave;
+        }
+        if name.starts_with("exitsave") {
+            return SaveKind::Exitsave;
+        }
+        // SE names manual saves "Save12_...", LE "Save 12 - ..."
+        let number: String = match name.strip_prefix("save") {
+            Some(rest) => rest.trim_start().chars().take_while(|c| c.is_ascii_digit()).collect(),
+            None => return SaveKind::Unknown,
+        };
+        match number.parse::<u32>() {
+            Ok(number) if number == self.save_number => SaveKind::Manual,
+            _ => SaveKind::Unknown,
+        }
+    }
+
+    /// Whether the version was written by a released version of the game, so the layout of the
+    /// header is known, see ```read_header()```.
+    pub fn is_known_version(&self) -> bool {
+        matches!(self.version, OLDEST_KNOWN_VERSION..=LEGENDARY_EDITION_MAX_VERSION | COMPRESSION_TYPE_MIN_VERSION)
+    }
+
+    /// Infers the edition of the game the save was made with, see ```SkyrimEdition```.
+    /// Never returns ```VirtualReality```, VR saves are ```SpecialEdition``` here, use ```SaveFile::edition()```.
+    pub fn edition(&self) -> SkyrimEdition {
+        match self.version {
+            0..=LEGENDARY_EDITION_MAX_VERSION => SkyrimEdition::LegendaryEdition,
+            COMPRESSION_TYPE_MIN_VERSION.. if self.compression_type <= 2 => SkyrimEdition::SpecialEdition,
+            _ => SkyrimEdition::Unknown,
+        }
+    }
+}
+
+fn parse_game_date(game_date: &str) -> Option<GameDate> {
+    let game_date = game_date.trim();
+    // the words around the numbers depend on the language of the game, only the order of the numbers doesn't
+    let numbers: Vec<&str> = game_date.split(|c: char| !c.is_ascii_digit()).filter(|number| !number.is_empty()).collect();
+    let (days, mut hours, minutes): (u32, u8, u8) = match numbers[..] {
+        [days, hours, minutes] => (days.parse().ok()?, hours.parse().ok()?, minutes.parse().ok()?),
+        _ => return None,
+    };
+    let upper = game_date.to_ascii_uppercase();
+    let half = match (upper.ends_with("AM"), upper.ends_with("PM")) {
+        (true, _) => Some(0),
+        (_, true) => Some(12),
+        _ => None,
+    };
+    if let Some(offset) = half {
+        if hours == 0 || hours > 12 {
+            return None;
+        }
+        hours = hours % 12 + offset;
+    }
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(GameDate { days, hours, minutes })
+}
+
+/// The playable races of the base game, see ```Header::race()```.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PlayerRace {
+    Argonian,
+    Breton,
+    DarkElf,
+    HighElf,
+    Imperial,
+    Khajiit,
+    Nord,
+    Orc,
+    Redguard,
+    WoodElf,
+    /// A race added by a mod, contains the editor id.
+    Custom(String),
+}
+
+impl PlayerRace {
+    /// Maps a race editor id like "NordRace" or "NordRaceVampire" to the race.
+    pub fn from_editor_id(editor_id: &str) -> PlayerRace {
+        let base = editor_id.strip_suffix("Vampire").unwrap_or(editor_id);
+        match base {
+            "ArgonianRace" => PlayerRace::Argonian,
+            "BretonRace" => PlayerRace::Breton,
+            "DarkElfRace" => PlayerRace::DarkElf,
+            "HighElfRace" => PlayerRace::HighElf,
+            "ImperialRace" => PlayerRace::Imperial,
+            "KhajiitRace" => PlayerRace::Khajiit,
+            "NordRace" => PlayerRace::Nord,
+            "OrcRace" => PlayerRace::Orc,
+            "RedguardRace" => PlayerRace::Redguard,
+            "WoodElfRace" => PlayerRace::WoodElf,
+            _ => PlayerRace::Custom(editor_id.to_string()),
+        }
+    }
+
+    /// The name shown in game, the editor id for custom races.
+    pub fn name(&self) -> &str {
+        match self {
+            PlayerRace::Argonian => "Argonian",
+            PlayerRace::Breton => "Breton",
+            PlayerRace::DarkElf => "Dark Elf",
+            PlayerRace::HighElf => "High Elf",
+            PlayerRace::Imperial => "Imperial",
+            PlayerRace::Khajiit => "Khajiit",
+            PlayerRace::Nord => "Nord",
+            PlayerRace::Orc => "Orc",
+            PlayerRace::Redguard => "Redguard",
+            PlayerRace::WoodElf => "Wood Elf",
+            PlayerRace::Custom(editor_id) => editor_id,
+        }
+    }
+}
+
+impl Display for PlayerRace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The sex of the player, stored as u16 (0 male, 1 female).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PlayerSex {
     Male,
-    Female
+    Female,
+    /// A value other than 0 or 1, e.g. written by a mod. Kept so the save can be written back unchanged.
+    Unknown(u16),
 }
 
 impl From<u16> for PlayerSex {
     fn from(x: u16) -> Self {
-       match x {
-           0 => PlayerSex::Male,
-           1 => PlayerSex::Female,
-           _ => {
-               println!("Error while reading playerSex");
-               PlayerSex::Female
-           }
-       }
+        match x {
+            0 => PlayerSex::Male,
+            1 => PlayerSex::Female,
+            _ => PlayerSex::Unknown(x),
+        }
+    }
+}
+
+impl From<PlayerSex> for u16 {
+    fn from(x: PlayerSex) -> Self {
+        match x {
+            PlayerSex::Male => 0,
+            PlayerSex::Female => 1,
+            PlayerSex::Unknown(x) => x,
+        }
     }
 }
 
 impl Display for PlayerSex {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&*format!("{:?}", self))
-    }
-}
-
-pub fn read_header(sfr: &mut SaveFileReader) -> Header {
-    Header {
-        version: sfr.read_u32(),
-        save_number: sfr.read_u32(),
-        player_name: sfr.read_w_string().content,
-        player_level: sfr.read_u32(),
-        player_location: sfr.read_w_string().content,
-        game_date: sfr.read_w_string().content,
-        player_race_editor_id: sfr.read_w_string().content,
-        player_sex: sfr.read_u16().into(),
-        player_cur_exp: sfr.read_f32(),
-        player_lvl_up_exp: sfr.read_f32(),
-        filetime: read_filetime(sfr),
-        shot_width: sfr.read_u32(),
-        shot_height: sfr.read_u32(),
-        compression_type: sfr.read_u16(),
-    }
-}
\ No newline at end of file
+        f.write_str(&format!("{:?}", self))
+    }
+}
+
+/// Reads the header, after the magic and the header size.
+///
+/// The layout is the one of UESP's save file format page
+/// (<https://en.uesp.net/wiki/Skyrim_Mod:Save_File_Format>), which lists the same fields for all versions
+/// and marks only ```compressionType``` as Special Edition only, so it is read from
+/// ```COMPRESSION_TYPE_MIN_VERSION``` on. Headers older than ```OLDEST_KNOWN_VERSION``` are read with
+/// the same layout, check ```Header::is_known_version()``` before trusting them.
+pub fn read_header(sfr: &mut SaveFileReader) -> Result<Header, ReaderError> {
+    let version = sfr.read_u32()?;
+    Ok(Header {
+        version,
+        save_number: sfr.read_u32()?,
+        player_name: sfr.read_w_string()?.content,
+        player_level: sfr.read_u32()?,
+        player_location: sfr.read_w_string()?.content,
+        game_date: sfr.read_w_string()?.content,
+        player_race_editor_id: sfr.read_w_string()?.content,
+        player_sex: sfr.read_u16()?.into(),
+        player_cur_exp: sfr.read_f32()?,
+        player_lvl_up_exp: sfr.read_f32()?,
+        filetime: read_filetime(sfr)?,
+        shot_width: sfr.read_u32()?,
+        shot_height: sfr.read_u32()?,
+        compression_type: match version >= COMPRESSION_TYPE_MIN_VERSION {
+            true => sfr.read_u16()?,
+            false => 0,
+        },
+    })
+}
+
+impl Readable for Header {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        read_header(r)
+    }
+}
+
+/// Writes the header, the inverse of ```read_header()```.
+/// The compression type is only written for save versions that contain it.
+pub fn write_header(writer: &mut SaveFileWriter, header: &Header) -> Result<(), SaveWriteError> {
+    writer.write_u32(header.version);
+    writer.write_u32(header.save_number);
+    writer.write_w_string(&header.player_name)?;
+    writer.write_u32(header.player_level);
+    writer.write_w_string(&header.player_location)?;
+    writer.write_w_string(&header.game_date)?;
+    writer.write_w_string(&header.player_race_editor_id)?;
+    writer.write_u16(header.player_sex.into());
+    writer.write_f32(header.player_cur_exp);
+    writer.write_f32(header.player_lvl_up_exp);
+    write_filetime(writer, &header.filetime);
+    writer.write_u32(header.shot_width);
+    writer.write_u32(header.shot_height);
+    if header.version >= COMPRESSION_TYPE_MIN_VERSION {
+        writer.write_u16(header.compression_type);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A header of ```version``` written field by field, independent of ```write_header()```.
+    fn header_bytes(version: u32) -> Vec<u8> {
+        let mut w = SaveFileWriter::new();
+        w.write_u32(version);
+        w.write_u32(12);
+        w.write_w_string("Lydia").unwrap();
+        w.write_u32(5);
+        w.write_w_string("Whiterun").unwrap();
+        w.write_w_string("000.01.30").unwrap();
+        w.write_w_string("NordRace").unwrap();
+        w.write_u16(1);
+        w.write_f32(10.0);
+        w.write_f32(250.0);
+        w.write_u32(1);
+        w.write_u32(2);
+        w.write_u32(320);
+        w.write_u32(192);
+        if version >= COMPRESSION_TYPE_MIN_VERSION {
+            w.write_u16(2);
+        }
+        w.get_buffer()
+    }
+
+    #[test]
+    fn reads_legendary_edition_headers() {
+        for version in [OLDEST_KNOWN_VERSION, LEGENDARY_EDITION_MAX_VERSION] {
+            let bytes = header_bytes(version);
+            let mut sfr = SaveFileReader::from_slice(&bytes);
+            let header = read_header(&mut sfr).unwrap();
+            assert!(sfr.remaining().is_empty());
+            assert_eq!(header.version, version);
+            assert_eq!(header.save_number, 12);
+            assert_eq!(header.player_name, "Lydia");
+            assert_eq!(header.player_level, 5);
+            assert_eq!(header.player_race_editor_id, "NordRace");
+            assert_eq!(header.player_lvl_up_exp, 250.0);
+            assert_eq!((header.shot_width, header.shot_height), (320, 192));
+            assert_eq!(header.compression_type, 0);
+            assert_eq!(header.edition(), SkyrimEdition::LegendaryEdition);
+            assert!(header.is_known_version());
+        }
+    }
+
+    #[test]
+    fn reads_compression_type_from_special_edition_headers() {
+        let bytes = header_bytes(COMPRESSION_TYPE_MIN_VERSION);
+        let mut sfr = SaveFileReader::from_slice(&bytes);
+        let header = read_header(&mut sfr).unwrap();
+        assert!(sfr.remaining().is_empty());
+        assert_eq!(header.compression_type, 2);
+    }
+
+    #[test]
+    fn writes_headers_back() {
+        for version in [OLDEST_KNOWN_VERSION, LEGENDARY_EDITION_MAX_VERSION, COMPRESSION_TYPE_MIN_VERSION] {
+            let bytes = header_bytes(version);
+            let header = read_header(&mut SaveFileReader::from_slice(&bytes)).unwrap();
+            let mut w = SaveFileWriter::new();
+            write_header(&mut w, &header).unwrap();
+            assert_eq!(w.get_buffer(), bytes);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! A quick verdict whether a save looks sound, e.g. for a traffic light per save in a save manager.
+
+use crate::*;
+
+/// The overall verdict of a ```SaveHealth```.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HealthStatus {
+    /// The save parsed without any problem.
+    Good,
+    /// The save parsed, but parts of it had to be recovered or don't agree with each other.
+    Suspicious,
+    /// The save could not be parsed.
+    Broken,
+}
+
+/// The result of ```quick_health_check()```. Checks after a failed one are false, as they weren't reached.
+#[derive(Clone, Debug)]
+pub struct SaveHealth {
+    /// Whether the file starts with ```SAVE_MAGIC```.
+    pub magic_ok: bool,
+    /// Whether the body could be decompressed, or read completely if it is not compressed.
+    pub decompression_ok: bool,
+    /// Whether the sections of the body start at the offsets stored in the file location table.
+    pub offsets_consistent: bool,
+    /// The number of global data entries that couldn't be parsed and were kept unparsed.
+    pub recovered_errors: usize,
+    /// All warnings of the parse, including the recovered errors.
+    pub warnings: Vec<ParseWarning>,
+    /// The error that stopped the parse, None if the save could be parsed.
+    pub error: Option<SaveParseError>,
+}
+
+impl SaveHealth {
+    pub fn status(&self) -> HealthStatus {
+        match self.error {
+            Some(_) => HealthStatus::Broken,
+            None if !self.offsets_consistent || !self.warnings.is_empty() => HealthStatus::Suspicious,
+            None => HealthStatus::Good,
+        }
+    }
+}
+
+/// Parses ```buf``` with ```RecoveryStrategy::Lenient``` and sums up how sound the save looks.
+/// Never panics, whatever ```buf``` contains.
+pub fn quick_health_check(buf: &[u8]) -> SaveHealth {
+    let mut health = SaveHealth {
+        magic_ok: is_skyrim_save(buf),
+        decompression_ok: false,
+        offsets_consistent: false,
+        recovered_errors: 0,
+        warnings: Vec::new(),
+        error: None,
+    };
+    let options = ParseOptions { recovery: RecoveryStrategy::Lenient, ..ParseOptions::default() };
+    match parse_save_file_with_options(buf, &options) {
+        Ok(save) => {
+            health.decompression_ok = true;
+            health.offsets_consistent = save.section_offsets_consistent();
+            health.recovered_errors = save.warnings.iter()
+                .filter(|warning| matches!(warning, ParseWarning::UnparsedGlobalData { .. }))
+                .count();
+            health.warnings = save.warnings;
+        }
+        Err(e) => {
+            // only read the body again to tell whether the parse failed before or after it
+            let mut sfr = SaveFileReader::from_slice(buf);
+            health.decompression_ok = read_preamble(&mut sfr)
+                .and_then(|preamble| read_body(&sfr, &preamble.header, preamble.uncompressed_len).map(|_| ()))
+                .is_ok();
+            health.error = Some(e);
+        }
+    }
+    health
+}
+
+impl SaveFile {
+    /// Whether the sections of the body start at the offsets in the file location table, compared
+    /// relative to global data table 1 like ```check_section_offset()``` does.
+    fn section_offsets_consistent(&self) -> bool {
+        let table = &self.file_location_table;
+        let sizes = &self.section_sizes;
+        let base = table.global_data_table_1_offset as u64;
+        let global_data_table_2 = base + sizes.global_data_table_1 as u64;
+        let change_forms = global_data_table_2 + sizes.global_data_table_2 as u64;
+        let global_data_table_3 = change_forms + sizes.change_forms as u64;
+        let form_id_array = global_data_table_3 + sizes.global_data_table_3 as u64;
+        let unknown_3_table = form_id_array + (sizes.form_id_array + sizes.visited_worldspace_array) as u64;
+        [
+            (table.global_data_table_2_offset, global_data_table_2),
+            (table.change_forms_offset, change_forms),
+            (table.global_data_table_3_offset, global_data_table_3),
+            (table.form_id_array_count_offset, form_id_array),
+            (table.unknown_table_3_offset, unknown_3_table),
+        ].iter().all(|(offset, actual)| *offset as u64 == *actual)
+    }
+}
@@ -0,0 +1,61 @@
+//! A JSON dump of a save in the layout community tools read, enabled by the ```serde``` feature.
+//!
+//! The schema, version 1:
+//! ```text
+//! {
+//!   "format": "ess-json", "format_version": 1,
+//!   "header": { "version", "save_number", "player_name", "player_level", "player_location",
+//!               "game_date", "player_race", "player_sex", "player_cur_exp", "player_lvl_up_exp",
+//!               "screenshot_width", "screenshot_height", "compression_type", "form_version" },
+//!   "plugins": [ "Skyrim.esm", ... ],
+//!   "light_plugins": [ ... ],
+//!   "change_forms": [ { "form_id": "0001A332", "type": "NPC_", "change_flags": 3, "version": 74, "length": 120 }, ... ]
+//! }
+//! ```
+//! Form ids are 8 hex digits, ```null``` if they can't be resolved. The ```length``` of a change form
+//! is the size of its uncompressed data. Binary data (screenshot, change form data) is not included.
+
+use crate::{ChangeFormType, SaveFile};
+use serde_json::{json, Value};
+
+/// Increased on changes of the schema that existing readers can't handle.
+pub const INTEROP_FORMAT_VERSION: u32 = 1;
+
+impl SaveFile {
+    /// The save as JSON in the interop layout described in the ```interop``` module.
+    pub fn to_interop_json(&self) -> Value {
+        let header = &self.header;
+        let change_forms: Vec<Value> = self.change_forms.iter().map(|change_form| {
+            json!({
+                "form_id": self.resolve_form_id(change_form.form_id).map(|id| format!("{:08X}", id)),
+                "type": ChangeFormType::from_data_type(change_form.data_type).name(),
+                "change_flags": change_form.change_flags,
+                "version": change_form.version,
+                "length": change_form.data.len(),
+            })
+        }).collect();
+        json!({
+            "format": "ess-json",
+            "format_version": INTEROP_FORMAT_VERSION,
+            "header": {
+                "version": header.version,
+                "save_number": header.save_number,
+                "player_name": header.player_name,
+                "player_level": header.player_level,
+                "player_location": header.player_location,
+                "game_date": header.game_date,
+                "player_race": header.player_race_editor_id,
+                "player_sex": header.player_sex.to_string(),
+                "player_cur_exp": header.player_cur_exp,
+                "player_lvl_up_exp": header.player_lvl_up_exp,
+                "screenshot_width": header.shot_width,
+                "screenshot_height": header.shot_height,
+                "compression_type": header.compression_type,
+                "form_version": self.form_version,
+            },
+            "plugins": self.plugin_info,
+            "light_plugins": self.light_plugin_info,
+            "change_forms": change_forms,
+        })
+    }
+}
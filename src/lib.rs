@@ -1,5 +1,9 @@
-use lz4_flex::decompress;
+use flate2::read::ZlibDecoder;
+use lz4_flex::{decompress, decompress_into};
+use std::borrow::Cow;
 use std::fmt;
+use std::io::Read;
+use std::ops::{Range, RangeInclusive};
 
 pub mod global_data;
 
@@ -13,6 +17,14 @@ pub mod change_form;
 
 pub use change_form::*;
 
+pub mod change_form_type;
+
+pub use change_form_type::*;
+
+pub mod change_form_data;
+
+pub use change_form_data::*;
+
 pub mod reader;
 
 pub use reader::*;
@@ -20,10 +32,73 @@ pub use reader::*;
 pub mod header;
 use header::*;
 
-#[derive(Clone)]
+pub mod error;
+
+pub use error::*;
+
+pub mod writer;
+
+pub use writer::*;
+
+mod report;
+
+mod patch;
+
+mod validate;
+
+pub mod view;
+
+pub use view::*;
+
+pub mod papyrus;
+
+pub use papyrus::*;
+
+pub mod spans;
+
+pub use spans::*;
+
+pub mod streaming;
+
+pub use streaming::*;
+
+pub mod plugin_diff;
+
+pub use plugin_diff::*;
+
+pub mod health;
+
+pub use health::*;
+
+pub mod reference_graph;
+
+pub use reference_graph::*;
+
+#[cfg(feature = "serde")]
+pub mod interop;
+
+#[cfg(feature = "serde")]
+pub use interop::*;
+
+/// The magic every save starts with.
+pub const SAVE_MAGIC: &str = "TESV_SAVEGAME";
+
+/// Oldest form version with light plugins (Special Edition 1.5.3), older saves don't store the light plugin count.
+pub const LIGHT_PLUGIN_MIN_FORM_VERSION: u8 = 78;
+/// Form version of Skyrim 1.9, the last patch of the Legendary Edition, as listed on UESP.
+pub const MIN_KNOWN_FORM_VERSION: u8 = 74;
+/// Form version of the Special Edition from 1.5.3 on, see ```LIGHT_PLUGIN_MIN_FORM_VERSION```.
+pub const MAX_KNOWN_FORM_VERSION: u8 = LIGHT_PLUGIN_MIN_FORM_VERSION;
+/// The form versions of the body whose layout is known. Older patches of the Legendary Edition write
+/// lower versions, which aren't documented.
+pub const KNOWN_FORM_VERSIONS: RangeInclusive<u8> = MIN_KNOWN_FORM_VERSION..=MAX_KNOWN_FORM_VERSION;
+
+#[derive(Clone, PartialEq)]
 pub struct ScreenshotData {
     pub height: u32,
     pub width: u32,
+    pub channel_order: ChannelOrder,
+    /// The pixels row by row, with the channels in ```channel_order```.
     pub data: Vec<u8>,
 }
 
@@ -32,20 +107,89 @@ impl std::fmt::Debug for ScreenshotData {
         f.debug_struct("Screenshot Data")
             .field("height", &self.height)
             .field("width", &self.width)
+            .field("channel_order", &self.channel_order)
             .field("Size in bytes", &self.data.len())
             .finish()
     }
 }
 
+impl ScreenshotData {
+    /// The pixels as RGBA, e.g. for PNG export. Screenshots without alpha channel get an opaque one.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        match self.channel_order {
+            ChannelOrder::Rgba => self.data.clone(),
+            ChannelOrder::Rgb => self.data.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 0xFF]).collect(),
+        }
+    }
+
+    /// The pixels as BGRA, the layout some image APIs (e.g. Windows bitmaps) expect.
+    pub fn to_bgra(&self) -> Vec<u8> {
+        let mut data = self.to_rgba();
+        data.chunks_exact_mut(4).for_each(|p| p.swap(0, 2));
+        data
+    }
+}
+
+/// The layout of the screenshot pixels, which depends on the save version.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelOrder {
+    /// 3 bytes per pixel, used by the original Skyrim (versions < 12).
+    Rgb,
+    /// 4 bytes per pixel, used by Skyrim Special Edition (versions >= 12, ```COMPRESSION_TYPE_MIN_VERSION```).
+    /// The alpha channel is usually fully opaque.
+    Rgba,
+}
+
+impl ChannelOrder {
+    pub fn for_version(version: u32) -> ChannelOrder {
+        match version >= COMPRESSION_TYPE_MIN_VERSION {
+            true => ChannelOrder::Rgba,
+            false => ChannelOrder::Rgb,
+        }
+    }
+
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            ChannelOrder::Rgb => 3,
+            ChannelOrder::Rgba => 4,
+        }
+    }
+}
+
+/// Writes the screenshot pixel data. The dimensions are part of the header and written by ```write_header()```.
+pub fn write_screenshot(writer: &mut SaveFileWriter, screenshot: &ScreenshotData) -> Result<(), SaveWriteError> {
+    let expected = screenshot.channel_order.bytes_per_pixel() * screenshot.width as usize * screenshot.height as usize;
+    if screenshot.data.len() != expected {
+        return Err(SaveWriteError::ScreenshotSizeMismatch { expected, actual: screenshot.data.len() });
+    }
+    writer.write_bytes(&screenshot.data);
+    Ok(())
+}
+
+/// Writes the magic, the header size and the header, the size is computed from the written header.
+pub fn write_file_header(writer: &mut SaveFileWriter, header: &Header) -> Result<(), SaveWriteError> {
+    let mut header_writer = SaveFileWriter::new();
+    write_header(&mut header_writer, header)?;
+    writer.write_bytes(SAVE_MAGIC.as_bytes());
+    writer.write_u32(header_writer.get_buffer_len() as u32);
+    writer.write_bytes(&header_writer.get_buffer());
+    Ok(())
+}
+
 #[derive(Clone)]
 pub struct SaveFile {
     pub magic: String,
     pub header: Header,
     pub screenshot_data: ScreenshotData,
+    /// Saves older than ```COMPRESSION_TYPE_MIN_VERSION``` don't store the body lengths,
+    /// for them this is the size of the rest of the file and ```body_compressed_len``` is 0.
     pub body_uncompressed_len: u32,
     pub body_compressed_len: u32,
     pub form_version: u8,
+    /// The names of the full plugins, in the order of the save. Together with ```light_plugin_info```
+    /// the two lists as stored, see ```plugins()``` for both with their indices.
     pub plugin_info: Vec<String>,
+    /// The names of the light plugins, empty for saves older than ```LIGHT_PLUGIN_MIN_FORM_VERSION```.
     pub light_plugin_info: Vec<String>,
     pub file_location_table: FileLocationTable,
     pub global_data_table_1: Vec<GlobalDataType>,
@@ -55,6 +199,13 @@ pub struct SaveFile {
     pub form_id_array: Vec<u32>,
     pub visited_worldspace_array: Vec<u32>,
     pub unknown_3_table: Vec<String>,
+    /// Bytes of the body behind ```unknown_3_table```, e.g. metadata appended by other tools.
+    /// Empty for saves written by the game, kept so it is written back.
+    pub trailing_bytes: Vec<u8>,
+    /// Byte sizes of the body sections, recorded while parsing.
+    pub section_sizes: SectionSizes,
+    /// Problems that didn't stop the parse, empty for saves that parsed cleanly.
+    pub warnings: Vec<ParseWarning>,
 }
 
 impl fmt::Debug for SaveFile {
@@ -74,11 +225,66 @@ impl fmt::Debug for SaveFile {
             .field("form_id_array (length)", &self.form_id_array.len())
             .field("visited_worldspace_array (length)", &self.visited_worldspace_array.len())
             .field("unknown_3_table (length)", &self.unknown_3_table.len())
+            .field("trailing_bytes (length)", &self.trailing_bytes.len())
+            .field("section_sizes", &self.section_sizes)
+            .field("warnings", &self.warnings)
             .finish()
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// The position of the player, see ```SaveFile::player_position()```.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayerPosition {
+    /// The worldspace containing the cell (```cell_x```, ```cell_y```), usually 0 in interiors.
+    /// None if the form id can't be resolved.
+    pub world_space: Option<u32>,
+    pub cell_x: i32,
+    pub cell_y: i32,
+    /// The worldspace or interior cell the player is in, None if the form id can't be resolved.
+    pub location: Option<u32>,
+    /// The position in ```location```.
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+/// The separately owned parts of a ```SaveFile```, see ```SaveFile::into_parts()```.
+#[derive(Debug, Clone)]
+pub struct SaveFileParts {
+    pub metadata: SaveFileMetadata,
+    pub header: Header,
+    pub screenshot_data: ScreenshotData,
+    pub tables: SaveFileTables,
+    pub change_forms: Vec<ChangeForm>,
+}
+
+/// Information about the file layout of a save.
+#[derive(Debug, Clone)]
+pub struct SaveFileMetadata {
+    pub magic: String,
+    pub body_uncompressed_len: u32,
+    pub body_compressed_len: u32,
+    pub form_version: u8,
+    pub file_location_table: FileLocationTable,
+    pub section_sizes: SectionSizes,
+    pub warnings: Vec<ParseWarning>,
+}
+
+/// All tables of the body, except for the change forms.
+#[derive(Debug, Clone)]
+pub struct SaveFileTables {
+    pub plugin_info: Vec<String>,
+    pub light_plugin_info: Vec<String>,
+    pub global_data_table_1: Vec<GlobalDataType>,
+    pub global_data_table_2: Vec<GlobalDataType>,
+    pub global_data_table_3: Vec<GlobalDataType>,
+    pub form_id_array: Vec<u32>,
+    pub visited_worldspace_array: Vec<u32>,
+    pub unknown_3_table: Vec<String>,
+    pub trailing_bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct FileLocationTable {
     pub form_id_array_count_offset: u32,
     pub unknown_table_3_offset: u32,
@@ -90,73 +296,389 @@ pub struct FileLocationTable {
     pub global_data_table_2_count: u32,
     pub global_data_table_3_count: u32,
     pub change_form_count: u32,
+    /// The unused space at the end of the table. Usually zero, but kept for writing the table back.
+    pub reserved: [u32; 15],
+}
+
+/// Sizes in bytes of the sections in the uncompressed body.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SectionSizes {
+    pub plugin_info: usize,
+    pub file_location_table: usize,
+    pub global_data_table_1: usize,
+    pub global_data_table_2: usize,
+    pub change_forms: usize,
+    pub global_data_table_3: usize,
+    pub form_id_array: usize,
+    pub visited_worldspace_array: usize,
+    pub unknown_3_table: usize,
 }
 
+/// Overview of how much space the parts of a save take up, see ```SaveFile::size_report()```.
+#[derive(Debug, Clone, Copy)]
+pub struct SizeReport {
+    pub compressed_body_len: u32,
+    pub uncompressed_body_len: u32,
+    pub screenshot_len: usize,
+    pub sections: SectionSizes,
+}
 
-pub fn parse_save_file(buf: Vec<u8>) -> SaveFile {
-    let mut sfr = SaveFileReader::new(buf);
-    let magic = sfr.read_string(13);
-    if magic != "TESV_SAVEGAME".to_string() {
-        panic!("File invalid or corrupted, could not read magic.")
+impl SizeReport {
+    /// Ratio of compressed to uncompressed body size. 1.0 for uncompressed saves.
+    pub fn compression_ratio(&self) -> f64 {
+        if self.uncompressed_body_len == 0 || self.compressed_body_len == 0 {
+            return 1.0;
+        }
+        self.compressed_body_len as f64 / self.uncompressed_body_len as f64
     }
 
-    let _header_size = sfr.read_u32();
+    /// Share of a section size in the uncompressed body, from 0.0 to 1.0.
+    pub fn share_of_body(&self, section_size: usize) -> f64 {
+        if self.uncompressed_body_len == 0 {
+            return 0.0;
+        }
+        section_size as f64 / self.uncompressed_body_len as f64
+    }
+}
 
-    let header = read_header(&mut sfr);
+impl SaveFile {
+    /// Resolves a form id stored in this save to the full 32-bit form id, see ```FormIdType::resolve()```.
+    pub fn resolve_form_id(&self, form_id: FormIdType) -> Option<u32> {
+        form_id.resolve(&self.form_id_array)
+    }
 
-    let screenshot_data = sfr.read_bytes_to_vec((4 * header.shot_width * header.shot_height) as usize);
+    /// The number of the save, see ```Header::save_number```.
+    pub fn save_number(&self) -> u32 {
+        self.header.save_number
+    }
 
-    let uncompressed_len = sfr.read_u32();
-    let compressed_len = sfr.read_u32();
+    /// Guesses whether both saves are of the same character, e.g. to group the saves in a save manager.
+    /// The save doesn't store an id of the character, so the player name and the race (see ```Header::race()```)
+    /// are compared. Different characters with the same name and race can't be told apart.
+    ///
+    /// The save numbers are not compared: loading an older save and saving again continues with its
+    /// number, so the saves of one character don't have to form a single ascending line.
+    pub fn same_character_as(&self, other: &SaveFile) -> bool {
+        self.header.player_name == other.header.player_name && self.header.race() == other.header.race()
+    }
 
-    let body_buffer = read_body(sfr, &header, uncompressed_len);
-    let mut sfr_body = SaveFileReader::new(body_buffer);
+    /// Infers the edition of the game the save was made with, see ```SkyrimEdition```.
+    /// Unlike ```Header::edition()```, VR saves are recognized by their plugin list.
+    pub fn edition(&self) -> SkyrimEdition {
+        match self.header.edition() {
+            SkyrimEdition::SpecialEdition if self.plugin_info.iter().any(|plugin| plugin.eq_ignore_ascii_case(SKYRIM_VR_MASTER)) => {
+                SkyrimEdition::VirtualReality
+            }
+            edition => edition,
+        }
+    }
 
-    let form_version = sfr_body.read_u8();
+    /// The player location from the global data tables, if present.
+    pub fn player_location(&self) -> Option<&PlayerLocation> {
+        self.global_data_table_1.iter().find_map(|data| match data {
+            GlobalDataType::PlayerLocation(location) => Some(location),
+            _ => None,
+        })
+    }
 
-    let _plugin_info_size = sfr_body.read_u32();
-    let plugin_count = sfr_body.read_u8();
-    let plugin_info = read_strings_into_vec(&mut sfr_body, plugin_count as u32);
-    let light_plugin_count = sfr_body.read_u16();
-    let light_plugin_info = read_strings_into_vec(&mut sfr_body, light_plugin_count as u32);
+    /// The misc stat named ```name``` (e.g. "Days Passed"), compared ignoring ASCII case. None if the save doesn't have it.
+    ///
+    /// The save has no stat of the real time played: the time stats of ```MiscStatCategory::General```
+    /// ("Days Passed", "Hours Slept", "Hours Waiting") count in-game time, like ```Header::game_date```.
+    pub fn misc_stat(&self, name: &str) -> Option<&MiscStats> {
+        self.global_data_table_1.iter()
+            .filter_map(|data| match data {
+                GlobalDataType::MiscStats(stats) => Some(stats),
+                _ => None,
+            })
+            .flatten()
+            .find(|stat| stat.name.eq_ignore_ascii_case(name))
+    }
 
-    let file_location_table = read_file_location_table(&mut sfr_body);
+    /// Where the player is, with the form ids of the ```PlayerLocation``` resolved.
+    /// None if the save has no player location.
+    pub fn player_position(&self) -> Option<PlayerPosition> {
+        let location = self.player_location()?;
+        Some(PlayerPosition {
+            world_space: self.resolve_form_id(location.world_space_1),
+            cell_x: location.coor_x,
+            cell_y: location.coor_y,
+            location: self.resolve_form_id(location.world_space_2),
+            x: location.pos_x,
+            y: location.pos_y,
+            z: location.pos_z,
+        })
+    }
 
-    // file location table has some unused space at the end, we need to advance to the data afterwards
-    sfr_body.read_bytes_to_vec(4 * 15);
+    fn player_location_mut(&mut self) -> Option<&mut PlayerLocation> {
+        self.global_data_table_1.iter_mut().find_map(|data| match data {
+            GlobalDataType::PlayerLocation(location) => Some(location),
+            _ => None,
+        })
+    }
 
-    let global_data_table_1 = read_global_data(&mut sfr_body, file_location_table.global_data_table_1_count);
+    /// Removes personal information before sharing the save: blanks the player name and location
+    /// in the header and replaces the screenshot by a solid gray image of the same dimensions.
+    /// Everything needed to debug parsing stays untouched, which includes the name of the player
+    /// in the data of the NPC_ change form.
+    pub fn anonymize(&mut self) {
+        self.header.player_name = String::new();
+        self.header.player_location = String::new();
+        let gray: &[u8] = match self.screenshot_data.channel_order {
+            ChannelOrder::Rgb => &[0x80, 0x80, 0x80],
+            ChannelOrder::Rgba => &[0x80, 0x80, 0x80, 0xFF],
+        };
+        let pixel_count = self.screenshot_data.data.len() / gray.len();
+        self.screenshot_data.data = gray.repeat(pixel_count);
+    }
 
+    /// The change form of the form with the full form id ```full_form_id``` (e.g. 0x0001A332 from the
+    /// Creation Kit), if the save contains one. The form ids of the change forms are resolved
+    /// with ```resolve_form_id()```, so plugin indices are taken into account.
+    pub fn change_form_by_id(&self, full_form_id: u32) -> Option<&ChangeForm> {
+        self.change_forms.iter().find(|change_form| self.resolve_form_id(change_form.form_id) == Some(full_form_id))
+    }
 
-    let global_data_table_2 = read_global_data(&mut sfr_body, file_location_table.global_data_table_2_count);
+    /// Renames the plugin (or light plugin) ```old``` to ```new```, e.g. after a mod renamed its plugin.
+    /// Form ids refer to plugins by their index, so nothing else has to change.
+    /// Names are compared ignoring ASCII case, like the game does.
+    pub fn rename_plugin(&mut self, old: &str, new: &str) -> Result<(), EditError> {
+        if !old.eq_ignore_ascii_case(new) && self.has_plugin(new) {
+            return Err(EditError::DuplicatePlugin(new.to_string()));
+        }
+        let plugin = self.plugin_info.iter_mut()
+            .chain(self.light_plugin_info.iter_mut())
+            .find(|plugin| plugin.eq_ignore_ascii_case(old))
+            .ok_or_else(|| EditError::PluginNotFound(old.to_string()))?;
+        *plugin = new.to_string();
+        Ok(())
+    }
 
-    let change_forms = read_change_forms(&mut sfr_body, file_location_table.change_form_count);
+    /// The raw id (without the 0xFF prefix) the game will assign to the next created object.
+    pub fn next_object_id(&self) -> Option<u32> {
+        self.player_location().map(|location| location.next_object_id)
+    }
 
-    // We need to add 1 to the global data table 3 count as that is the actual value, known bug in Skyrim
-    let global_data_table_3 = read_global_data(&mut sfr_body, file_location_table.global_data_table_3_count + 1);
+    /// Returns the next object id and increments the stored counter, so the id can be used for a
+    /// new created object without colliding with the objects the game creates.
+    /// Returns None if the save has no player location or all ids are used up, as a created
+    /// RefID only holds 22 bits.
+    pub fn allocate_object_id(&mut self) -> Option<u32> {
+        let location = self.player_location_mut()?;
+        let id = location.next_object_id;
+        if id >= 1 << 22 {
+            return None;
+        }
+        location.next_object_id += 1;
+        Some(id)
+    }
 
-    let form_id_array_count = sfr_body.read_u32();
-    let form_id_array: Vec<u32> = read_u32s_into_vec(&mut sfr_body, form_id_array_count);
+    /// The full form id (0xFFxxxxxx) the game will assign to the next created object.
+    /// Tools adding created objects to the save should use ```allocate_object_id()```.
+    pub fn next_created_form_id(&self) -> Option<u32> {
+        self.next_object_id().map(|id| CREATED_FORM_ID_PREFIX | id)
+    }
 
-    let visited_worldspace_array_count = sfr_body.read_u32();
-    let visited_worldspace_array = read_u32s_into_vec(&mut sfr_body, visited_worldspace_array_count);
+    /// Iterates over all ref ids in the global data tables and change forms.
+    /// Ids referenced multiple times are returned multiple times.
+    pub fn all_ref_ids(&self) -> impl Iterator<Item = FormIdType> + '_ {
+        self.global_data_table_1.iter()
+            .chain(&self.global_data_table_2)
+            .chain(&self.global_data_table_3)
+            .flat_map(|data| data.ref_ids())
+            .chain(self.change_forms.iter().map(|form| form.form_id))
+    }
 
-    let _unknown_3_table_size = sfr_body.read_u32();
-    let unknown_3_table_count = sfr_body.read_u32();
-    let unknown_3_table = read_strings_into_vec(&mut sfr_body, unknown_3_table_count);
+    /// A cursor behind the forms in ```change_forms```, to read the others with ```read_change_forms_from()```,
+    /// e.g. after parsing with ```ParseOptions::max_change_forms```. ```body``` is the uncompressed body of this
+    /// save, the forms already read are skipped by their headers.
+    pub fn change_form_cursor(&self, body: &[u8]) -> Result<ChangeFormCursor, SaveParseError> {
+        let sizes = &self.section_sizes;
+        let mut sfr = SaveFileReader::from_slice(body);
+        // the form version is in front of the plugin info
+        sfr.set_index(1 + sizes.plugin_info + sizes.file_location_table + sizes.global_data_table_1 + sizes.global_data_table_2)?;
+        scan_change_form_headers(&mut sfr, self.change_forms.len() as u32)?;
+        Ok(ChangeFormCursor {
+            offset: sfr.get_index(),
+            read: self.change_forms.len() as u32,
+            count: self.file_location_table.change_form_count,
+        })
+    }
 
-    let screenshot_height = header.shot_height;
-    let screenshot_width = header.shot_width;
+    /// Splits the save into separately owned parts, so unneeded ones (like the screenshot) can be dropped early.
+    pub fn into_parts(self) -> SaveFileParts {
+        SaveFileParts {
+            metadata: SaveFileMetadata {
+                magic: self.magic,
+                body_uncompressed_len: self.body_uncompressed_len,
+                body_compressed_len: self.body_compressed_len,
+                form_version: self.form_version,
+                file_location_table: self.file_location_table,
+                section_sizes: self.section_sizes,
+                warnings: self.warnings,
+            },
+            header: self.header,
+            screenshot_data: self.screenshot_data,
+            tables: SaveFileTables {
+                plugin_info: self.plugin_info,
+                light_plugin_info: self.light_plugin_info,
+                global_data_table_1: self.global_data_table_1,
+                global_data_table_2: self.global_data_table_2,
+                global_data_table_3: self.global_data_table_3,
+                form_id_array: self.form_id_array,
+                visited_worldspace_array: self.visited_worldspace_array,
+                unknown_3_table: self.unknown_3_table,
+                trailing_bytes: self.trailing_bytes,
+            },
+            change_forms: self.change_forms,
+        }
+    }
+
+    pub fn size_report(&self) -> SizeReport {
+        SizeReport {
+            compressed_body_len: self.body_compressed_len,
+            uncompressed_body_len: self.body_uncompressed_len,
+            screenshot_len: self.screenshot_data.data.len(),
+            sections: self.section_sizes,
+        }
+    }
+}
 
+/// Options controlling how a save file is parsed, see ```parse_save_file_with_options()```.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// What happens on malformed data: global data of an unknown type or whose data can't be parsed
+    /// (kept as ```GlobalDataType::UnknownType``` when recovered), invalid vsvals and strings, and a
+    /// form id array not at its offset in the file location table.
+    /// Lenient by default, the warnings are collected in ```SaveFile::warnings```.
+    pub recovery: RecoveryStrategy,
+    /// How strings with a length exceeding their section are handled.
+    pub string_mode: StringMode,
+    /// The largest uncompressed body size accepted, checked before the body is decompressed.
+    /// None accepts any size, set a limit when parsing saves from untrusted sources.
+    pub max_body_size: Option<usize>,
+    /// The most change forms read into ```SaveFile::change_forms```, e.g. to preview a huge save quickly.
+    /// The forms behind are skipped by their headers, ```FileLocationTable::change_form_count``` still
+    /// has the total and ```ParseWarning::TruncatedChangeForms``` is recorded. None reads all, a truncated save
+    /// fails ```SaveFile::validate()``` and can't be written.
+    pub max_change_forms: Option<usize>,
+}
 
-    SaveFile {
+/// Parses a save file with the default ```ParseOptions```.
+pub fn parse_save_file(buf: Vec<u8>) -> Result<SaveFile, SaveParseError> {
+    parse_save_file_with_options(&buf, &ParseOptions::default())
+}
+
+/// Checks only whether ```buf``` starts with ```SAVE_MAGIC```, e.g. to find saves in a directory
+/// without parsing every file. ```buf``` only has to contain the first 13 bytes of the file.
+pub fn is_skyrim_save(buf: &[u8]) -> bool {
+    buf.starts_with(SAVE_MAGIC.as_bytes())
+}
+
+/// Parses only the header, without touching the screenshot or the body.
+/// ```buf``` only has to contain the start of the file: the magic, the header size and the header.
+pub fn parse_header_only(buf: &[u8]) -> Result<Header, SaveParseError> {
+    let mut sfr = SaveFileReader::from_slice(buf);
+    Ok(read_magic_and_header(&mut sfr)?.1)
+}
+
+pub fn parse_save_file_with_options(buf: &[u8], options: &ParseOptions) -> Result<SaveFile, SaveParseError> {
+    let mut sfr = SaveFileReader::from_slice(buf).with_string_mode(options.string_mode).with_recovery(options.recovery);
+    let Preamble { magic, header, screenshot_data, uncompressed_len, compressed_len } = read_preamble(&mut sfr)?;
+    if let Some(limit) = options.max_body_size.filter(|limit| uncompressed_len as usize > *limit) {
+        return Err(SaveParseError::BodyTooLarge { requested: uncompressed_len as usize, limit });
+    }
+
+    // the warnings of the header, the reader is borrowed by the body from here on
+    let mut warnings = sfr.take_warnings();
+    let body_buffer = read_body(&sfr, &header, uncompressed_len)?;
+    let mut sfr_body = SaveFileReader::from_slice(&body_buffer).with_string_mode(options.string_mode).with_recovery(options.recovery);
+
+    let form_version = sfr_body.read_u8()?;
+    if !KNOWN_FORM_VERSIONS.contains(&form_version) {
+        warnings.push(ParseWarning::UnknownFormVersion(form_version));
+    }
+
+    let mut section_sizes = SectionSizes::default();
+    let mut section_start = sfr_body.get_index();
+
+    let (plugin_info, light_plugin_info) = read_plugin_info(&mut sfr_body, form_version)?;
+    section_sizes.plugin_info = end_section(&sfr_body, &mut section_start);
+
+    let file_location_table = read_file_location_table(&mut sfr_body)?;
+    section_sizes.file_location_table = end_section(&sfr_body, &mut section_start);
+    let data_start = sfr_body.get_index();
+
+    let global_data_table_1 = read_global_data_with_warnings(&mut sfr_body, file_location_table.global_data_table_1_count, options, &mut warnings)?;
+    section_sizes.global_data_table_1 = end_section(&sfr_body, &mut section_start);
+
+    let global_data_table_2 = read_global_data_with_warnings(&mut sfr_body, file_location_table.global_data_table_2_count, options, &mut warnings)?;
+    section_sizes.global_data_table_2 = end_section(&sfr_body, &mut section_start);
+
+    let change_form_count = match options.max_change_forms {
+        Some(max) if max < file_location_table.change_form_count as usize => max as u32,
+        _ => file_location_table.change_form_count,
+    };
+    #[cfg(not(feature = "rayon"))]
+    let change_forms = read_change_forms(&mut sfr_body, change_form_count)?;
+    #[cfg(feature = "rayon")]
+    let change_forms = read_change_forms_parallel(&mut sfr_body, change_form_count)?;
+    scan_change_form_headers(&mut sfr_body, file_location_table.change_form_count - change_form_count)?;
+    if change_form_count < file_location_table.change_form_count {
+        warnings.push(ParseWarning::TruncatedChangeForms { read: change_form_count, count: file_location_table.change_form_count });
+    }
+    section_sizes.change_forms = end_section(&sfr_body, &mut section_start);
+
+    // We need to add 1 to the global data table 3 count as that is the actual value, known bug in Skyrim
+    let global_data_table_3 = read_global_data_with_warnings(&mut sfr_body, file_location_table.global_data_table_3_count.saturating_add(1), options, &mut warnings)?;
+    section_sizes.global_data_table_3 = end_section(&sfr_body, &mut section_start);
+
+    let offset_check = check_section_offset(&file_location_table, "form id array", file_location_table.form_id_array_count_offset,
+                                            data_start, sfr_body.get_index());
+    match (offset_check, options.recovery) {
+        (Err(e), RecoveryStrategy::Strict) => return Err(e),
+        (Err(SaveParseError::SectionOffsetMismatch { section, expected, actual }), RecoveryStrategy::Lenient) => {
+            warnings.push(ParseWarning::SectionOffsetMismatch { section, expected, actual });
+        }
+        _ => {}
+    }
+    let form_id_array_count = sfr_body.read_u32()?;
+    let form_id_array: Vec<u32> = read_u32s_into_vec(&mut sfr_body, form_id_array_count)?;
+    section_sizes.form_id_array = end_section(&sfr_body, &mut section_start);
+
+    let visited_worldspace_array_count = sfr_body.read_u32()?;
+    let visited_worldspace_array = read_u32s_into_vec(&mut sfr_body, visited_worldspace_array_count)?;
+    section_sizes.visited_worldspace_array = end_section(&sfr_body, &mut section_start);
+
+    // the size includes the count
+    let unknown_3_table_size = sfr_body.read_u32()?;
+    let unknown_3_table_count = sfr_body.read_u32()?;
+    let unknown_3_table = match unknown_3_table_count {
+        // some saves pad the empty table to a non-zero size, the padding is skipped to end at the end of the body
+        0 => {
+            sfr_body.read_bytes(unknown_3_table_size.saturating_sub(4) as usize)?;
+            Vec::new()
+        }
+        _ => read_sized_array(
+            &mut sfr_body,
+            unknown_3_table_size.saturating_sub(4),
+            unknown_3_table_count,
+            |r| Ok(r.read_w_string()?.content),
+        )?,
+    };
+    section_sizes.unknown_3_table = end_section(&sfr_body, &mut section_start);
+
+    // the warnings of the global data are already collected, these are the ones of the other sections
+    warnings.append(&mut sfr_body.take_warnings());
+    let trailing_bytes = sfr_body.remaining().to_vec();
+    if !trailing_bytes.is_empty() {
+        warnings.push(ParseWarning::TrailingBytes(trailing_bytes.len()));
+    }
+
+    Ok(SaveFile {
         magic,
         header,
-        screenshot_data: ScreenshotData {
-            height: screenshot_height,
-            width: screenshot_width,
-            data: screenshot_data,
-        },
+        screenshot_data,
         body_uncompressed_len: uncompressed_len,
         body_compressed_len: compressed_len,
         form_version,
@@ -170,40 +692,405 @@ pub fn parse_save_file(buf: Vec<u8>) -> SaveFile {
         form_id_array,
         visited_worldspace_array,
         unknown_3_table,
+        trailing_bytes,
+        section_sizes,
+        warnings,
+    })
+}
+
+/// Parses only the global data entries of type ```type_id``` from all three tables, e.g. 3 for the
+/// global variables. The other entries are skipped by their length and the change forms by their
+/// headers, nothing else is decoded or decompressed.
+pub fn extract_global_data(buf: &[u8], type_id: u32) -> Result<Vec<GlobalDataType>, SaveParseError> {
+    let mut sfr = SaveFileReader::from_slice(buf);
+    let preamble = read_preamble(&mut sfr)?;
+    let body_buffer = read_body(&sfr, &preamble.header, preamble.uncompressed_len)?;
+    let mut sfr_body = SaveFileReader::from_slice(&body_buffer);
+    let form_version = sfr_body.read_u8()?;
+    read_plugin_info(&mut sfr_body, form_version)?;
+    let file_location_table = read_file_location_table(&mut sfr_body)?;
+
+    let mut entries = read_global_data_of_type(&mut sfr_body, file_location_table.global_data_table_1_count, type_id)?;
+    entries.extend(read_global_data_of_type(&mut sfr_body, file_location_table.global_data_table_2_count, type_id)?);
+    for _i in 0..file_location_table.change_form_count {
+        let header = read_change_form_header(&mut sfr_body)?;
+        sfr_body.read_bytes(header.length1 as usize)?;
     }
+    // see parse_save_file_with_options() for the + 1
+    entries.extend(read_global_data_of_type(&mut sfr_body, file_location_table.global_data_table_3_count.saturating_add(1), type_id)?);
+    Ok(entries)
+}
+
+/// Reads only the headers of the change forms, skipping the global data and never decompressing
+/// change form data. Much faster than a full parse when only form ids, types and flags are needed.
+pub fn scan_change_forms(buf: &[u8]) -> Result<Vec<ChangeFormHeader>, SaveParseError> {
+    let mut sfr = SaveFileReader::from_slice(buf);
+    let preamble = read_preamble(&mut sfr)?;
+    let body_buffer = read_body(&sfr, &preamble.header, preamble.uncompressed_len)?;
+    let mut sfr_body = SaveFileReader::from_slice(&body_buffer);
+    let file_location_table = skip_to_change_forms(&mut sfr_body)?;
+    scan_change_form_headers(&mut sfr_body, file_location_table.change_form_count)
+}
+
+/// Locates the change forms in the uncompressed ```body``` (see ```decompress_body()```) without
+/// decompressing them, for tools that list the forms and decode single ones on demand with
+/// ```decode_change_form()```. The body has to be kept, as the entries only store positions in it.
+pub fn index_change_forms(body: &[u8]) -> Result<Vec<ChangeFormIndexEntry>, SaveParseError> {
+    let mut sfr_body = SaveFileReader::from_slice(body);
+    let file_location_table = skip_to_change_forms(&mut sfr_body)?;
+    index_change_form_headers(&mut sfr_body, file_location_table.change_form_count)
 }
 
+/// Moves the reader from the start of the body to the change forms, skipping the global data without parsing it.
+fn skip_to_change_forms(sfr_body: &mut SaveFileReader) -> Result<FileLocationTable, SaveParseError> {
+    let form_version = sfr_body.read_u8()?;
+    read_plugin_info(sfr_body, form_version)?;
+
+    let file_location_table = read_file_location_table(sfr_body)?;
+
+    skip_global_data(sfr_body, file_location_table.global_data_table_1_count)?;
+    skip_global_data(sfr_body, file_location_table.global_data_table_2_count)?;
+    Ok(file_location_table)
+}
+
+/// Reads the header and decompresses the body of a save file, returning the uncompressed body.
+/// No part of the body is parsed, so this can be used to inspect the raw body data.
+pub fn decompress_body(buf: &[u8]) -> Result<Vec<u8>, SaveParseError> {
+    let mut sfr = SaveFileReader::from_slice(buf);
+    let preamble = read_preamble(&mut sfr)?;
+    Ok(read_body(&sfr, &preamble.header, preamble.uncompressed_len)?.into_owned())
+}
+
+/// Everything in front of the body.
+struct Preamble {
+    magic: String,
+    header: Header,
+    screenshot_data: ScreenshotData,
+    uncompressed_len: u32,
+    compressed_len: u32,
+}
 
-fn read_body(sfr: SaveFileReader, header: &Header, uncompressed_len: u32) -> Vec<u8> {
-    let index = sfr.get_index();
-    let buffer = sfr.get_buffer();
-    let buffer_len = buffer.len();
+fn read_preamble(sfr: &mut SaveFileReader) -> Result<Preamble, SaveParseError> {
+    let layout = read_preamble_layout(sfr)?;
+    let screenshot_data = ScreenshotData {
+        height: layout.header.shot_height,
+        width: layout.header.shot_width,
+        channel_order: ChannelOrder::for_version(layout.header.version),
+        data: sfr.sub_reader(layout.screenshot.clone())?.remaining().to_vec(),
+    };
+
+    Ok(Preamble {
+        magic: layout.magic,
+        header: layout.header,
+        screenshot_data,
+        uncompressed_len: layout.uncompressed_len,
+        compressed_len: layout.compressed_len,
+    })
+}
+
+/// Where the parts in front of the body are, see ```read_preamble_layout()```.
+struct PreambleLayout {
+    magic: String,
+    header: Header,
+    /// The header without the magic and the header size.
+    header_range: Range<usize>,
+    screenshot: Range<usize>,
+    uncompressed_len: u32,
+    compressed_len: u32,
+}
+
+/// Reads everything in front of the body like ```read_preamble()```, but without copying the screenshot.
+/// ```sfr``` is left at the start of the body.
+fn read_preamble_layout(sfr: &mut SaveFileReader) -> Result<PreambleLayout, SaveParseError> {
+    let header_start = sfr.get_index() + SAVE_MAGIC.len() + 4;
+    let (magic, header) = read_magic_and_header(sfr)?;
+    let header_end = sfr.get_index();
+
+    let screenshot_len = screenshot_len(&header, sfr.remaining().len())?;
+    sfr.read_bytes(screenshot_len)?;
+
+    // saves older than the compression type have no body lengths, the body is the rest of the file
+    let (uncompressed_len, compressed_len) = match header.version >= COMPRESSION_TYPE_MIN_VERSION {
+        true => (sfr.read_u32()?, sfr.read_u32()?),
+        false => (sfr.remaining().len() as u32, 0),
+    };
+
+    Ok(PreambleLayout {
+        magic,
+        header,
+        header_range: header_start..header_end,
+        screenshot: header_end..header_end + screenshot_len,
+        uncompressed_len,
+        compressed_len,
+    })
+}
+
+/// The size of the screenshot in bytes, checked against the ```remaining``` bytes of the file,
+/// as corrupted dimensions could overflow the size or point past the end of the file.
+fn screenshot_len(header: &Header, remaining: usize) -> Result<usize, SaveParseError> {
+    ChannelOrder::for_version(header.version).bytes_per_pixel().checked_mul(header.shot_width as usize)
+        .and_then(|len| len.checked_mul(header.shot_height as usize))
+        .filter(|len| *len <= remaining)
+        .ok_or(SaveParseError::InvalidScreenshotDimensions {
+            width: header.shot_width,
+            height: header.shot_height,
+        })
+}
 
-    let range = std::ops::Range { start: index, end: buffer_len };
+fn read_magic_and_header(sfr: &mut SaveFileReader) -> Result<(String, Header), SaveParseError> {
+    let magic = sfr.read_string(SAVE_MAGIC.len()).map_err(|_| SaveParseError::InvalidMagic)?;
+    if magic != SAVE_MAGIC {
+        return Err(SaveParseError::InvalidMagic);
+    }
+
+    let _header_size = sfr.read_u32()?;
+
+    Ok((magic, read_header(sfr)?))
+}
+
+/// Reads the names of the plugins and the light plugins, which have to fill the plugin info exactly.
+/// The plugin count is checked against the size of the plugin info, so a corrupted count fails early.
+///
+/// The plugin count is a u8, as the game loads at most 255 full plugins (index 0xFF is reserved for
+/// created forms), while the light plugin count is a u16, as up to 4096 light plugins share index 0xFE.
+/// Saves older than ```LIGHT_PLUGIN_MIN_FORM_VERSION``` have no light plugins at all.
+fn read_plugin_info(sfr: &mut SaveFileReader, form_version: u8) -> Result<(Vec<String>, Vec<String>), SaveParseError> {
+    let plugin_info_size = sfr.read_u32()?;
+    read_sized(sfr, plugin_info_size, |r| {
+        let plugin_count = r.read_u8()?;
+        // every plugin name takes at least its u16 length
+        if 1 + 2 * plugin_count as usize > r.get_buffer_len() {
+            return Err(SaveParseError::InvalidPluginCount {
+                count: plugin_count as u32,
+                plugin_info_size: r.get_buffer_len() as u32,
+            });
+        }
+        let plugin_info = read_strings_into_vec(r, plugin_count as u32)?;
+        if form_version < LIGHT_PLUGIN_MIN_FORM_VERSION {
+            return Ok((plugin_info, Vec::new()));
+        }
+        let light_plugin_count = r.read_u16()?;
+        let light_plugin_info = read_strings_into_vec(r, light_plugin_count as u32)?;
+        Ok((plugin_info, light_plugin_info))
+    })
+}
+
+/// Checks that a section starts at its ```offset``` from the file location table.
+/// Depending on the compression, the offsets don't count from the same base, so they are compared
+/// relative to the offset of global data table 1, which directly follows the file location table at
+/// ```data_start``` in the body.
+fn check_section_offset(table: &FileLocationTable, section: &'static str, offset: u32, data_start: usize, position: usize) -> Result<(), SaveParseError> {
+    let actual = table.global_data_table_1_offset as i64 + (position - data_start) as i64;
+    if actual != offset as i64 {
+        return Err(SaveParseError::SectionOffsetMismatch { section, expected: offset, actual: actual as u32 });
+    }
+    Ok(())
+}
+
+/// Returns the number of bytes read since ```section_start``` and moves it to the current position.
+fn end_section(sfr: &SaveFileReader, section_start: &mut usize) -> usize {
+    let size = sfr.get_index() - *section_start;
+    *section_start = sfr.get_index();
+    size
+}
+
+
+/// Returns the body following the current position of the reader, decompressed according to the
+/// compression type in the header (0: none, 1: zLib, 2: LZ4 block or frame).
+/// Uncompressed bodies are borrowed from the reader's buffer instead of being copied. They are
+/// exactly ```uncompressed_len``` bytes long, data appended by other tools is ignored.
+fn read_body<'a>(sfr: &'a SaveFileReader, header: &Header, uncompressed_len: u32) -> Result<Cow<'a, [u8]>, SaveParseError> {
+    let buffer = sfr.remaining();
     match header.compression_type {
-        0 => buffer[range].to_vec(),
-        1 => panic!("zLib compression not supported"),
+        0 => buffer.get(..uncompressed_len as usize).map(Cow::Borrowed).ok_or(
+            SaveParseError::Reader(ReaderError::UnexpectedEnd {
+                index: sfr.get_index(),
+                requested: uncompressed_len as usize,
+                buffer_len: sfr.get_buffer_len(),
+            })),
+        1 => {
+            let mut body = Vec::with_capacity(uncompressed_len as usize);
+            ZlibDecoder::new(buffer).read_to_end(&mut body)
+                .map_err(|e| SaveParseError::BodyDecompression(e.to_string()))?;
+            Ok(Cow::Owned(check_body_len(body, uncompressed_len)?))
+        }
+        2 if buffer.starts_with(&LZ4_FRAME_MAGIC.to_le_bytes()) => Ok(Cow::Owned(decompress_lz4_frame(buffer, uncompressed_len)?)),
         2 => {
-            decompress(&buffer[range], uncompressed_len as usize)
-                .expect("Could not decompress body! File may be corrupted.")
+            let body = decompress(buffer, uncompressed_len as usize)
+                .map_err(|e| SaveParseError::BodyDecompression(format!("{:?}", e)))?;
+            Ok(Cow::Owned(check_body_len(body, uncompressed_len)?))
+        }
+        compression_type => Err(SaveParseError::UnsupportedCompression(compression_type))
+    }
+}
+
+/// Fails unless the decompressed ```body``` has the ```uncompressed_len``` of the save.
+fn check_body_len(body: Vec<u8>, uncompressed_len: u32) -> Result<Vec<u8>, SaveParseError> {
+    match body.len() == uncompressed_len as usize {
+        true => Ok(body),
+        false => Err(SaveParseError::BodyDecompression(format!(
+            "Decompressed to {} bytes, but the save specifies {}", body.len(), uncompressed_len
+        ))),
+    }
+}
+
+/// Magic of the LZ4 frame format. The game stores the body as a single LZ4 block, but some tools write a frame.
+const LZ4_FRAME_MAGIC: u32 = 0x184D2204;
+
+/// Decompresses a body stored in the LZ4 frame format. lz4_flex 0.7 has no public frame decoder, so the
+/// blocks of the frame are decoded one after the other into the same buffer, which also resolves the
+/// references of linked blocks into the previous ones. Checksums are skipped without verifying them,
+/// frames using a dictionary are not supported.
+fn decompress_lz4_frame(buffer: &[u8], uncompressed_len: u32) -> Result<Vec<u8>, SaveParseError> {
+    let mut r = SaveFileReader::from_slice(buffer);
+    let _magic = r.read_u32()?;
+    let flags = r.read_u8()?;
+    let _block_descriptor = r.read_u8()?;
+    if flags >> 6 != 1 {
+        return Err(SaveParseError::BodyDecompression(format!("Unsupported LZ4 frame version {}", flags >> 6)));
+    }
+    if flags & 0x01 != 0 {
+        return Err(SaveParseError::BodyDecompression("LZ4 frames with a dictionary are not supported".to_string()));
+    }
+    // content size
+    if flags & 0x08 != 0 {
+        r.read_bytes(8)?;
+    }
+    let _header_checksum = r.read_u8()?;
+
+    let mut body = Vec::with_capacity(uncompressed_len as usize);
+    loop {
+        let block_size = r.read_u32()?;
+        if block_size == 0 {
+            break;
+        }
+        // the highest bit marks a block stored uncompressed
+        let block = r.read_bytes((block_size & 0x7FFFFFFF) as usize)?;
+        match block_size & 0x80000000 != 0 {
+            true => body.extend_from_slice(block),
+            false => decompress_into(block, &mut body).map_err(|e| SaveParseError::BodyDecompression(format!("{:?}", e)))?,
+        }
+        // block checksum
+        if flags & 0x10 != 0 {
+            r.read_bytes(4)?;
         }
-        _ => panic!("Encountered unspecified/unsupported compression type. Is the file corrupted?")
     }
+    check_body_len(body, uncompressed_len)
 }
 
-fn read_file_location_table(sfr_body: &mut SaveFileReader) -> FileLocationTable {
-    FileLocationTable {
-        form_id_array_count_offset: sfr_body.read_u32(),
-        unknown_table_3_offset: sfr_body.read_u32(),
-        global_data_table_1_offset: sfr_body.read_u32(),
-        global_data_table_2_offset: sfr_body.read_u32(),
-        change_forms_offset: sfr_body.read_u32(),
-        global_data_table_3_offset: sfr_body.read_u32(),
-        global_data_table_1_count: sfr_body.read_u32(),
-        global_data_table_2_count: sfr_body.read_u32(),
-        global_data_table_3_count: sfr_body.read_u32(),
-        change_form_count: sfr_body.read_u32(),
+fn read_file_location_table(sfr_body: &mut SaveFileReader) -> Result<FileLocationTable, ReaderError> {
+    Ok(FileLocationTable {
+        form_id_array_count_offset: sfr_body.read_u32()?,
+        unknown_table_3_offset: sfr_body.read_u32()?,
+        global_data_table_1_offset: sfr_body.read_u32()?,
+        global_data_table_2_offset: sfr_body.read_u32()?,
+        change_forms_offset: sfr_body.read_u32()?,
+        global_data_table_3_offset: sfr_body.read_u32()?,
+        global_data_table_1_count: sfr_body.read_u32()?,
+        global_data_table_2_count: sfr_body.read_u32()?,
+        global_data_table_3_count: sfr_body.read_u32()?,
+        change_form_count: sfr_body.read_u32()?,
+        reserved: read_array(sfr_body)?,
+    })
+}
+
+impl Readable for FileLocationTable {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        read_file_location_table(r)
     }
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &[u8] = include_bytes!("../benches/data/sample.ess");
+
+    fn strict() -> ParseOptions {
+        ParseOptions { recovery: RecoveryStrategy::Strict, ..ParseOptions::default() }
+    }
+
+    /// The sample written as a Legendary Edition save: version 9, an RGB screenshot and no body lengths.
+    /// The screenshot is twice as wide, as the offsets in the file location table of the sample are smaller
+    /// than its screenshot and can't be moved to the front.
+    fn legendary_edition_sample() -> Vec<u8> {
+        let mut save = parse_save_file(SAMPLE.to_vec()).unwrap();
+        save.header.version = LEGENDARY_EDITION_MAX_VERSION;
+        save.header.compression_type = 0;
+        save.header.shot_width *= 2;
+        save.screenshot_data.width = save.header.shot_width;
+        let channel_order = ChannelOrder::for_version(save.header.version);
+        let pixels = save.header.shot_width as usize * save.header.shot_height as usize;
+        save.screenshot_data.data = vec![0; channel_order.bytes_per_pixel() * pixels];
+        save.screenshot_data.channel_order = channel_order;
+        save.to_bytes_patched(SAMPLE).unwrap()
+    }
+
+    #[test]
+    fn legendary_edition_body_is_the_rest_of_the_file() {
+        let buf = legendary_edition_sample();
+        let save = parse_save_file_with_options(&buf, &strict()).unwrap();
+        let sample = parse_save_file_with_options(SAMPLE, &strict()).unwrap();
+
+        assert_eq!(save.header.edition(), SkyrimEdition::LegendaryEdition);
+        assert_eq!(save.body_uncompressed_len as usize, decompress_body(&buf).unwrap().len());
+        assert_eq!(save.body_compressed_len, 0);
+        assert_eq!(save.body_uncompressed_len, sample.body_uncompressed_len);
+        assert!(save.change_forms == sample.change_forms);
+        assert_eq!(save.form_id_array, sample.form_id_array);
+        assert!(save.warnings.is_empty());
+    }
+
+    /// A frame of a single block, stored uncompressed.
+    fn lz4_frame(data: &[u8]) -> Vec<u8> {
+        let mut w = SaveFileWriter::new();
+        w.write_u32(LZ4_FRAME_MAGIC);
+        // version 1, no checksums or content size, followed by the block descriptor and the header checksum
+        w.write_bytes(&[0x40, 0x40, 0]);
+        w.write_u32(data.len() as u32 | 0x80000000);
+        w.write_bytes(data);
+        w.write_u32(0);
+        w.get_buffer()
+    }
+
+    #[test]
+    fn lz4_frame_must_have_the_uncompressed_len() {
+        assert_eq!(decompress_lz4_frame(&lz4_frame(b"body"), 4).unwrap(), b"body");
+        assert!(matches!(decompress_lz4_frame(&lz4_frame(b"body"), 5), Err(SaveParseError::BodyDecompression(_))));
+        assert!(matches!(decompress_lz4_frame(&lz4_frame(b"body"), 3), Err(SaveParseError::BodyDecompression(_))));
+    }
+
+    #[test]
+    fn zlib_body_must_have_the_uncompressed_len() {
+        let mut save = parse_save_file(SAMPLE.to_vec()).unwrap();
+        save.header.compression_type = 1;
+        let mut buf = save.to_bytes_patched(SAMPLE).unwrap();
+        assert!(parse_save_file_with_options(&buf, &strict()).is_ok());
+
+        // the uncompressed length follows the screenshot
+        let position = read_preamble_layout(&mut SaveFileReader::from_slice(&buf)).unwrap().screenshot.end;
+        buf[position..position + 4].copy_from_slice(&(save.body_uncompressed_len + 1).to_le_bytes());
+        assert!(matches!(parse_save_file_with_options(&buf, &strict()), Err(SaveParseError::BodyDecompression(_))));
+    }
+
+    #[test]
+    fn legendary_edition_save_is_patched_without_body_lengths() {
+        let buf = legendary_edition_sample();
+        let save = parse_save_file_with_options(&buf, &strict()).unwrap();
+        assert_eq!(save.to_bytes_patched(&buf).unwrap(), buf);
+
+        let (_, spans) = crate::parse_save_file_with_spans(&buf, &strict()).unwrap();
+        assert!(spans.iter().all(|span| !span.path.starts_with("body_") || span.path == "body"));
+    }
+
+    #[test]
+    fn allocated_object_ids_fit_into_a_ref_id() {
+        let mut save = parse_save_file(SAMPLE.to_vec()).unwrap();
+        save.player_location_mut().unwrap().next_object_id = (1 << 22) - 1;
+        assert_eq!(save.allocate_object_id(), Some((1 << 22) - 1));
+        assert_eq!(save.allocate_object_id(), None);
+        assert_eq!(save.player_location_mut().unwrap().next_object_id, 1 << 22);
+    }
+}
@@ -0,0 +1,298 @@
+//! The start of the Papyrus virtual machine state, stored as global data of type 1001.
+//!
+//! Only the string table, the scripts and the script instances are parsed, which is enough to find
+//! the forms scripts are attached to. The rest (references, arrays, active scripts, ...) is kept as raw bytes.
+
+use crate::reader::*;
+use crate::{FormIdType, GlobalDataType, SaveFile, LIGHT_PLUGIN_MIN_FORM_VERSION};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Papyrus {
+    pub header: u16,
+    /// The string table, scripts and instances refer to names by their index in it.
+    pub strings: Vec<String>,
+    pub scripts: Vec<Script>,
+    pub script_instances: Vec<ScriptInstance>,
+    /// Everything following the script instances, not parsed yet.
+    pub remainder: Vec<u8>,
+}
+
+impl Papyrus {
+    /// The string at ```index``` of the string table, None if the index is out of range.
+    pub fn string(&self, index: u16) -> Option<&str> {
+        self.strings.get(index as usize).map(String::as_str)
+    }
+}
+
+/// The definition of a script, with the types of its member variables.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Script {
+    /// Index into ```Papyrus::strings```.
+    pub name: u16,
+    /// Index into ```Papyrus::strings``` of the script this one extends.
+    pub type_name: u16,
+    pub members: Vec<ScriptMember>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScriptMember {
+    /// Index into ```Papyrus::strings```.
+    pub name: u16,
+    /// Index into ```Papyrus::strings```.
+    pub member_type: u16,
+}
+
+/// A script attached to a form.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScriptInstance {
+    pub id: u32,
+    /// Index into ```Papyrus::strings```.
+    pub script_name: u16,
+    pub unknown2bits: u16,
+    pub unknown: i16,
+    /// The form the script is attached to.
+    pub ref_id: FormIdType,
+    pub unknown_byte: u8,
+}
+
+/// A script instance found by ```SaveFile::orphaned_script_instances()```.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScriptInstanceRef {
+    pub id: u32,
+    /// The name of the script, empty if its string index is out of range.
+    pub script_name: String,
+    pub form_id: FormIdType,
+}
+
+/// Reads the data of global data of type 1001, see ```GlobalDataType::Papyrus```.
+pub fn read_papyrus(r: &mut SaveFileReader) -> Result<Papyrus, ReaderError> {
+    let header = r.read_u16()?;
+    let string_count = r.read_u16()?;
+    let strings = read_strings_into_vec(r, string_count as u32)?;
+    let script_count = r.read_u32()?;
+    let scripts = read_vec(r, script_count)?;
+    let instance_count = r.read_u32()?;
+    let script_instances = read_vec(r, instance_count)?;
+    Ok(Papyrus {
+        header,
+        strings,
+        scripts,
+        script_instances,
+        remainder: r.remaining().to_vec(),
+    })
+}
+
+impl Readable for Script {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        let name = r.read_u16()?;
+        let type_name = r.read_u16()?;
+        let member_count = r.read_u32()?;
+        Ok(Script {
+            name,
+            type_name,
+            members: read_vec(r, member_count)?,
+        })
+    }
+}
+
+impl Readable for ScriptMember {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        Ok(ScriptMember {
+            name: r.read_u16()?,
+            member_type: r.read_u16()?,
+        })
+    }
+}
+
+impl Readable for ScriptInstance {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        Ok(ScriptInstance {
+            id: r.read_u32()?,
+            script_name: r.read_u16()?,
+            unknown2bits: r.read_u16()?,
+            unknown: r.read_u16()? as i16,
+            ref_id: read_ref_id(r)?,
+            unknown_byte: r.read_u8()?,
+        })
+    }
+}
+
+impl SaveFile {
+    /// Parses the Papyrus global data, None if the save has none.
+    pub fn papyrus(&self) -> Option<Result<Papyrus, ReaderError>> {
+        self.global_data_table_3.iter().find_map(|data| match data {
+            GlobalDataType::Papyrus(raw) => Some(read_papyrus(&mut SaveFileReader::from_slice(raw))),
+            _ => None,
+        })
+    }
+
+    /// The script instances attached to a form that doesn't resolve in this save: its index is
+    /// past the end of the form id array, or its plugin is not in the plugin lists.
+    /// Instances not attached to any form (form id 0) and instances on created forms are not included.
+    ///
+    /// These are the instances save cleaners remove, left behind by uninstalled plugins.
+    pub fn orphaned_script_instances(&self) -> Result<Vec<ScriptInstanceRef>, ReaderError> {
+        let papyrus = match self.papyrus() {
+            Some(papyrus) => papyrus?,
+            None => return Ok(Vec::new()),
+        };
+        Ok(papyrus.script_instances.iter()
+            .filter(|instance| instance.ref_id != FormIdType::Default(0) && !self.plugin_is_loaded(instance.ref_id))
+            .map(|instance| ScriptInstanceRef {
+                id: instance.id,
+                script_name: papyrus.string(instance.script_name).unwrap_or_default().to_string(),
+                form_id: instance.ref_id,
+            })
+            .collect())
+    }
+
+    /// Whether the form id resolves to a form of a plugin of this save or a created form.
+    fn plugin_is_loaded(&self, form_id: FormIdType) -> bool {
+        let full_form_id = match self.resolve_form_id(form_id) {
+            Some(full_form_id) => full_form_id,
+            None => return false,
+        };
+        match full_form_id >> 24 {
+            // created forms only exist in the save
+            0xFF => true,
+            // light plugins share index 0xFE, the next 12 bits are the light plugin index
+            0xFE if self.form_version >= LIGHT_PLUGIN_MIN_FORM_VERSION => {
+                (((full_form_id >> 12) & 0xFFF) as usize) < self.light_plugin_info.len()
+            }
+            plugin_index => (plugin_index as usize) < self.plugin_info.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_save_file, write_ref_id, SaveFileWriter};
+
+    const STRINGS: [&str; 4] = ["dunFishScript", "ObjectReference", "::count_var", "Int"];
+
+    /// A Papyrus section with the strings above, one script with a member and the given instances,
+    /// as (id, script name index, form id).
+    fn papyrus_bytes(instances: &[(u32, u16, FormIdType)]) -> Vec<u8> {
+        let mut w = SaveFileWriter::new();
+        w.write_u16(3);
+        w.write_u16(STRINGS.len() as u16);
+        for string in STRINGS {
+            w.write_w_string(string).unwrap();
+        }
+        w.write_u32(1);
+        w.write_u16(0);
+        w.write_u16(1);
+        w.write_u32(1);
+        w.write_u16(2);
+        w.write_u16(3);
+        w.write_u32(instances.len() as u32);
+        for (id, script_name, form_id) in instances {
+            w.write_u32(*id);
+            w.write_u16(*script_name);
+            w.write_u16(0x2);
+            w.write_u16(0xFFFF);
+            write_ref_id(&mut w, *form_id).unwrap();
+            w.write_u8(1);
+        }
+        w.write_bytes(&[0xAB, 0xCD]);
+        w.get_buffer()
+    }
+
+    /// The sample with two plugins, one light plugin and the given Papyrus section.
+    fn save_with_papyrus(papyrus: Vec<u8>) -> SaveFile {
+        let mut save = parse_save_file(include_bytes!("../benches/data/sample.ess").to_vec()).unwrap();
+        save.plugin_info = vec!["Skyrim.esm".to_string(), "Update.esm".to_string()];
+        save.light_plugin_info = vec!["ccBGSSSE001-Fish.esm".to_string()];
+        save.form_id_array = vec![0x0100_0800, 0x0500_0800, 0xFE00_0800, 0xFE00_1800];
+        save.global_data_table_3.retain(|data| !matches!(data, GlobalDataType::Papyrus(_)));
+        save.global_data_table_3.push(GlobalDataType::Papyrus(papyrus));
+        save
+    }
+
+    #[test]
+    fn strings_scripts_and_instances_are_read() {
+        let papyrus = read_papyrus(&mut SaveFileReader::from_slice(&papyrus_bytes(&[(0x4D2, 0, FormIdType::Default(0x14))]))).unwrap();
+        assert_eq!(papyrus.header, 3);
+        assert_eq!(papyrus.strings, STRINGS);
+        assert_eq!((papyrus.string(1), papyrus.string(4)), (Some("ObjectReference"), None));
+        assert_eq!(papyrus.scripts, [Script { name: 0, type_name: 1, members: vec![ScriptMember { name: 2, member_type: 3 }] }]);
+        assert_eq!(papyrus.script_instances, [ScriptInstance {
+            id: 0x4D2,
+            script_name: 0,
+            unknown2bits: 0x2,
+            unknown: -1,
+            ref_id: FormIdType::Default(0x14),
+            unknown_byte: 1,
+        }]);
+        assert_eq!(papyrus.remainder, [0xAB, 0xCD]);
+    }
+
+    #[test]
+    fn truncated_papyrus_is_an_error() {
+        let bytes = papyrus_bytes(&[(1, 0, FormIdType::Default(0x14))]);
+        // cut into the last instance, in front of the remainder
+        let truncated = &bytes[..bytes.len() - 4];
+        assert!(matches!(read_papyrus(&mut SaveFileReader::from_slice(truncated)), Err(ReaderError::UnexpectedEnd { .. })));
+        // more strings than stored
+        let mut bytes = bytes;
+        bytes[2] = 200;
+        assert!(read_papyrus(&mut SaveFileReader::from_slice(&bytes)).is_err());
+    }
+
+    #[test]
+    fn orphaned_script_instances_are_the_unresolved_ones() {
+        let save = save_with_papyrus(papyrus_bytes(&[
+            (1, 0, FormIdType::Index(0)),
+            (2, 0, FormIdType::Index(1)),
+            (3, 0, FormIdType::Index(2)),
+            (4, 0, FormIdType::Index(3)),
+            (5, 0, FormIdType::Index(10)),
+            (6, 0, FormIdType::Created(0x123)),
+            (7, 0, FormIdType::Default(0)),
+            (8, 0, FormIdType::Default(0x14)),
+            (9, 99, FormIdType::Index(1)),
+        ]));
+        let orphans = save.orphaned_script_instances().unwrap();
+        assert_eq!(orphans, [
+            // plugin 0x05 is not loaded
+            ScriptInstanceRef { id: 2, script_name: "dunFishScript".to_string(), form_id: FormIdType::Index(1) },
+            // light plugin 0x001 is not loaded
+            ScriptInstanceRef { id: 4, script_name: "dunFishScript".to_string(), form_id: FormIdType::Index(3) },
+            // past the end of the form id array
+            ScriptInstanceRef { id: 5, script_name: "dunFishScript".to_string(), form_id: FormIdType::Index(10) },
+            ScriptInstanceRef { id: 9, script_name: String::new(), form_id: FormIdType::Index(1) },
+        ]);
+    }
+
+    #[test]
+    fn light_plugin_index_needs_a_form_version_with_light_plugins() {
+        let mut save = save_with_papyrus(papyrus_bytes(&[(3, 0, FormIdType::Index(2))]));
+        assert!(save.plugin_is_loaded(FormIdType::Index(2)));
+        assert!(save.orphaned_script_instances().unwrap().is_empty());
+
+        // without light plugins 0xFE is the index of a full plugin
+        save.form_version = LIGHT_PLUGIN_MIN_FORM_VERSION - 1;
+        assert!(!save.plugin_is_loaded(FormIdType::Index(2)));
+        assert_eq!(save.orphaned_script_instances().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn plugin_is_loaded_resolves_the_form_id() {
+        let save = save_with_papyrus(papyrus_bytes(&[]));
+        assert!(save.plugin_is_loaded(FormIdType::Default(0x14)));
+        assert!(save.plugin_is_loaded(FormIdType::Created(0x123)));
+        assert!(save.plugin_is_loaded(FormIdType::Index(0)));
+        assert!(!save.plugin_is_loaded(FormIdType::Index(1)));
+        assert!(!save.plugin_is_loaded(FormIdType::Index(4)));
+        assert!(!save.plugin_is_loaded(FormIdType::Unknown(1)));
+    }
+
+    #[test]
+    fn save_without_papyrus_has_no_orphans() {
+        let mut save = save_with_papyrus(Vec::new());
+        save.global_data_table_3.retain(|data| !matches!(data, GlobalDataType::Papyrus(_)));
+        assert!(save.papyrus().is_none());
+        assert!(save.orphaned_script_instances().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,470 @@
+//! Writing a modified save by patching the save it was parsed from.
+//!
+//! Only a part of the data in a save is parsed, so a save can't be written from a ```SaveFile``` alone.
+//! Instead the bytes of every section that was not modified are copied from the original, and only
+//! the modified sections are encoded again.
+
+use crate::*;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+impl SaveFile {
+    /// Serializes this save, reusing the bytes of ```original``` (the save this one was parsed from)
+    /// for every section that is unchanged. An unmodified save is reproduced byte for byte.
+    ///
+    /// Modified sections are encoded again, global data and change forms entry by entry.
+    /// Fails with ```SaveWriteError::NotReproducible``` if a modified section can't be encoded
+    /// without losing data this library doesn't parse, and with ```SaveWriteError::UnsupportedGlobalData```
+    /// if a modified global data entry has a type that can't be written yet. A save parsed with
+    /// ```ParseOptions::max_change_forms``` that is missing change forms fails with
+    /// ```SaveWriteError::TruncatedChangeForms```.
+    /// The offsets in the file location table are moved by the size changes of the sections in front of them.
+    pub fn to_bytes_patched(&self, original: &[u8]) -> Result<Vec<u8>, SaveWriteError> {
+        self.patch(original, &self.header, &self.screenshot_data)
+    }
+
+    /// Like ```to_bytes_patched()```, but writes a 1x1 black screenshot instead of ```screenshot_data``` and sets
+    /// the dimensions in the header to match, to shrink the save. A screenshot of no pixels at all is avoided,
+    /// as the game creates a texture of the dimensions for the load menu.
+    pub fn to_bytes_without_screenshot(&self, original: &[u8]) -> Result<Vec<u8>, SaveWriteError> {
+        let channel_order = ChannelOrder::for_version(self.header.version);
+        let screenshot = ScreenshotData {
+            height: 1,
+            width: 1,
+            data: vec![0; channel_order.bytes_per_pixel()],
+            channel_order,
+        };
+        let header = Header { shot_width: 1, shot_height: 1, ..self.header.clone() };
+        self.patch(original, &header, &screenshot)
+    }
+
+    /// ```to_bytes_patched()``` with ```header``` and ```screenshot``` in place of the ones of this save.
+    fn patch(&self, original: &[u8], header: &Header, screenshot: &ScreenshotData) -> Result<Vec<u8>, SaveWriteError> {
+        self.check_change_forms_complete()?;
+        let options = ParseOptions { recovery: RecoveryStrategy::Silent, ..ParseOptions::default() };
+        let parsed = parse_save_file_with_options(original, &options).map_err(invalid_original)?;
+
+        let mut sfr = SaveFileReader::from_slice(original);
+        let layout = read_preamble_layout(&mut sfr).map_err(invalid_original)?;
+        let (header_end, screenshot_end) = (layout.header_range.end, layout.screenshot.end);
+        let body_start = sfr.get_index();
+        let body = read_body(&sfr, &layout.header, layout.uncompressed_len).map_err(invalid_original)?;
+
+        let mut w = SaveFileWriter::new();
+        match *header == parsed.header {
+            true => w.write_bytes(&original[..header_end]),
+            false => write_file_header(&mut w, header)?,
+        }
+        match *screenshot == parsed.screenshot_data {
+            true => w.write_bytes(&original[header_end..screenshot_end]),
+            false => write_screenshot(&mut w, screenshot)?,
+        }
+        let has_body_lengths = header.version >= COMPRESSION_TYPE_MIN_VERSION;
+        let new_body_start = w.get_buffer_len() + if has_body_lengths { 8 } else { 0 };
+        let preamble_delta = new_body_start as i64 - body_start as i64;
+
+        let new_body = self.patch_body(&parsed, &body, preamble_delta)?;
+        if new_body[..] == body[..] && header.compression_type == parsed.header.compression_type
+            && has_body_lengths == (parsed.header.version >= COMPRESSION_TYPE_MIN_VERSION) {
+            w.write_bytes(&original[screenshot_end..]);
+            return Ok(w.get_buffer());
+        }
+
+        let stored = compress_body(&new_body, header.compression_type)?;
+        if has_body_lengths {
+            w.write_u32(new_body.len() as u32);
+            w.write_u32(if header.compression_type == 0 { 0 } else { stored.len() as u32 });
+        }
+        w.write_bytes(&stored);
+        if parsed.header.compression_type == 0 {
+            // data appended to an uncompressed body is not part of it, see read_body()
+            w.write_bytes(&original[body_start + body.len()..]);
+        }
+        Ok(w.get_buffer())
+    }
+
+    /// Builds the uncompressed body from the sections of ```body```, the body of ```parsed```.
+    fn patch_body(&self, parsed: &SaveFile, body: &[u8], preamble_delta: i64) -> Result<Vec<u8>, SaveWriteError> {
+        let sizes = &parsed.section_sizes;
+        let mut sections = Sections { body, position: 0 };
+        let mut w = SaveFileWriter::new();
+
+        sections.next(1);
+        w.write_u8(self.form_version);
+
+        patch_section(
+            &mut w,
+            "plugin info",
+            (&self.plugin_info, &self.light_plugin_info),
+            (&parsed.plugin_info, &parsed.light_plugin_info),
+            sections.next(sizes.plugin_info),
+            |w, (plugins, light_plugins)| write_plugin_info(w, plugins, light_plugins, self.form_version),
+        )?;
+
+        // the offsets are only known after writing the sections behind it
+        let file_location_table_start = w.get_buffer_len();
+        let original_table = sections.next(sizes.file_location_table);
+        w.write_bytes(original_table);
+
+        let global_data_table_1 = (sections.position, w.get_buffer_len());
+        patch_global_data(&mut w, &self.global_data_table_1, &parsed.global_data_table_1, sections.next(sizes.global_data_table_1))?;
+        let global_data_table_2 = (sections.position, w.get_buffer_len());
+        patch_global_data(&mut w, &self.global_data_table_2, &parsed.global_data_table_2, sections.next(sizes.global_data_table_2))?;
+        let change_forms = (sections.position, w.get_buffer_len());
+        patch_change_forms(&mut w, &self.change_forms, &parsed.change_forms, sections.next(sizes.change_forms))?;
+        let global_data_table_3 = (sections.position, w.get_buffer_len());
+        patch_global_data(&mut w, &self.global_data_table_3, &parsed.global_data_table_3, sections.next(sizes.global_data_table_3))?;
+
+        let form_id_array = (sections.position, w.get_buffer_len());
+        patch_section(&mut w, "form id array", &self.form_id_array, &parsed.form_id_array,
+                      sections.next(sizes.form_id_array), |w, ids| write_u32_array(w, ids))?;
+        patch_section(&mut w, "visited worldspace array", &self.visited_worldspace_array, &parsed.visited_worldspace_array,
+                      sections.next(sizes.visited_worldspace_array), |w, ids| write_u32_array(w, ids))?;
+        let unknown_3_table = (sections.position, w.get_buffer_len());
+        patch_section(&mut w, "unknown table 3", &self.unknown_3_table, &parsed.unknown_3_table,
+                      sections.next(sizes.unknown_3_table), |w, strings| write_unknown_3_table(w, strings))?;
+
+        w.write_bytes(&self.trailing_bytes);
+
+        let starts = SectionStarts {
+            global_data_table_1,
+            global_data_table_2,
+            change_forms,
+            global_data_table_3,
+            form_id_array,
+            unknown_3_table,
+        };
+        let mut new_body = w.get_buffer();
+        let table = self.file_location_table_for(&parsed.file_location_table, &starts, preamble_delta);
+        let mut table_writer = SaveFileWriter::new();
+        write_file_location_table(&mut table_writer, &table);
+        let table_bytes = table_writer.get_buffer();
+        new_body[file_location_table_start..file_location_table_start + table_bytes.len()].copy_from_slice(&table_bytes);
+        Ok(new_body)
+    }
+
+    /// Recomputes the counts and offsets of the file location table from the current contents of the
+    /// tables, e.g. after adding global data or change forms. ```original``` is the save this one was parsed
+    /// from, the offsets are moved by the size changes of the sections against it, so changes in front of the
+    /// body (header, screenshot) are not included. ```to_bytes_patched()``` computes its table on its own and
+    /// doesn't need this.
+    ///
+    /// The sections are encoded to get their sizes, unchanged global data entries keep the size they have in
+    /// ```original```. Compressed change forms whose data still has its stored uncompressed length keep their
+    /// stored length, as compressing them again may give another size. Fails like ```to_bytes_patched()```
+    /// if a modified section can't be encoded, and with ```SaveWriteError::TruncatedChangeForms``` if not
+    /// all change forms were read.
+    pub fn rebuild_file_location_table(&mut self, original: &[u8]) -> Result<(), SaveWriteError> {
+        self.check_change_forms_complete()?;
+        let options = ParseOptions { recovery: RecoveryStrategy::Silent, ..ParseOptions::default() };
+        let parsed = parse_save_file_with_options(original, &options).map_err(invalid_original)?;
+        let body = decompress_body(original).map_err(invalid_original)?;
+        let sizes = parsed.section_sizes;
+        let mut sections = Sections { body: &body, position: 0 };
+        sections.next(1 + sizes.plugin_info + sizes.file_location_table);
+
+        let plugin_info = encoded_len(|w| write_plugin_info(w, &self.plugin_info, &self.light_plugin_info, self.form_version))?;
+        let global_data_table_1 = encoded_len(|w| {
+            patch_global_data(w, &self.global_data_table_1, &parsed.global_data_table_1, sections.next(sizes.global_data_table_1))
+        })?;
+        let global_data_table_2 = encoded_len(|w| {
+            patch_global_data(w, &self.global_data_table_2, &parsed.global_data_table_2, sections.next(sizes.global_data_table_2))
+        })?;
+        let change_forms = self.change_forms.iter().map(change_form_len).sum::<Result<usize, SaveWriteError>>()?;
+        sections.next(sizes.change_forms);
+        let global_data_table_3 = encoded_len(|w| {
+            patch_global_data(w, &self.global_data_table_3, &parsed.global_data_table_3, sections.next(sizes.global_data_table_3))
+        })?;
+        let form_id_array = 4 + 4 * self.form_id_array.len();
+        let visited_worldspace_array = 4 + 4 * self.visited_worldspace_array.len();
+
+        let table = &mut self.file_location_table;
+        let offset = |start: u32, size: usize| start.wrapping_add(size as u32);
+        let original_offset = parsed.file_location_table.global_data_table_1_offset;
+        table.global_data_table_1_offset = (original_offset as i64 + plugin_info as i64 - sizes.plugin_info as i64) as u32;
+        table.global_data_table_2_offset = offset(table.global_data_table_1_offset, global_data_table_1);
+        table.change_forms_offset = offset(table.global_data_table_2_offset, global_data_table_2);
+        table.global_data_table_3_offset = offset(table.change_forms_offset, change_forms);
+        table.form_id_array_count_offset = offset(table.global_data_table_3_offset, global_data_table_3);
+        table.unknown_table_3_offset = offset(table.form_id_array_count_offset, form_id_array + visited_worldspace_array);
+        table.global_data_table_1_count = self.global_data_table_1.len() as u32;
+        table.global_data_table_2_count = self.global_data_table_2.len() as u32;
+        // stored one less than the actual count, see parse_save_file_with_options()
+        table.global_data_table_3_count = self.global_data_table_3.len().saturating_sub(1) as u32;
+        table.change_form_count = self.change_forms.len() as u32;
+        Ok(())
+    }
+
+    /// Fails if the save was parsed with ```ParseOptions::max_change_forms``` and is missing change forms,
+    /// as writing it would drop them.
+    fn check_change_forms_complete(&self) -> Result<(), SaveWriteError> {
+        match self.warnings.iter().find_map(|warning| match warning {
+            ParseWarning::TruncatedChangeForms { read, count } => Some((*read, *count)),
+            _ => None,
+        }) {
+            Some((read, count)) => Err(SaveWriteError::TruncatedChangeForms { read, count }),
+            None => Ok(()),
+        }
+    }
+
+    /// The file location table with the counts of this save and the original offsets moved by
+    /// the size changes in front of the sections.
+    fn file_location_table_for(&self, original: &FileLocationTable, starts: &SectionStarts, preamble_delta: i64) -> FileLocationTable {
+        let moved = |offset: u32, (original_start, new_start): (usize, usize)| {
+            (offset as i64 + preamble_delta + new_start as i64 - original_start as i64) as u32
+        };
+        FileLocationTable {
+            form_id_array_count_offset: moved(original.form_id_array_count_offset, starts.form_id_array),
+            unknown_table_3_offset: moved(original.unknown_table_3_offset, starts.unknown_3_table),
+            global_data_table_1_offset: moved(original.global_data_table_1_offset, starts.global_data_table_1),
+            global_data_table_2_offset: moved(original.global_data_table_2_offset, starts.global_data_table_2),
+            change_forms_offset: moved(original.change_forms_offset, starts.change_forms),
+            global_data_table_3_offset: moved(original.global_data_table_3_offset, starts.global_data_table_3),
+            global_data_table_1_count: self.global_data_table_1.len() as u32,
+            global_data_table_2_count: self.global_data_table_2.len() as u32,
+            // stored one less than the actual count, see parse_save_file_with_options()
+            global_data_table_3_count: self.global_data_table_3.len().saturating_sub(1) as u32,
+            change_form_count: self.change_forms.len() as u32,
+            reserved: self.file_location_table.reserved,
+        }
+    }
+}
+
+/// The number of bytes ```encode``` writes.
+fn encoded_len(encode: impl FnOnce(&mut SaveFileWriter) -> Result<(), SaveWriteError>) -> Result<usize, SaveWriteError> {
+    let mut w = SaveFileWriter::new();
+    encode(&mut w)?;
+    Ok(w.get_buffer_len())
+}
+
+/// The size of a change form as ```write_change_form()``` writes it, see ```SaveFile::rebuild_file_location_table()```.
+fn change_form_len(change_form: &ChangeForm) -> Result<usize, SaveWriteError> {
+    let length = &change_form.length;
+    let (width, stored_len) = match length.uncompressed_len() {
+        Some(uncompressed_len) if uncompressed_len as usize == change_form.data.len() => (length.width(), length.stored_len() as usize),
+        _ => {
+            let stored_len = change_form.stored_data()?.len();
+            let length2 = if change_form.is_compressed() { change_form.data.len() as u32 } else { 0 };
+            (ChangeFormLength::fitting(length.width(), stored_len as u32, length2).width(), stored_len)
+        }
+    };
+    // RefID, change flags, data type and version
+    Ok(3 + 4 + 1 + 1 + 2 * width + stored_len)
+}
+
+/// Hands out consecutive sections of the original body.
+struct Sections<'a> {
+    body: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Sections<'a> {
+    fn next(&mut self, size: usize) -> &'a [u8] {
+        let section = &self.body[self.position..self.position + size];
+        self.position += size;
+        section
+    }
+}
+
+/// Start positions of the sections the file location table points to, in the original and the new body.
+struct SectionStarts {
+    global_data_table_1: (usize, usize),
+    global_data_table_2: (usize, usize),
+    change_forms: (usize, usize),
+    global_data_table_3: (usize, usize),
+    form_id_array: (usize, usize),
+    unknown_3_table: (usize, usize),
+}
+
+fn invalid_original(e: SaveParseError) -> SaveWriteError {
+    SaveWriteError::InvalidOriginal(Box::new(e))
+}
+
+/// Copies the original bytes of a section if its value is unchanged. Otherwise the new value is encoded,
+/// after checking that encoding the original value reproduces the original bytes.
+fn patch_section<T: PartialEq>(
+    w: &mut SaveFileWriter,
+    name: &'static str,
+    value: T,
+    original_value: T,
+    original: &[u8],
+    encode: impl Fn(&mut SaveFileWriter, T) -> Result<(), SaveWriteError>,
+) -> Result<(), SaveWriteError> {
+    if value == original_value {
+        w.write_bytes(original);
+        return Ok(());
+    }
+    let mut check = SaveFileWriter::new();
+    encode(&mut check, original_value)?;
+    if check.get_buffer() != original {
+        return Err(SaveWriteError::NotReproducible(name));
+    }
+    encode(w, value)
+}
+
+/// Writes the entries of a global data table, copying the unchanged ones.
+/// The bytes behind the parsed part of a changed entry are kept.
+fn patch_global_data(w: &mut SaveFileWriter, entries: &[GlobalDataType], original_entries: &[GlobalDataType], original: &[u8]) -> Result<(), SaveWriteError> {
+    let mut r = SaveFileReader::from_slice(original);
+    for (index, entry) in entries.iter().enumerate() {
+        let original_entry = match original_entries.get(index) {
+            Some(original_entry) => original_entry,
+            None => {
+                write_global_data_entry(w, entry, &[])?;
+                continue;
+            }
+        };
+        // the original was parsed from these bytes, so reading them again can't fail
+        let start = r.get_index();
+        let _data_type = r.read_u32().unwrap();
+        let length = r.read_u32().unwrap();
+        let data = r.read_bytes(length as usize).unwrap();
+        if entry == original_entry {
+            w.write_bytes(&original[start..r.get_index()]);
+            continue;
+        }
+        let mut check = SaveFileWriter::new();
+        write_global_data_type(&mut check, original_entry)?;
+        let parsed_part = check.get_buffer();
+        if !data.starts_with(&parsed_part) {
+            return Err(SaveWriteError::NotReproducible("global data"));
+        }
+        write_global_data_entry(w, entry, &data[parsed_part.len()..])?;
+    }
+    Ok(())
+}
+
+fn write_global_data_entry(w: &mut SaveFileWriter, entry: &GlobalDataType, unparsed: &[u8]) -> Result<(), SaveWriteError> {
+    let mut data = SaveFileWriter::new();
+    write_global_data_type(&mut data, entry)?;
+    data.write_bytes(unparsed);
+    w.write_u32(entry.type_id());
+    w.write_u32(data.get_buffer_len() as u32);
+    w.write_bytes(&data.get_buffer());
+    Ok(())
+}
+
+/// Writes the change forms, copying the unchanged ones.
+fn patch_change_forms(w: &mut SaveFileWriter, change_forms: &[ChangeForm], original_forms: &[ChangeForm], original: &[u8]) -> Result<(), SaveWriteError> {
+    let mut r = SaveFileReader::from_slice(original);
+    for (index, change_form) in change_forms.iter().enumerate() {
+        let original_form = match original_forms.get(index) {
+            Some(original_form) => original_form,
+            None => {
+                write_change_form(w, change_form)?;
+                continue;
+            }
+        };
+        // the original was parsed from these bytes, so reading them again can't fail
+        let start = r.get_index();
+        let header = read_change_form_header(&mut r).unwrap();
+        r.read_bytes(header.length1 as usize).unwrap();
+        match change_form == original_form {
+            true => w.write_bytes(&original[start..r.get_index()]),
+            false => write_change_form(w, change_form)?,
+        }
+    }
+    Ok(())
+}
+
+fn write_plugin_info(w: &mut SaveFileWriter, plugins: &[String], light_plugins: &[String], form_version: u8) -> Result<(), SaveWriteError> {
+    let mut section = SaveFileWriter::new();
+    section.write_count(plugins.len(), CountEncoding::U8)?;
+    for plugin in plugins {
+        section.write_w_string(plugin)?;
+    }
+    if form_version >= LIGHT_PLUGIN_MIN_FORM_VERSION {
+        section.write_count(light_plugins.len(), CountEncoding::U16)?;
+        for plugin in light_plugins {
+            section.write_w_string(plugin)?;
+        }
+    }
+    w.write_u32(section.get_buffer_len() as u32);
+    w.write_bytes(&section.get_buffer());
+    Ok(())
+}
+
+fn write_file_location_table(w: &mut SaveFileWriter, table: &FileLocationTable) {
+    w.write_u32(table.form_id_array_count_offset);
+    w.write_u32(table.unknown_table_3_offset);
+    w.write_u32(table.global_data_table_1_offset);
+    w.write_u32(table.global_data_table_2_offset);
+    w.write_u32(table.change_forms_offset);
+    w.write_u32(table.global_data_table_3_offset);
+    w.write_u32(table.global_data_table_1_count);
+    w.write_u32(table.global_data_table_2_count);
+    w.write_u32(table.global_data_table_3_count);
+    w.write_u32(table.change_form_count);
+    table.reserved.iter().for_each(|value| w.write_u32(*value));
+}
+
+fn write_u32_array(w: &mut SaveFileWriter, values: &[u32]) -> Result<(), SaveWriteError> {
+    w.write_count(values.len(), CountEncoding::U32)?;
+    values.iter().for_each(|value| w.write_u32(*value));
+    Ok(())
+}
+
+fn write_unknown_3_table(w: &mut SaveFileWriter, strings: &[String]) -> Result<(), SaveWriteError> {
+    let mut table = SaveFileWriter::new();
+    for string in strings {
+        table.write_w_string(string)?;
+    }
+    // the size includes the count
+    w.write_u32(table.get_buffer_len() as u32 + 4);
+    w.write_count(strings.len(), CountEncoding::U32)?;
+    w.write_bytes(&table.get_buffer());
+    Ok(())
+}
+
+/// Compresses the body according to the compression type (0: none, 1: zLib, 2: LZ4), see ```read_body()```.
+fn compress_body(body: &[u8], compression_type: u16) -> Result<Vec<u8>, SaveWriteError> {
+    match compression_type {
+        0 => Ok(body.to_vec()),
+        1 => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(body)
+                .and_then(|_| encoder.finish())
+                .map_err(|e| SaveWriteError::Compression(e.to_string()))
+        }
+        2 => Ok(lz4_flex::compress(body)),
+        compression_type => Err(SaveWriteError::Compression(format!("unsupported compression type {}", compression_type))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &[u8] = include_bytes!("../benches/data/sample.ess");
+
+    #[test]
+    fn rebuilt_table_of_unmodified_save_is_unchanged() {
+        let mut save = parse_save_file(SAMPLE.to_vec()).unwrap();
+        let table = save.file_location_table;
+        save.rebuild_file_location_table(SAMPLE).unwrap();
+        assert_eq!(save.file_location_table, table);
+    }
+
+    #[test]
+    fn rebuilt_table_matches_patched_save() {
+        let mut save = parse_save_file(SAMPLE.to_vec()).unwrap();
+        let change_form = save.change_forms[0].clone();
+        save.change_forms.push(change_form);
+        save.form_id_array.push(0x14);
+        save.rebuild_file_location_table(SAMPLE).unwrap();
+
+        let patched = parse_save_file(save.to_bytes_patched(SAMPLE).unwrap()).unwrap();
+        assert_eq!(save.file_location_table, patched.file_location_table);
+    }
+
+    #[test]
+    fn truncated_save_is_not_written() {
+        let options = ParseOptions { max_change_forms: Some(10), ..ParseOptions::default() };
+        let mut save = parse_save_file_with_options(SAMPLE, &options).unwrap();
+        let count = save.file_location_table.change_form_count;
+        let is_truncated = |error| matches!(error, SaveWriteError::TruncatedChangeForms { read: 10, count: c } if c == count);
+        assert!(is_truncated(save.rebuild_file_location_table(SAMPLE).unwrap_err()));
+        assert!(is_truncated(save.to_bytes_patched(SAMPLE).unwrap_err()));
+    }
+}
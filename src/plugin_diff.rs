@@ -0,0 +1,140 @@
+//! The load order of a save and comparing the load orders of two saves, e.g. a working and a broken one.
+
+use crate::SaveFile;
+
+/// A plugin of the save, see ```SaveFile::plugins()```. The name is borrowed from the save.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PluginInfo<'a> {
+    pub name: &'a str,
+    /// Whether the plugin is a light plugin (ESL), i.e. listed in ```SaveFile::light_plugin_info```.
+    /// Whether a full plugin is a master can't be told from the save.
+    pub is_light: bool,
+    /// The index in the plugin list, or in the light plugin list if ```is_light```.
+    pub index: usize,
+}
+
+impl PluginInfo<'_> {
+    /// Whether the full ```form_id``` belongs to this plugin: its index is in the upper byte for full plugins,
+    /// light plugins share the upper byte 0xFE and have their index in the next 12 bits.
+    pub fn owns_form_id(&self, form_id: u32) -> bool {
+        match self.is_light {
+            false => (form_id >> 24) as usize == self.index,
+            true => form_id >> 24 == 0xFE && ((form_id >> 12) & 0xFFF) as usize == self.index,
+        }
+    }
+}
+
+/// Where a plugin is in the load order of a save.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PluginSlot {
+    /// The index in the plugin list, or in the light plugin list if ```is_light```.
+    /// This is the index form ids refer to the plugin by.
+    pub index: usize,
+    pub is_light: bool,
+}
+
+/// A plugin only in one of the compared saves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PluginEntry {
+    pub name: String,
+    pub slot: PluginSlot,
+}
+
+/// A plugin in both compared saves, but at a different index (or in the other list).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PluginMove {
+    /// The name as in the first save.
+    pub name: String,
+    pub from: PluginSlot,
+    pub to: PluginSlot,
+}
+
+/// The result of ```SaveFile::plugin_diff()```.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PluginDiff {
+    /// Plugins only in the second save, in its load order.
+    pub added: Vec<PluginEntry>,
+    /// Plugins only in the first save, in its load order.
+    pub removed: Vec<PluginEntry>,
+    /// Plugins in both saves whose index changed, in the load order of the first save.
+    /// Form ids of these plugins refer to a different index in the two saves.
+    pub moved: Vec<PluginMove>,
+}
+
+impl PluginDiff {
+    /// Whether both saves have the same load order.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.moved.is_empty()
+    }
+}
+
+impl SaveFile {
+    /// All plugins followed by all light plugins, the load order the form ids of this save refer to.
+    pub fn plugins(&self) -> impl Iterator<Item = PluginInfo<'_>> {
+        let full = self.plugin_info.iter().enumerate().map(|(index, name)| PluginInfo { name, is_light: false, index });
+        let light = self.light_plugin_info.iter().enumerate().map(|(index, name)| PluginInfo { name, is_light: true, index });
+        full.chain(light)
+    }
+
+    /// Whether ```name``` is in the plugins or the light plugins, e.g. to check that "Dawnguard.esm" is loaded
+    /// before editing its forms. Names are compared ignoring ASCII case, like the game does.
+    pub fn has_plugin(&self, name: &str) -> bool {
+        self.plugin_slots().iter().any(|(plugin, _)| plugin.eq_ignore_ascii_case(name))
+    }
+
+    /// Compares the plugins and light plugins of this save (before) with ```other``` (after).
+    /// Names are compared ignoring ASCII case, like the game does.
+    pub fn plugin_diff(&self, other: &SaveFile) -> PluginDiff {
+        let before = self.plugin_slots();
+        let after = other.plugin_slots();
+        let find = |plugins: &[(&str, PluginSlot)], name: &str| {
+            plugins.iter().find(|(plugin, _)| plugin.eq_ignore_ascii_case(name)).map(|(_, slot)| *slot)
+        };
+
+        let mut diff = PluginDiff::default();
+        for &(name, from) in &before {
+            match find(&after, name) {
+                None => diff.removed.push(PluginEntry { name: name.to_string(), slot: from }),
+                Some(to) if to != from => diff.moved.push(PluginMove { name: name.to_string(), from, to }),
+                Some(_) => {}
+            }
+        }
+        for &(name, slot) in &after {
+            if find(&before, name).is_none() {
+                diff.added.push(PluginEntry { name: name.to_string(), slot });
+            }
+        }
+        diff
+    }
+
+    /// All plugins followed by all light plugins, with their slots.
+    fn plugin_slots(&self) -> Vec<(&str, PluginSlot)> {
+        let full = self.plugin_info.iter().enumerate().map(|(index, name)| (name.as_str(), PluginSlot { index, is_light: false }));
+        let light = self.light_plugin_info.iter().enumerate().map(|(index, name)| (name.as_str(), PluginSlot { index, is_light: true }));
+        full.chain(light).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn plugins_are_listed_in_load_order() {
+        let mut save = parse_save_file(include_bytes!("../benches/data/sample.ess").to_vec()).unwrap();
+        save.plugin_info = vec!["Skyrim.esm".to_string(), "Update.esm".to_string()];
+        save.light_plugin_info = vec!["ccBGSSSE001-Fish.esm".to_string()];
+
+        let plugins: Vec<_> = save.plugins().collect();
+        assert_eq!(plugins, vec![
+            PluginInfo { name: "Skyrim.esm", is_light: false, index: 0 },
+            PluginInfo { name: "Update.esm", is_light: false, index: 1 },
+            PluginInfo { name: "ccBGSSSE001-Fish.esm", is_light: true, index: 0 },
+        ]);
+        assert!(plugins[1].owns_form_id(0x0100_0800));
+        assert!(!plugins[1].owns_form_id(0xFE00_0800));
+        assert!(plugins[2].owns_form_id(0xFE00_0800));
+        assert!(!plugins[2].owns_form_id(0xFE00_1800));
+        assert!(save.has_plugin("update.ESM"));
+    }
+}
@@ -1,111 +1,340 @@
 use crate::fundamental_types::*;
+use crate::ParseWarning;
+use std::borrow::Cow;
 use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::ops::Range;
 
-pub struct SaveFileReader {
+/// Errors that can occur while reading from a savegame buffer.
+#[derive(Clone, Debug)]
+pub enum ReaderError {
+    /// Tried to read ```requested``` bytes at ```index```, but the buffer is only ```buffer_len``` bytes long.
+    UnexpectedEnd { index: usize, requested: usize, buffer_len: usize },
+    /// The string starting at ```index``` is not valid UTF-8.
+    InvalidString { index: usize },
+    /// The section starting at ```index``` declares a size of ```expected``` bytes, but its content takes ```actual``` bytes.
+    SizeMismatch { index: usize, expected: usize, actual: usize },
+    /// The type tag read at ```index``` is unknown, so the width of the data following it is unknown as well.
+    UnknownDataType { index: usize, data_type: u32 },
+    /// The vsval at ```index``` has the invalid size indicator 3.
+    InvalidVSVal { index: usize },
+    /// The ```field``` read at ```index``` has a value that isn't known to be used by the game.
+    UnknownValue { index: usize, field: &'static str, value: u32 },
+}
+
+impl fmt::Display for ReaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReaderError::UnexpectedEnd { index, requested, buffer_len } => write!(
+                f,
+                "Tried to read {} bytes at index {}, but the buffer is only {} bytes long",
+                requested, index, buffer_len
+            ),
+            ReaderError::InvalidString { index } => write!(f, "Invalid string at index {}", index),
+            ReaderError::SizeMismatch { index, expected, actual } => write!(
+                f,
+                "Section at index {} should be {} bytes long, but its content is {} bytes long",
+                index, expected, actual
+            ),
+            ReaderError::UnknownDataType { index, data_type } => {
+                write!(f, "Unknown data type {} at index {}", data_type, index)
+            }
+            ReaderError::InvalidVSVal { index } => write!(f, "Invalid vsval at index {}", index),
+            ReaderError::UnknownValue { index, field, value } => {
+                write!(f, "Unknown value {} of {} at index {}", value, field, index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReaderError {}
+
+/// How ```read_w_string()``` handles a length field that points past the end of the buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum StringMode {
+    /// Fail with ```ReaderError::UnexpectedEnd```.
+    #[default]
+    Strict,
+    /// Clamp the string to the remaining bytes of the buffer, recording ```ParseWarning::ClampedString```.
+    Lossy,
+}
+
+/// What happens on malformed data that can be recovered from, e.g. an invalid vsval or string.
+/// Used by ```SaveFileReader``` and ```ParseOptions```.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum RecoveryStrategy {
+    /// Fail with an error.
+    Strict,
+    /// Recover and record a ```ParseWarning```, collected in ```SaveFile::warnings``` when parsing a save,
+    /// see ```SaveFileReader::take_warnings()```.
+    #[default]
+    Lenient,
+    /// Recover without reporting anything.
+    Silent,
+}
+
+/// Reads the data types used in a savegame from a buffer.
+/// The buffer is either owned (```new()```) or borrowed (```from_slice()```),
+/// so sub-sections can be parsed without copying them out of the parent buffer.
+///
+/// All reads are bounds checked and return ```ReaderError::UnexpectedEnd``` instead of panicking.
+pub struct SaveFileReader<'a> {
     index: usize,
-    buffer: Vec<u8>,
+    buffer: Cow<'a, [u8]>,
+    string_mode: StringMode,
+    recovery: RecoveryStrategy,
+    warnings: Vec<ParseWarning>,
 }
 
-impl SaveFileReader {
+impl SaveFileReader<'static> {
     pub fn new(buffer: Vec<u8>) -> Self {
         SaveFileReader {
             index: 0,
-            buffer,
+            buffer: Cow::Owned(buffer),
+            string_mode: StringMode::default(),
+            recovery: RecoveryStrategy::default(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Like ```new()```, but starts reading at ```index```, e.g. at an offset of the file location table.
+    /// Fails if ```index``` is past the end of the buffer.
+    pub fn new_at(buffer: Vec<u8>, index: usize) -> Result<Self, ReaderError> {
+        let mut reader = SaveFileReader::new(buffer);
+        reader.set_index(index)?;
+        Ok(reader)
+    }
+}
+
+/// A reader of an empty buffer.
+impl Default for SaveFileReader<'_> {
+    fn default() -> Self {
+        SaveFileReader::from_slice(&[])
+    }
+}
+
+impl<'a> SaveFileReader<'a> {
+    pub fn from_slice(buffer: &'a [u8]) -> Self {
+        SaveFileReader {
+            index: 0,
+            buffer: Cow::Borrowed(buffer),
+            string_mode: StringMode::default(),
+            recovery: RecoveryStrategy::default(),
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn with_string_mode(mut self, string_mode: StringMode) -> Self {
+        self.string_mode = string_mode;
+        self
+    }
+
+    pub fn string_mode(&self) -> StringMode {
+        self.string_mode
+    }
+
+    pub fn with_recovery(mut self, recovery: RecoveryStrategy) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
+    /// Records ```warning``` if the recovery strategy is ```RecoveryStrategy::Lenient```.
+    pub(crate) fn warn(&mut self, warning: ParseWarning) {
+        if self.recovery == RecoveryStrategy::Lenient {
+            self.warnings.push(warning);
         }
     }
 
-    pub fn read_f32(&mut self) -> f32 {
-        // should not panic as try_from cannot fail as long as read_bytes actually returns 4 bytes.
-        let bytes: [u8; 4] = <[u8; 4]>::try_from(self.read_bytes_to_vec(4).as_slice()).unwrap();
-        f32::from_le_bytes(bytes)
+    /// Handles an unknown ```value``` of ```field``` read at ```index``` according to the recovery strategy:
+    /// fails with ```ReaderError::UnknownValue``` for ```RecoveryStrategy::Strict```, otherwise the caller
+    /// continues with a substitute.
+    pub(crate) fn recover_unknown_value(&mut self, index: usize, field: &'static str, value: u32) -> Result<(), ReaderError> {
+        match self.recovery {
+            RecoveryStrategy::Strict => Err(ReaderError::UnknownValue { index, field, value }),
+            _ => {
+                self.warn(ParseWarning::UnknownValue { field, value });
+                Ok(())
+            }
+        }
     }
 
-    pub fn read_i32(&mut self) -> i32 {
-        // should not panic as try_from cannot fail as long as read_bytes actually returns 4 bytes.
-        let bytes: [u8; 4] = <[u8; 4]>::try_from(self.read_bytes_to_vec(4).as_slice()).unwrap();
-        i32::from_le_bytes(bytes)
+    /// Returns the warnings recorded so far under ```RecoveryStrategy::Lenient``` and clears them.
+    /// Readers returned by ```sub_reader()``` collect their own warnings.
+    pub fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        std::mem::take(&mut self.warnings)
     }
 
+    pub fn recovery(&self) -> RecoveryStrategy {
+        self.recovery
+    }
 
-    pub fn read_u32(&mut self) -> u32 {
-        // should not panic as try_from cannot fail as long as read_bytes actually returns 4 bytes.
-        let bytes: [u8; 4] = <[u8; 4]>::try_from(self.read_bytes(4)).unwrap();
-        u32::from_le_bytes(bytes)
+    pub fn read_f32(&mut self) -> Result<f32, ReaderError> {
+        Ok(f32::from_le_bytes(self.read_byte_array()?))
     }
 
-    pub fn read_u16(&mut self) -> u16 {
-        // should not panic as try_from cannot fail as long as read_bytes actually returns 4 bytes.
-        let bytes: [u8; 2] = <[u8; 2]>::try_from(self.read_bytes(2)).unwrap();
-        u16::from_le_bytes(bytes)
+    pub fn read_i32(&mut self) -> Result<i32, ReaderError> {
+        Ok(i32::from_le_bytes(self.read_byte_array()?))
     }
 
-    pub fn read_u8(&mut self) -> u8 {
-        let result = self.buffer[self.index];
-        self.index += 1;
-        result
+    pub fn read_u32(&mut self) -> Result<u32, ReaderError> {
+        Ok(u32::from_le_bytes(self.read_byte_array()?))
     }
 
-    /// Reads a vsval. If it has an invalid size indicator, returns U8(0)
-    pub fn read_vsval(&mut self) -> VSVal {
-        let first_byte = self.read_u8();
+    pub fn read_u16(&mut self) -> Result<u16, ReaderError> {
+        Ok(u16::from_le_bytes(self.read_byte_array()?))
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, ReaderError> {
+        let [result] = self.read_byte_array()?;
+        Ok(result)
+    }
+
+    /// Big endian variant of ```read_f32()```, for related formats. Savegames are always little endian.
+    pub fn read_f32_be(&mut self) -> Result<f32, ReaderError> {
+        Ok(f32::from_be_bytes(self.read_byte_array()?))
+    }
+
+    /// Big endian variant of ```read_i32()```.
+    pub fn read_i32_be(&mut self) -> Result<i32, ReaderError> {
+        Ok(i32::from_be_bytes(self.read_byte_array()?))
+    }
+
+    /// Big endian variant of ```read_u32()```.
+    pub fn read_u32_be(&mut self) -> Result<u32, ReaderError> {
+        Ok(u32::from_be_bytes(self.read_byte_array()?))
+    }
+
+    /// Big endian variant of ```read_u16()```.
+    pub fn read_u16_be(&mut self) -> Result<u16, ReaderError> {
+        Ok(u16::from_be_bytes(self.read_byte_array()?))
+    }
+
+    /// Reads a vsval. If it has an invalid size indicator, fails with ```ReaderError::InvalidVSVal```
+    /// or returns U8(0), depending on the ```RecoveryStrategy``` of the reader.
+    pub fn read_vsval(&mut self) -> Result<VSVal, ReaderError> {
+        let index = self.index;
+        let first_byte = self.read_u8()?;
         let val_type_enc = first_byte & 0b00000011;
         match val_type_enc {
-            0 => VSVal::U8((first_byte & 0b11111100) >> 2),
+            0 => Ok(VSVal::U8((first_byte & 0b11111100) >> 2)),
             1 => {
                 let first_byte = first_byte as u16;
-                let second_byte = self.read_u8();
-                VSVal::U16(((second_byte as u16) << 8 ^ first_byte) >> 2)
+                let second_byte = self.read_u8()?;
+                Ok(VSVal::U16(((second_byte as u16) << 8 ^ first_byte) >> 2))
             }
             2 => {
                 let first_byte = first_byte as u32;
-                let second_byte = self.read_u8() as u32;
-                let third_byte = self.read_u8() as u32;
-                VSVal::U32((third_byte << 16 ^ second_byte << 8 ^ first_byte) >> 2)
-            }
-            _ => {
-                println!("Found invalid vsval!");
-                VSVal::U8(0)
+                let second_byte = self.read_u8()? as u32;
+                let third_byte = self.read_u8()? as u32;
+                Ok(VSVal::U32((third_byte << 16 ^ second_byte << 8 ^ first_byte) >> 2))
             }
+            _ => match self.recovery {
+                RecoveryStrategy::Strict => Err(ReaderError::InvalidVSVal { index }),
+                _ => {
+                    self.warn(ParseWarning::InvalidVSVal { index });
+                    Ok(VSVal::U8(0))
+                }
+            },
         }
     }
 
-    pub fn read_w_string(&mut self) -> WString {
-        let length: u16 = self.read_u16();
-        let string_part = &self.buffer[self.index..self.index + length as usize];
-        self.index += length as usize;
+    /// Reads a string prefixed with its length as u16.
+    /// A length exceeding the buffer is handled according to the ```StringMode``` of the reader,
+    /// invalid UTF-8 according to its ```RecoveryStrategy```.
+    pub fn read_w_string(&mut self) -> Result<WString, ReaderError> {
+        let mut length: u16 = self.read_u16()?;
+        let index = self.index;
+        if self.string_mode == StringMode::Lossy && length as usize > self.remaining().len() {
+            // remaining is smaller than length, so it fits into u16
+            let clamped = self.remaining().len() as u16;
+            self.warn(ParseWarning::ClampedString { index, length, clamped });
+            length = clamped;
+        }
+        let recovery = self.recovery;
+        let string_part = self.read_bytes(length as usize)?;
         let content = match std::str::from_utf8(string_part) {
             Ok(str) => str.to_string(),
-            Err(e) => {
-                println!("String parse error: {:?}", e);
+            Err(_) if recovery == RecoveryStrategy::Strict => return Err(ReaderError::InvalidString { index }),
+            Err(_) => {
+                self.warn(ParseWarning::InvalidString { index });
                 "Error while parsing string!".to_string()
             }
         };
-        WString {
+        Ok(WString {
             length,
             content,
+        })
+    }
+
+    pub fn read_string(&mut self, length: usize) -> Result<String, ReaderError> {
+        let index = self.index;
+        let string_part = self.read_bytes(length)?;
+        match std::str::from_utf8(string_part) {
+            Ok(str) => Ok(str.to_string()),
+            Err(_) => Err(ReaderError::InvalidString { index }),
         }
     }
 
-    pub fn read_string(&mut self, length: usize) -> String {
-        let string_part = &self.buffer[self.index..self.index + length];
-        self.index += length;
-        std::str::from_utf8(string_part).expect("Could not parse string.").to_string()
+    /// Reads a string terminated by a 0 byte, or by the end of the buffer.
+    /// The terminator is consumed, but not part of the result.
+    pub fn read_cstring(&mut self) -> Result<String, ReaderError> {
+        let length = self.remaining().iter().position(|byte| *byte == 0).unwrap_or(self.remaining().len());
+        let string = self.read_string(length)?;
+        if self.index < self.buffer.len() {
+            self.index += 1;
+        }
+        Ok(string)
+    }
+
+    /// Reads a fixed number of bytes into an array, used for the number types.
+    fn read_byte_array<const N: usize>(&mut self) -> Result<[u8; N], ReaderError> {
+        // cannot fail as read_bytes returns exactly N bytes on success.
+        Ok(<[u8; N]>::try_from(self.read_bytes(N)?).unwrap())
+    }
+
+    pub fn read_bytes(&mut self, bytes: usize) -> Result<&[u8], ReaderError> {
+        let start = self.index;
+        let end = start.checked_add(bytes).filter(|end| *end <= self.buffer.len()).ok_or(
+            ReaderError::UnexpectedEnd {
+                index: start,
+                requested: bytes,
+                buffer_len: self.buffer.len(),
+            })?;
+        self.index = end;
+        Ok(&self.buffer[start..end])
     }
 
-    fn read_bytes(&mut self, bytes: usize) -> &[u8] {
-        let res = &self.buffer[self.index..self.index + bytes];
-        self.index += bytes;
-        res
+    pub fn read_bytes_to_vec(&mut self, bytes: usize) -> Result<Vec<u8>, ReaderError> {
+        Ok(self.read_bytes(bytes)?.to_vec())
     }
 
-    pub fn read_bytes_to_vec(&mut self, bytes: usize) -> Vec<u8> {
-        let res = &self.buffer[self.index..self.index + bytes];
-        self.index += bytes;
-        res.to_vec()
+    /// Advances past the next ```bytes``` bytes and returns a reader borrowing just those bytes,
+    /// using the same ```StringMode``` and ```RecoveryStrategy```.
+    pub fn read_sub_reader(&mut self, bytes: usize) -> Result<SaveFileReader<'_>, ReaderError> {
+        let start = self.index;
+        self.read_bytes(bytes)?;
+        self.sub_reader(start..self.index)
     }
 
+    /// Returns a reader borrowing ```range``` of the buffer, using the same ```StringMode``` and ```RecoveryStrategy```.
+    /// Independent of the current position, which is not changed.
+    pub fn sub_reader(&self, range: Range<usize>) -> Result<SaveFileReader<'_>, ReaderError> {
+        let section = self.buffer.get(range.clone()).ok_or(ReaderError::UnexpectedEnd {
+            index: range.start,
+            requested: range.end.saturating_sub(range.start),
+            buffer_len: self.buffer.len(),
+        })?;
+        Ok(SaveFileReader::from_slice(section).with_string_mode(self.string_mode).with_recovery(self.recovery))
+    }
+
+    /// Returns the underlying buffer, copying it if it is borrowed.
     pub fn get_buffer(self) -> Vec<u8> {
-        self.buffer
+        self.buffer.into_owned()
+    }
+
+    /// The not yet read part of the buffer.
+    pub fn remaining(&self) -> &[u8] {
+        &self.buffer[self.index..]
     }
 
     pub fn get_buffer_len(&self) -> usize {
@@ -115,57 +344,267 @@ impl SaveFileReader {
     pub fn get_index(&self) -> usize {
         self.index
     }
+
+    /// Moves the position to ```index```, forwards or backwards, so a part of the buffer can be read again.
+    /// The end of the buffer is a valid position, anything past it fails.
+    pub fn set_index(&mut self, index: usize) -> Result<(), ReaderError> {
+        if index > self.buffer.len() {
+            return Err(ReaderError::UnexpectedEnd {
+                index,
+                requested: 0,
+                buffer_len: self.buffer.len(),
+            });
+        }
+        self.index = index;
+        Ok(())
+    }
 }
 
-pub fn read_filetime(r: &mut SaveFileReader) -> FileTime {
-    FileTime {
-        dw_low_date_time: r.read_u32(),
-        dw_high_date_time: r.read_u32(),
+/// A type that can be read from a ```SaveFileReader``` on its own, so reads can be composed generically,
+/// e.g. with ```read_vec()```. Types whose layout depends on context (flags, versions) are read with
+/// free functions taking that context instead.
+pub trait Readable: Sized {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError>;
+}
+
+impl Readable for u8 {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        r.read_u8()
+    }
+}
+
+impl Readable for u16 {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        r.read_u16()
+    }
+}
+
+impl Readable for u32 {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        r.read_u32()
+    }
+}
+
+impl Readable for i32 {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        r.read_i32()
+    }
+}
+
+impl Readable for f32 {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        r.read_f32()
+    }
+}
+
+impl Readable for VSVal {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        r.read_vsval()
+    }
+}
+
+impl Readable for WString {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        r.read_w_string()
+    }
+}
+
+/// Read as wstring, without the length.
+impl Readable for String {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        Ok(r.read_w_string()?.content)
     }
 }
 
-pub fn read_strings_into_vec(save_file_reader: &mut SaveFileReader, count: u32) -> Vec<String> {
-    read_into_vec(save_file_reader, count, |r| r.read_w_string().content)
+impl Readable for FileTime {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        read_filetime(r)
+    }
+}
+
+impl Readable for RefId {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        Ok(RefId {
+            byte0: r.read_u8()?,
+            byte1: r.read_u8()?,
+            byte2: r.read_u8()?,
+        })
+    }
+}
+
+/// Read as RefID, see ```read_ref_id()```.
+impl Readable for FormIdType {
+    fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+        read_ref_id(r)
+    }
+}
+
+/// Reads ```count``` elements of a ```Readable``` type, see ```read_into_vec()```.
+pub fn read_vec<T: Readable>(r: &mut SaveFileReader, count: u32) -> Result<Vec<T>, ReaderError> {
+    read_into_vec(r, count, T::read)
+}
+
+/// Reads ```N``` elements of a ```Readable``` type into an array, for structures with a fixed number of elements.
+/// Unlike ```read_vec()``` nothing is allocated. Stops at the first error.
+pub fn read_array<T: Readable, const N: usize>(r: &mut SaveFileReader) -> Result<[T; N], ReaderError> {
+    let mut error = None;
+    let array: [Option<T>; N] = std::array::from_fn(|_| match error {
+        Some(_) => None,
+        None => T::read(r).map_err(|e| error = Some(e)).ok(),
+    });
+    match error {
+        Some(e) => Err(e),
+        // every element was read if there was no error
+        None => Ok(array.map(|element| element.unwrap())),
+    }
 }
 
-pub fn read_u32s_into_vec(save_file_reader: &mut SaveFileReader, count: u32) -> Vec<u32> {
-    read_into_vec(save_file_reader, count, |r| r.read_u32())
+pub fn read_filetime(r: &mut SaveFileReader) -> Result<FileTime, ReaderError> {
+    Ok(FileTime {
+        dw_low_date_time: r.read_u32()?,
+        dw_high_date_time: r.read_u32()?,
+    })
 }
 
-pub fn read_ref_ids_into_vec(r: &mut SaveFileReader, count: u32) -> Vec<FormIdType> {
-    read_into_vec(r, count, |r| read_ref_id(r))
+pub fn read_strings_into_vec(save_file_reader: &mut SaveFileReader, count: u32) -> Result<Vec<String>, ReaderError> {
+    read_vec(save_file_reader, count)
 }
 
+pub fn read_u32s_into_vec(save_file_reader: &mut SaveFileReader, count: u32) -> Result<Vec<u32>, ReaderError> {
+    read_vec(save_file_reader, count)
+}
+
+pub fn read_ref_ids_into_vec(r: &mut SaveFileReader, count: u32) -> Result<Vec<FormIdType>, ReaderError> {
+    read_vec(r, count)
+}
+
+/// The most elements ```read_into_vec()``` reserves space for before reading them.
+const MAX_PREALLOCATED_ELEMENTS: usize = 1 << 16;
+
 /// Calls ```func``` with the argument ```arg``` ```count``` times and stores the result of those calls in a ```Vec```.
+/// Stops at the first error returned by ```func```.
 ///
 /// This function is normally used to read loads of elements from an array.
-pub fn read_into_vec<S, T>(arg: &mut S, count: u32, func: fn(&mut S) -> T) -> Vec<T> {
+pub fn read_into_vec<S, T, E>(arg: &mut S, count: u32, func: fn(&mut S) -> Result<T, E>) -> Result<Vec<T>, E> {
     let arr_count: usize = match count.try_into() {
         Ok(c) => c,
-        Err(_) => usize::max_value()
+        Err(_) => usize::MAX
     };
-    let mut vec: Vec<T> = Vec::with_capacity(arr_count);
+    // a corrupted count must not reserve gigabytes up front, the Vec grows if there really are more elements
+    let mut vec: Vec<T> = Vec::with_capacity(arr_count.min(MAX_PREALLOCATED_ELEMENTS));
     for _i in 0..count {
-        vec.push(func(arg));
+        vec.push(func(arg)?);
     }
-    vec
+    Ok(vec)
 }
 
-pub fn read_ref_id(sfr: &mut SaveFileReader) -> FormIdType {
-    RefId {
-        byte0: sfr.read_u8(),
-        byte1: sfr.read_u8(),
-        byte2: sfr.read_u8(),
-    }.get_form_id()
+/// Calls ```func``` on a reader limited to the next ```size``` bytes and checks that it consumes all of them.
+/// Used for sections that declare their size, so a content not matching it fails with
+/// ```ReaderError::SizeMismatch``` instead of desyncing everything after the section.
+pub fn read_sized<T, E: From<ReaderError>>(r: &mut SaveFileReader, size: u32, func: impl FnOnce(&mut SaveFileReader) -> Result<T, E>) -> Result<T, E> {
+    let index = r.get_index();
+    let mut section = r.read_sub_reader(size as usize)?;
+    let result = func(&mut section)?;
+    let (end, mut warnings) = (section.get_index(), section.take_warnings());
+    r.warnings.append(&mut warnings);
+    if end != size as usize {
+        return Err(ReaderError::SizeMismatch { index, expected: size as usize, actual: end }.into());
+    }
+    Ok(result)
+}
+
+/// Reads ```count``` elements with ```func``` that have to take exactly ```size``` bytes, see ```read_sized()```.
+pub fn read_sized_array<T>(r: &mut SaveFileReader, size: u32, count: u32, func: fn(&mut SaveFileReader) -> Result<T, ReaderError>) -> Result<Vec<T>, ReaderError> {
+    let index = r.get_index();
+    let mut section = r.read_sub_reader(size as usize)?;
+    let result = read_into_vec(&mut section, count, func)?;
+    let (end, mut warnings) = (section.get_index(), section.take_warnings());
+    r.warnings.append(&mut warnings);
+    if end != size as usize {
+        return Err(ReaderError::SizeMismatch { index, expected: size as usize, actual: end });
+    }
+    Ok(result)
+}
+
+pub fn read_ref_id(sfr: &mut SaveFileReader) -> Result<FormIdType, ReaderError> {
+    Ok(RefId::read(sfr)?.get_form_id())
 }
 
 /// Convenience function for when vsvals are used as array size indicators for usage in loops.
 /// This function returns a u32 that can be used directly instead of a vsval enum variant that first
 /// has to be matched
-pub fn read_vsval_to_u32(sfr: &mut SaveFileReader) -> u32 {
-    match sfr.read_vsval() {
-        VSVal::U8(x) => x as u32,
-        VSVal::U16(x) => x as u32,
-        VSVal::U32(x) => x
+pub fn read_vsval_to_u32(sfr: &mut SaveFileReader) -> Result<u32, ReaderError> {
+    Ok(sfr.read_vsval()?.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reader(bytes: &[u8], recovery: RecoveryStrategy) -> SaveFileReader<'_> {
+        SaveFileReader::from_slice(bytes).with_recovery(recovery)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn invalid_vsval_is_handled_by_the_recovery_strategy() {
+        let bytes = [0b11];
+        assert!(matches!(reader(&bytes, RecoveryStrategy::Strict).read_vsval(), Err(ReaderError::InvalidVSVal { index: 0 })));
+
+        let mut r = reader(&bytes, RecoveryStrategy::Lenient);
+        assert_eq!(r.read_vsval().unwrap(), VSVal::U8(0));
+        assert_eq!(r.take_warnings(), vec![ParseWarning::InvalidVSVal { index: 0 }]);
+        assert!(r.take_warnings().is_empty());
+
+        let mut r = reader(&bytes, RecoveryStrategy::Silent);
+        assert_eq!(r.read_vsval().unwrap(), VSVal::U8(0));
+        assert!(r.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn invalid_string_is_handled_by_the_recovery_strategy() {
+        let bytes = [2, 0, 0xFF, 0xFE];
+        assert!(matches!(reader(&bytes, RecoveryStrategy::Strict).read_w_string(), Err(ReaderError::InvalidString { index: 2 })));
+
+        let mut r = reader(&bytes, RecoveryStrategy::Lenient);
+        assert_eq!(r.read_w_string().unwrap().length, 2);
+        assert_eq!(r.take_warnings(), vec![ParseWarning::InvalidString { index: 2 }]);
+
+        let mut r = reader(&bytes, RecoveryStrategy::Silent);
+        r.read_w_string().unwrap();
+        assert!(r.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn lossy_string_is_clamped_with_a_warning() {
+        let bytes = [5, 0, b'a', b'b'];
+        let mut r = reader(&bytes, RecoveryStrategy::Lenient).with_string_mode(StringMode::Lossy);
+        assert_eq!(r.read_w_string().unwrap().content, "ab");
+        assert_eq!(r.take_warnings(), vec![ParseWarning::ClampedString { index: 2, length: 5, clamped: 2 }]);
+        assert!(reader(&bytes, RecoveryStrategy::Lenient).read_w_string().is_err());
+    }
+
+    /// A type without ```Default```.
+    #[derive(Debug, PartialEq)]
+    struct Pair(u8, u8);
+
+    impl Readable for Pair {
+        fn read(r: &mut SaveFileReader) -> Result<Self, ReaderError> {
+            Ok(Pair(r.read_u8()?, r.read_u8()?))
+        }
+    }
+
+    #[test]
+    fn read_array_reads_types_without_default() {
+        let mut r = SaveFileReader::from_slice(&[1, 2, 3, 4, 5]);
+        assert_eq!(read_array::<Pair, 2>(&mut r).unwrap(), [Pair(1, 2), Pair(3, 4)]);
+        assert!(matches!(read_array::<Pair, 1>(&mut r), Err(ReaderError::UnexpectedEnd { index: 5, .. })));
+    }
+
+    #[test]
+    fn read_sized_keeps_the_warnings_of_the_section() {
+        let bytes = [0b11, 0];
+        let mut r = reader(&bytes, RecoveryStrategy::Lenient);
+        read_sized(&mut r, 1, |section| section.read_vsval()).unwrap();
+        assert_eq!(r.take_warnings(), vec![ParseWarning::InvalidVSVal { index: 0 }]);
+    }
+}
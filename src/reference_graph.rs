@@ -0,0 +1,170 @@
+//! A directed graph of which form references which, e.g. to visualize it with Graphviz.
+
+use crate::*;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// What a ```ReferenceEdge``` was built from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ReferenceKind {
+    /// From the criminal of a crime to its victim.
+    CrimeVictim,
+    /// From the criminal of a theft to the base object of the stolen item.
+    CrimeItem,
+    /// From the criminal of a theft to the owner of the stolen item.
+    CrimeOwnership,
+    /// From the criminal of a crime to the faction the crime was reported to.
+    CrimeFaction,
+    /// From a witness of a crime to the criminal.
+    CrimeWitness,
+    /// From a created enchantment, potion or poison to one of its magic effects.
+    EnchantmentEffect,
+    /// From an actor reference to the animation it plays.
+    AnimObject,
+    /// From an ingredient to an ingredient it was combined with.
+    IngredientCombination,
+    /// From an NPC to a faction it is in, see ```NpcData::factions```.
+    Faction,
+    /// From an NPC to a spell, leveled spell or shout, see ```NpcData::spell_list```.
+    Spell,
+    /// From a cell to its owning faction or NPC.
+    CellOwnership,
+}
+
+impl ReferenceKind {
+    /// A short name of the kind, used as the edge label in ```ReferenceGraph::to_dot()```.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReferenceKind::CrimeVictim => "victim",
+            ReferenceKind::CrimeItem => "stolen item",
+            ReferenceKind::CrimeOwnership => "stolen from",
+            ReferenceKind::CrimeFaction => "crime faction",
+            ReferenceKind::CrimeWitness => "witnessed",
+            ReferenceKind::EnchantmentEffect => "effect",
+            ReferenceKind::AnimObject => "animation",
+            ReferenceKind::IngredientCombination => "combined with",
+            ReferenceKind::Faction => "faction",
+            ReferenceKind::Spell => "spell",
+            ReferenceKind::CellOwnership => "owner",
+        }
+    }
+}
+
+/// A reference from one form to another, both as full form ids.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReferenceEdge {
+    pub from: u32,
+    pub to: u32,
+    pub kind: ReferenceKind,
+}
+
+/// The result of ```SaveFile::reference_graph()```.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReferenceGraph {
+    /// All forms with at least one edge, sorted.
+    pub nodes: BTreeSet<u32>,
+    /// All edges, sorted by ```from```, then ```to```. The same edge is only contained once.
+    pub edges: BTreeSet<ReferenceEdge>,
+}
+
+impl ReferenceGraph {
+    /// Adds an edge between the resolved ids, skipping ids that can't be resolved and null references.
+    fn add(&mut self, save: &SaveFile, from: FormIdType, to: FormIdType, kind: ReferenceKind) {
+        let (from, to) = match (save.resolve_form_id(from), save.resolve_form_id(to)) {
+            (Some(from), Some(to)) if from != 0 && to != 0 => (from, to),
+            _ => return,
+        };
+        self.nodes.extend([from, to]);
+        self.edges.insert(ReferenceEdge { from, to, kind });
+    }
+
+    /// The edges starting at ```form_id```.
+    pub fn edges_from(&self, form_id: u32) -> impl Iterator<Item = &ReferenceEdge> + '_ {
+        self.edges.iter().filter(move |edge| edge.from == form_id)
+    }
+
+    /// The edges ending at ```form_id```.
+    pub fn edges_to(&self, form_id: u32) -> impl Iterator<Item = &ReferenceEdge> + '_ {
+        self.edges.iter().filter(move |edge| edge.to == form_id)
+    }
+
+    /// The graph in the DOT language of Graphviz, nodes are labeled with their form id in hex
+    /// and edges with ```ReferenceKind::name()```.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph references {\n");
+        for node in &self.nodes {
+            // writing into a String can't fail
+            let _ = writeln!(dot, "    \"{:08X}\";", node);
+        }
+        for edge in &self.edges {
+            let _ = writeln!(dot, "    \"{:08X}\" -> \"{:08X}\" [label=\"{}\"];", edge.from, edge.to, edge.kind.name());
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl SaveFile {
+    /// Builds the graph of references between forms from the crimes, created enchantments, animation objects
+    /// and ingredient combinations in the global data tables and the NPC and cell change forms.
+    ///
+    /// Ref ids whose meaning is unknown (e.g. most of ```QuestStaticData```) give no edge, as it isn't known
+    /// which form holds them. Change forms that can't be decoded are skipped.
+    pub fn reference_graph(&self) -> ReferenceGraph {
+        let mut graph = ReferenceGraph::default();
+        for data in self.global_data_table_1.iter().chain(&self.global_data_table_2).chain(&self.global_data_table_3) {
+            match data {
+                GlobalDataType::ProcessLists(p) => {
+                    for crime in &p.all_crimes {
+                        graph.add(self, crime.criminal_id, crime.victim_id, ReferenceKind::CrimeVictim);
+                        graph.add(self, crime.criminal_id, crime.item_base_id, ReferenceKind::CrimeItem);
+                        graph.add(self, crime.criminal_id, crime.ownership_id, ReferenceKind::CrimeOwnership);
+                        graph.add(self, crime.criminal_id, crime.crime_faction_id, ReferenceKind::CrimeFaction);
+                        for witness in &crime.witnesses {
+                            graph.add(self, *witness, crime.criminal_id, ReferenceKind::CrimeWitness);
+                        }
+                    }
+                }
+                GlobalDataType::CreatedObjects(c) => {
+                    for enchantment in c.weapon_ench_table.iter()
+                        .chain(&c.armour_ench_table)
+                        .chain(&c.potion_table)
+                        .chain(&c.poison_table) {
+                        for effect in &enchantment.effects {
+                            graph.add(self, enchantment.ref_id, effect.effect_id, ReferenceKind::EnchantmentEffect);
+                        }
+                    }
+                }
+                GlobalDataType::AnimObjects(a) => {
+                    a.iter().for_each(|a| graph.add(self, a.achr, a.anim, ReferenceKind::AnimObject));
+                }
+                GlobalDataType::IngredientShared(i) => {
+                    i.iter().for_each(|i| graph.add(self, i.ingredient0, i.ingredient1, ReferenceKind::IngredientCombination));
+                }
+                _ => {}
+            }
+        }
+
+        for change_form in &self.change_forms {
+            match read_change_form_data(change_form) {
+                Ok(ChangeFormData::Npc(npc)) => {
+                    for faction in npc.factions.iter().flatten() {
+                        graph.add(self, change_form.form_id, faction.faction, ReferenceKind::Faction);
+                    }
+                    if let Some(list) = &npc.spell_list {
+                        for spell in list.spells.iter().chain(&list.leveled_spells).chain(&list.shouts) {
+                            graph.add(self, change_form.form_id, *spell, ReferenceKind::Spell);
+                        }
+                    }
+                }
+                Ok(ChangeFormData::Cell(cell)) => {
+                    if let Some(owner) = cell.ownership {
+                        graph.add(self, change_form.form_id, owner, ReferenceKind::CellOwnership);
+                    }
+                }
+                _ => {}
+            }
+        }
+        graph
+    }
+}
@@ -0,0 +1,183 @@
+//! A plaintext report of a parsed save, to be attached to bug reports instead of the save itself.
+
+use crate::{ChangeFormType, FormIdType, GlobalDataType, SaveFile};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+impl SaveFile {
+    /// Produces a human readable dump of the parsed save: header, plugins, global variables,
+    /// crimes, created objects and a histogram of the change form types.
+    /// Binary blobs (screenshot, unparsed data) are omitted.
+    pub fn to_report(&self) -> String {
+        let mut report = String::new();
+        // writing into a String can't fail
+        self.write_report(&mut report).unwrap();
+        report
+    }
+
+    fn write_report(&self, out: &mut String) -> std::fmt::Result {
+        let header = &self.header;
+        writeln!(out, "== Header ==")?;
+        writeln!(out, "Player: {} (level {}, {}, {})", header.player_name, header.player_level,
+                 header.player_race_editor_id, header.player_sex)?;
+        writeln!(out, "Experience: {} / {}", header.player_cur_exp, header.player_lvl_up_exp)?;
+        writeln!(out, "Location: {}", header.player_location)?;
+        writeln!(out, "Game date: {}", header.game_date)?;
+        writeln!(out, "Save number: {}", header.save_number)?;
+        writeln!(out, "Save version: {} ({:?}), form version {}", header.version, self.edition(), self.form_version)?;
+        for warning in &self.warnings {
+            writeln!(out, "Warning: {}", warning)?;
+        }
+        writeln!(out)?;
+
+        writeln!(out, "== Plugins ({}) ==", self.plugin_info.len())?;
+        for (index, plugin) in self.plugin_info.iter().enumerate() {
+            writeln!(out, "{:02X} {}", index, plugin)?;
+        }
+        if !self.light_plugin_info.is_empty() {
+            writeln!(out, "== Light plugins ({}) ==", self.light_plugin_info.len())?;
+            for (index, plugin) in self.light_plugin_info.iter().enumerate() {
+                writeln!(out, "FE:{:03X} {}", index, plugin)?;
+            }
+        }
+        writeln!(out)?;
+
+        for data in self.global_data() {
+            match data {
+                GlobalDataType::GlobalVariables(variables) => {
+                    writeln!(out, "== Global variables ({}) ==", variables.len())?;
+                    for variable in variables {
+                        writeln!(out, "{} = {}", self.format_form_id(variable.form_id), variable.value)?;
+                    }
+                    writeln!(out)?;
+                }
+                GlobalDataType::ProcessLists(process_lists) => {
+                    let active: Vec<_> = process_lists.all_crimes.iter().filter(|c| !c.is_cleared).collect();
+                    writeln!(out, "== Crimes ({}, {} active) ==", process_lists.all_crimes.len(), active.len())?;
+                    // summed as u64, the u32 bounties of many crimes can exceed u32::MAX
+                    let mut bounties: BTreeMap<String, (usize, u64)> = BTreeMap::new();
+                    for crime in &active {
+                        let entry = bounties.entry(self.format_form_id(crime.crime_faction_id)).or_default();
+                        entry.0 += 1;
+                        entry.1 += u64::from(crime.bounty);
+                    }
+                    for (faction, (count, bounty)) in bounties {
+                        writeln!(out, "Faction {}: {} active crime(s), bounty {}", faction, count, bounty)?;
+                    }
+                    let mut types: BTreeMap<String, usize> = BTreeMap::new();
+                    for crime in &process_lists.all_crimes {
+                        *types.entry(format!("{:?}", crime.crime_type)).or_default() += 1;
+                    }
+                    for (crime_type, count) in types {
+                        writeln!(out, "{}: {}", crime_type, count)?;
+                    }
+                    writeln!(out)?;
+                }
+                GlobalDataType::CreatedObjects(created) => {
+                    writeln!(out, "== Created objects ==")?;
+                    writeln!(out, "Weapon enchantments: {}", created.weapon_ench_table.len())?;
+                    writeln!(out, "Armour enchantments: {}", created.armour_ench_table.len())?;
+                    writeln!(out, "Potions: {}", created.potion_table.len())?;
+                    writeln!(out, "Poisons: {}", created.poison_table.len())?;
+                    writeln!(out)?;
+                }
+                _ => {}
+            }
+        }
+
+        writeln!(out, "== Change forms ({}) ==", self.change_forms.len())?;
+        let mut histogram: BTreeMap<&'static str, usize> = BTreeMap::new();
+        for change_form in &self.change_forms {
+            *histogram.entry(ChangeFormType::from_data_type(change_form.data_type).name()).or_default() += 1;
+        }
+        for (name, count) in histogram {
+            writeln!(out, "{}: {}", name, count)?;
+        }
+        Ok(())
+    }
+
+    fn global_data(&self) -> impl Iterator<Item = &GlobalDataType> {
+        self.global_data_table_1.iter()
+            .chain(&self.global_data_table_2)
+            .chain(&self.global_data_table_3)
+    }
+
+    /// The resolved form id in hex, or the unresolved id if it can't be resolved.
+    fn format_form_id(&self, form_id: FormIdType) -> String {
+        match self.resolve_form_id(form_id) {
+            Some(id) => format!("{:08X}", id),
+            None => format!("{:?}", form_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_save_file, Crime, CrimeType, ProcessLists};
+
+    const COMPANIONS_FACTION: FormIdType = FormIdType::Default(0x48362);
+
+    fn crime(crime_type: CrimeType, bounty: u32, crime_faction_id: FormIdType, is_cleared: bool) -> Crime {
+        Crime {
+            witness_num: 0,
+            crime_type,
+            u1: 0,
+            quantity: 0,
+            serial_num: 0,
+            u2: 0,
+            u3: 0,
+            elapsed_time: 0.0,
+            victim_id: FormIdType::Default(0),
+            criminal_id: FormIdType::Default(0x14),
+            item_base_id: FormIdType::Default(0),
+            ownership_id: FormIdType::Default(0),
+            witnesses: Vec::new(),
+            bounty,
+            crime_faction_id,
+            is_cleared,
+            u4: 0,
+        }
+    }
+
+    #[test]
+    fn report_sums_large_bounties() {
+        let mut save = parse_save_file(include_bytes!("../benches/data/sample.ess").to_vec()).unwrap();
+        let mut all_crimes: Vec<Crime> = (0..3).map(|_| crime(CrimeType::Murder, u32::MAX, COMPANIONS_FACTION, false)).collect();
+        all_crimes.push(crime(CrimeType::Theft, 1000, COMPANIONS_FACTION, true));
+        all_crimes.push(crime(CrimeType::Assault, 40, FormIdType::Index(0), false));
+        save.global_data_table_1.retain(|data| !matches!(data, GlobalDataType::ProcessLists(_)));
+        save.global_data_table_1.push(GlobalDataType::ProcessLists(ProcessLists {
+            u1: 0.0,
+            u2: 0.0,
+            u3: 0.0,
+            next_num: 5,
+            all_crimes,
+            remainder: Vec::new(),
+        }));
+
+        let report = save.to_report();
+        let mut written = String::new();
+        save.write_report(&mut written).unwrap();
+        assert_eq!(report, written);
+
+        let lines: Vec<&str> = report.lines().collect();
+        let crimes = lines.iter().position(|line| *line == "== Crimes (5, 4 active) ==").unwrap();
+        let index_faction = format!("{:08X}", save.form_id_array[0]);
+        let mut factions = vec![
+            format!("Faction 00048362: 3 active crime(s), bounty {}", 3 * u64::from(u32::MAX)),
+            format!("Faction {}: 1 active crime(s), bounty 40", index_faction),
+        ];
+        // the factions are sorted by their form id
+        factions.sort();
+        assert_eq!(lines[crimes + 1..crimes + 3], factions);
+        assert_eq!(lines[crimes + 3..crimes + 6], ["Assault: 1", "Murder: 3", "Theft: 1"]);
+        assert_eq!(lines[crimes + 6], "");
+
+        for header in ["== Header ==", &format!("== Plugins ({}) ==", save.plugin_info.len()), "== Change forms (603) =="] {
+            assert!(lines.contains(&header), "missing {}", header);
+        }
+        assert!(lines.iter().position(|line| *line == "== Header ==").unwrap() < crimes);
+        assert!(lines.iter().position(|line| *line == "== Change forms (603) ==").unwrap() > crimes);
+    }
+}
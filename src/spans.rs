@@ -0,0 +1,156 @@
+//! Maps the parsed fields of a save to the bytes they were read from, e.g. to highlight them in a hex view.
+
+use crate::*;
+use std::ops::Range;
+
+/// Where the range of a ```FieldSpan``` points into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpanSource {
+    /// The save file: magic, header, screenshot and the stored body.
+    File,
+    /// The uncompressed body, see ```decompress_body()```.
+    Body,
+}
+
+/// The bytes a field was read from, see ```parse_save_file_with_spans()```.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldSpan {
+    /// The path of the field, e.g. "header.player_name" or "change_forms[3].data".
+    pub path: String,
+    pub source: SpanSource,
+    pub range: Range<usize>,
+}
+
+/// Like ```parse_save_file_with_options()```, but also returns the byte ranges of the fields.
+///
+/// The header and the tables of the body are mapped field by field. Global data entries and change forms
+/// are mapped down to their type, length and data, the fields inside their data are not.
+/// The spans are recorded in a second pass after parsing, so the common parse isn't slowed down.
+pub fn parse_save_file_with_spans(buf: &[u8], options: &ParseOptions) -> Result<(SaveFile, Vec<FieldSpan>), SaveParseError> {
+    let save = parse_save_file_with_options(buf, options)?;
+    let mut recorder = SpanRecorder { source: SpanSource::File, spans: Vec::new() };
+
+    let mut sfr = SaveFileReader::from_slice(buf).with_string_mode(options.string_mode).with_recovery(options.recovery);
+    recorder.record(&mut sfr, "magic", |r| r.read_bytes(SAVE_MAGIC.len()).map(|_| ()))?;
+    recorder.record(&mut sfr, "header_size", |r| r.read_u32())?;
+    record_header(&mut recorder, &mut sfr)?;
+    recorder.record(&mut sfr, "screenshot", |r| r.read_bytes(save.screenshot_data.data.len()).map(|_| ()))?;
+    let mut body_reader = SaveFileReader::from_slice(buf);
+    read_preamble_layout(&mut body_reader)?;
+    // the body lengths are missing in old saves, see read_preamble_layout()
+    if sfr.get_index() < body_reader.get_index() {
+        recorder.record(&mut sfr, "body_uncompressed_len", |r| r.read_u32())?;
+        recorder.record(&mut sfr, "body_compressed_len", |r| r.read_u32())?;
+    }
+    let body = read_body(&body_reader, &save.header, save.body_uncompressed_len)?;
+    let stored_len = match save.header.compression_type {
+        0 => body.len(),
+        _ => sfr.remaining().len(),
+    };
+    recorder.record(&mut sfr, "body", |r| r.read_bytes(stored_len).map(|_| ()))?;
+
+    recorder.source = SpanSource::Body;
+    record_body(&mut recorder, &mut SaveFileReader::from_slice(&body).with_string_mode(options.string_mode).with_recovery(options.recovery), &save)?;
+    Ok((save, recorder.spans))
+}
+
+struct SpanRecorder {
+    source: SpanSource,
+    spans: Vec<FieldSpan>,
+}
+
+impl SpanRecorder {
+    /// Reads a field with ```read``` and records the bytes it took as ```path```.
+    fn record<T, E>(&mut self, r: &mut SaveFileReader, path: impl Into<String>, read: impl FnOnce(&mut SaveFileReader) -> Result<T, E>) -> Result<T, E> {
+        let start = r.get_index();
+        let value = read(r)?;
+        self.spans.push(FieldSpan { path: path.into(), source: self.source, range: start..r.get_index() });
+        Ok(value)
+    }
+}
+
+/// Records the fields in the order of ```read_header()```.
+fn record_header(recorder: &mut SpanRecorder, r: &mut SaveFileReader) -> Result<(), SaveParseError> {
+    let version = recorder.record(r, "header.version", |r| r.read_u32())?;
+    recorder.record(r, "header.save_number", |r| r.read_u32())?;
+    recorder.record(r, "header.player_name", |r| r.read_w_string())?;
+    recorder.record(r, "header.player_level", |r| r.read_u32())?;
+    recorder.record(r, "header.player_location", |r| r.read_w_string())?;
+    recorder.record(r, "header.game_date", |r| r.read_w_string())?;
+    recorder.record(r, "header.player_race_editor_id", |r| r.read_w_string())?;
+    recorder.record(r, "header.player_sex", |r| r.read_u16())?;
+    recorder.record(r, "header.player_cur_exp", |r| r.read_f32())?;
+    recorder.record(r, "header.player_lvl_up_exp", |r| r.read_f32())?;
+    recorder.record(r, "header.filetime", read_filetime)?;
+    recorder.record(r, "header.shot_width", |r| r.read_u32())?;
+    recorder.record(r, "header.shot_height", |r| r.read_u32())?;
+    if version >= COMPRESSION_TYPE_MIN_VERSION {
+        recorder.record(r, "header.compression_type", |r| r.read_u16())?;
+    }
+    Ok(())
+}
+
+/// Records the fields in the order of ```parse_save_file_with_options()```, which already read
+/// ```save``` from the same body, so only the sizes it found are used.
+fn record_body(recorder: &mut SpanRecorder, r: &mut SaveFileReader, save: &SaveFile) -> Result<(), SaveParseError> {
+    recorder.record(r, "form_version", |r| r.read_u8())?;
+    recorder.record(r, "plugin_info.size", |r| r.read_u32())?;
+    recorder.record(r, "plugin_info.count", |r| r.read_u8())?;
+    for index in 0..save.plugin_info.len() {
+        recorder.record(r, format!("plugin_info.plugins[{}]", index), |r| r.read_w_string())?;
+    }
+    if save.form_version >= LIGHT_PLUGIN_MIN_FORM_VERSION {
+        recorder.record(r, "plugin_info.light_count", |r| r.read_u16())?;
+        for index in 0..save.light_plugin_info.len() {
+            recorder.record(r, format!("plugin_info.light_plugins[{}]", index), |r| r.read_w_string())?;
+        }
+    }
+
+    for field in [
+        "form_id_array_count_offset", "unknown_table_3_offset", "global_data_table_1_offset", "global_data_table_2_offset",
+        "change_forms_offset", "global_data_table_3_offset", "global_data_table_1_count", "global_data_table_2_count",
+        "global_data_table_3_count", "change_form_count",
+    ] {
+        recorder.record(r, format!("file_location_table.{}", field), |r| r.read_u32())?;
+    }
+    recorder.record(r, "file_location_table.reserved", |r| r.read_bytes(4 * 15).map(|_| ()))?;
+
+    record_global_data(recorder, r, "global_data_table_1", save.global_data_table_1.len())?;
+    record_global_data(recorder, r, "global_data_table_2", save.global_data_table_2.len())?;
+    // not save.change_forms.len(), which can be truncated by ParseOptions::max_change_forms
+    for index in 0..save.file_location_table.change_form_count {
+        let header = recorder.record(r, format!("change_forms[{}].header", index), read_change_form_header)?;
+        recorder.record(r, format!("change_forms[{}].data", index), |r| r.read_bytes(header.length1 as usize).map(|_| ()))?;
+    }
+    record_global_data(recorder, r, "global_data_table_3", save.global_data_table_3.len())?;
+
+    recorder.record(r, "form_id_array.count", |r| r.read_u32())?;
+    recorder.record(r, "form_id_array.ids", |r| r.read_bytes(4 * save.form_id_array.len()).map(|_| ()))?;
+    recorder.record(r, "visited_worldspace_array.count", |r| r.read_u32())?;
+    recorder.record(r, "visited_worldspace_array.ids", |r| r.read_bytes(4 * save.visited_worldspace_array.len()).map(|_| ()))?;
+
+    let size = recorder.record(r, "unknown_3_table.size", |r| r.read_u32())?;
+    recorder.record(r, "unknown_3_table.count", |r| r.read_u32())?;
+    match save.unknown_3_table.len() {
+        // see the padding in parse_save_file_with_options()
+        0 => recorder.record(r, "unknown_3_table.padding", |r| r.read_bytes(size.saturating_sub(4) as usize).map(|_| ()))?,
+        count => {
+            for index in 0..count {
+                recorder.record(r, format!("unknown_3_table.strings[{}]", index), |r| r.read_w_string())?;
+            }
+        }
+    }
+    if !save.trailing_bytes.is_empty() {
+        recorder.record(r, "trailing_bytes", |r| r.read_bytes(save.trailing_bytes.len()).map(|_| ()))?;
+    }
+    Ok(())
+}
+
+fn record_global_data(recorder: &mut SpanRecorder, r: &mut SaveFileReader, table: &str, count: usize) -> Result<(), SaveParseError> {
+    for index in 0..count {
+        recorder.record(r, format!("{}[{}].type", table, index), |r| r.read_u32())?;
+        let length = recorder.record(r, format!("{}[{}].length", table, index), |r| r.read_u32())?;
+        recorder.record(r, format!("{}[{}].data", table, index), |r| r.read_bytes(length as usize).map(|_| ()))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,212 @@
+//! Parsing a save section by section into callbacks, without building a ```SaveFile```.
+
+use crate::*;
+
+/// Receives the parts of a save in the order they are stored, see ```parse_save_file_streaming()```.
+/// All methods do nothing by default, so only the needed ones have to be implemented.
+pub trait SaveVisitor {
+    fn on_header(&mut self, _header: &Header) {}
+
+    /// Called for every plugin, ```index``` counts full and light plugins separately.
+    fn on_plugin(&mut self, _index: usize, _name: &str, _is_light: bool) {}
+
+    fn on_file_location_table(&mut self, _table: &FileLocationTable) {}
+
+    /// Called for every global data entry, ```table``` is 1, 2 or 3.
+    fn on_global_data(&mut self, _table: u8, _data: GlobalDataType) {}
+
+    fn on_change_form(&mut self, _change_form: ChangeForm) {}
+
+    fn on_form_id_array(&mut self, _form_id_array: &[u32]) {}
+
+    /// Called for every problem that didn't stop the parse, the warnings ```SaveFile::warnings``` would hold.
+    fn on_warning(&mut self, _warning: ParseWarning) {}
+}
+
+/// Parses a save like ```parse_save_file()```, but hands every part to ```visitor``` instead of
+/// collecting them. Global data entries and change forms are passed by value and can be dropped
+/// right away, so only the (decompressed) body and one entry at a time are kept in memory.
+///
+/// The visited worldspace array and unknown table 3 are not read. The screenshot is skipped.
+pub fn parse_save_file_streaming(buf: &[u8], visitor: &mut impl SaveVisitor) -> Result<(), SaveParseError> {
+    parse_save_file_streaming_with_options(buf, &ParseOptions::default(), visitor)
+}
+
+/// Like ```parse_save_file_streaming()```, with the ```ParseOptions``` of ```parse_save_file_with_options()```.
+/// The warnings are passed to ```SaveVisitor::on_warning()``` as they occur.
+pub fn parse_save_file_streaming_with_options(buf: &[u8], options: &ParseOptions, visitor: &mut impl SaveVisitor) -> Result<(), SaveParseError> {
+    let mut sfr = SaveFileReader::from_slice(buf).with_string_mode(options.string_mode).with_recovery(options.recovery);
+    let layout = read_preamble_layout(&mut sfr)?;
+    if let Some(limit) = options.max_body_size.filter(|limit| layout.uncompressed_len as usize > *limit) {
+        return Err(SaveParseError::BodyTooLarge { requested: layout.uncompressed_len as usize, limit });
+    }
+    visitor.on_header(&layout.header);
+    sfr.take_warnings().into_iter().for_each(|warning| visitor.on_warning(warning));
+
+    let body = read_body(&sfr, &layout.header, layout.uncompressed_len)?;
+    let mut sfr_body = SaveFileReader::from_slice(&body).with_string_mode(options.string_mode).with_recovery(options.recovery);
+    let form_version = sfr_body.read_u8()?;
+    if !KNOWN_FORM_VERSIONS.contains(&form_version) {
+        visitor.on_warning(ParseWarning::UnknownFormVersion(form_version));
+    }
+    let (plugins, light_plugins) = read_plugin_info(&mut sfr_body, form_version)?;
+    plugins.iter().enumerate().for_each(|(index, plugin)| visitor.on_plugin(index, plugin, false));
+    light_plugins.iter().enumerate().for_each(|(index, plugin)| visitor.on_plugin(index, plugin, true));
+
+    let table = read_file_location_table(&mut sfr_body)?;
+    visitor.on_file_location_table(&table);
+    let data_start = sfr_body.get_index();
+
+    visit_global_data(&mut sfr_body, visitor, 1, table.global_data_table_1_count, options)?;
+    visit_global_data(&mut sfr_body, visitor, 2, table.global_data_table_2_count, options)?;
+    let change_form_count = match options.max_change_forms {
+        Some(max) if max < table.change_form_count as usize => max as u32,
+        _ => table.change_form_count,
+    };
+    for _i in 0..change_form_count {
+        let header = read_change_form_header(&mut sfr_body)?;
+        let stored = sfr_body.read_bytes(header.length1 as usize)?;
+        visitor.on_change_form(build_change_form(&header, stored)?);
+    }
+    scan_change_form_headers(&mut sfr_body, table.change_form_count - change_form_count)?;
+    if change_form_count < table.change_form_count {
+        visitor.on_warning(ParseWarning::TruncatedChangeForms { read: change_form_count, count: table.change_form_count });
+    }
+    // see parse_save_file_with_options() for the + 1
+    visit_global_data(&mut sfr_body, visitor, 3, table.global_data_table_3_count.saturating_add(1), options)?;
+
+    let offset_check = check_section_offset(&table, "form id array", table.form_id_array_count_offset, data_start, sfr_body.get_index());
+    match (offset_check, options.recovery) {
+        (Err(e), RecoveryStrategy::Strict) => return Err(e),
+        (Err(SaveParseError::SectionOffsetMismatch { section, expected, actual }), RecoveryStrategy::Lenient) => {
+            visitor.on_warning(ParseWarning::SectionOffsetMismatch { section, expected, actual });
+        }
+        _ => {}
+    }
+    let form_id_array_count = sfr_body.read_u32()?;
+    visitor.on_form_id_array(&read_u32s_into_vec(&mut sfr_body, form_id_array_count)?);
+    sfr_body.take_warnings().into_iter().for_each(|warning| visitor.on_warning(warning));
+    Ok(())
+}
+
+fn visit_global_data(r: &mut SaveFileReader, visitor: &mut impl SaveVisitor, table: u8, count: u32, options: &ParseOptions) -> Result<(), SaveParseError> {
+    let mut warnings = Vec::new();
+    for _i in 0..count {
+        read_global_data_with_warnings(r, 1, options, &mut warnings)?.into_iter().for_each(|data| visitor.on_global_data(table, data));
+        warnings.drain(..).for_each(|warning| visitor.on_warning(warning));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &[u8] = include_bytes!("../benches/data/sample.ess");
+
+    #[derive(Default)]
+    struct CountingVisitor {
+        header: Option<Header>,
+        plugins: usize,
+        light_plugins: usize,
+        table: Option<FileLocationTable>,
+        global_data: [usize; 3],
+        change_forms: usize,
+        last_change_form: Option<ChangeForm>,
+        form_id_array: Vec<u32>,
+        warnings: Vec<ParseWarning>,
+    }
+
+    impl SaveVisitor for CountingVisitor {
+        fn on_header(&mut self, header: &Header) {
+            self.header = Some(header.clone());
+        }
+
+        fn on_plugin(&mut self, _index: usize, _name: &str, is_light: bool) {
+            match is_light {
+                true => self.light_plugins += 1,
+                false => self.plugins += 1,
+            }
+        }
+
+        fn on_file_location_table(&mut self, table: &FileLocationTable) {
+            self.table = Some(*table);
+        }
+
+        fn on_global_data(&mut self, table: u8, _data: GlobalDataType) {
+            self.global_data[table as usize - 1] += 1;
+        }
+
+        fn on_change_form(&mut self, change_form: ChangeForm) {
+            self.change_forms += 1;
+            self.last_change_form = Some(change_form);
+        }
+
+        fn on_form_id_array(&mut self, form_id_array: &[u32]) {
+            self.form_id_array = form_id_array.to_vec();
+        }
+
+        fn on_warning(&mut self, warning: ParseWarning) {
+            self.warnings.push(warning);
+        }
+    }
+
+    fn visit(buf: &[u8], options: &ParseOptions) -> Result<CountingVisitor, SaveParseError> {
+        let mut visitor = CountingVisitor::default();
+        parse_save_file_streaming_with_options(buf, options, &mut visitor)?;
+        Ok(visitor)
+    }
+
+    #[test]
+    fn visitor_sees_what_the_eager_parse_returns() {
+        let save = parse_save_file(SAMPLE.to_vec()).unwrap();
+        let mut visitor = CountingVisitor::default();
+        parse_save_file_streaming(SAMPLE, &mut visitor).unwrap();
+
+        assert_eq!(visitor.header.unwrap(), save.header);
+        assert_eq!((visitor.plugins, visitor.light_plugins), (save.plugin_info.len(), save.light_plugin_info.len()));
+        assert_eq!(visitor.table.unwrap(), save.file_location_table);
+        assert_eq!(visitor.global_data, [
+            save.global_data_table_1.len(),
+            save.global_data_table_2.len(),
+            save.global_data_table_3.len(),
+        ]);
+        assert_eq!(visitor.change_forms, 603);
+        assert!(visitor.last_change_form.as_ref() == save.change_forms.last());
+        assert_eq!(visitor.form_id_array, save.form_id_array);
+        assert!(visitor.warnings.is_empty() && save.warnings.is_empty());
+    }
+
+    #[test]
+    fn visitor_gets_the_warnings_of_the_eager_parse() {
+        let mut save = parse_save_file(SAMPLE.to_vec()).unwrap();
+        save.global_data_table_2.push(GlobalDataType::UnknownType { data_type: 9000, data: vec![1, 2, 3] });
+        let buf = save.to_bytes_patched(SAMPLE).unwrap();
+
+        let options = ParseOptions { max_change_forms: Some(10), ..ParseOptions::default() };
+        let save = parse_save_file_with_options(&buf, &options).unwrap();
+        let visitor = visit(&buf, &options).unwrap();
+        assert_eq!(visitor.warnings, [
+            ParseWarning::UnknownGlobalDataType(9000),
+            ParseWarning::TruncatedChangeForms { read: 10, count: 603 },
+        ]);
+        assert_eq!(visitor.warnings, save.warnings);
+        assert_eq!(visitor.change_forms, 10);
+        assert_eq!(visitor.global_data[1], save.global_data_table_2.len());
+        assert_eq!(visitor.form_id_array, save.form_id_array);
+
+        let silent = ParseOptions { recovery: RecoveryStrategy::Silent, ..ParseOptions::default() };
+        assert!(visit(&buf, &silent).unwrap().warnings.is_empty());
+        let strict = ParseOptions { recovery: RecoveryStrategy::Strict, ..ParseOptions::default() };
+        assert!(matches!(visit(&buf, &strict), Err(SaveParseError::Reader(ReaderError::UnknownDataType { data_type: 9000, .. }))));
+    }
+
+    #[test]
+    fn body_size_limit_is_checked_before_decompressing() {
+        let options = ParseOptions { max_body_size: Some(1000), ..ParseOptions::default() };
+        let mut visitor = CountingVisitor::default();
+        let result = parse_save_file_streaming_with_options(SAMPLE, &options, &mut visitor);
+        assert!(matches!(result, Err(SaveParseError::BodyTooLarge { limit: 1000, .. })));
+        assert!(visitor.header.is_none());
+    }
+}
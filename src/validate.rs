@@ -0,0 +1,137 @@
+//! Consistency checks of a possibly modified save, run before writing it.
+
+use crate::*;
+
+impl SaveFile {
+    /// Checks that the parts of the save agree with each other, returning all problems found:
+    /// - the counts in the file location table match the lengths of the tables
+    /// - all RefIDs with an index refer to an entry of the form id array
+    /// - the screenshot data matches its dimensions, and those match the header
+    ///
+    /// A freshly parsed save is always consistent in the counts, as they were used to read the tables.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        let table = &self.file_location_table;
+        let counts = [
+            ("global data table 1", table.global_data_table_1_count, self.global_data_table_1.len()),
+            ("global data table 2", table.global_data_table_2_count, self.global_data_table_2.len()),
+            ("change forms", table.change_form_count, self.change_forms.len()),
+            // stored one less than the actual count, see parse_save_file_with_options()
+            ("global data table 3", table.global_data_table_3_count, self.global_data_table_3.len().saturating_sub(1)),
+        ];
+        for (name, count, entries) in counts {
+            if count as usize != entries {
+                errors.push(ValidationError::CountMismatch { table: name, count, entries });
+            }
+        }
+
+        let form_id_array_len = self.form_id_array.len();
+        for form_id in self.all_ref_ids() {
+            if let FormIdType::Index(index) = form_id {
+                if index as usize >= form_id_array_len {
+                    errors.push(ValidationError::FormIdIndexOutOfRange { index, form_id_array_len });
+                }
+            }
+        }
+
+        let screenshot = &self.screenshot_data;
+        let expected = screenshot.channel_order.bytes_per_pixel() * screenshot.width as usize * screenshot.height as usize;
+        if screenshot.data.len() != expected {
+            errors.push(ValidationError::ScreenshotSizeMismatch { expected, actual: screenshot.data.len() });
+        }
+        let header = (self.header.shot_width, self.header.shot_height);
+        if header != (screenshot.width, screenshot.height) {
+            errors.push(ValidationError::ScreenshotDimensionMismatch { header, screenshot: (screenshot.width, screenshot.height) });
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> SaveFile {
+        parse_save_file(include_bytes!("../benches/data/sample.ess").to_vec()).unwrap()
+    }
+
+    #[test]
+    fn parsed_sample_is_valid() {
+        assert_eq!(sample().validate(), Ok(()));
+    }
+
+    #[test]
+    fn global_data_table_3_count_is_one_less() {
+        let mut save = sample();
+        let entries = save.global_data_table_3.len();
+        assert_eq!(save.file_location_table.global_data_table_3_count as usize, entries - 1);
+
+        save.file_location_table.global_data_table_3_count = entries as u32;
+        assert_eq!(save.validate(), Err(vec![ValidationError::CountMismatch {
+            table: "global data table 3",
+            count: entries as u32,
+            entries: entries - 1,
+        }]));
+
+        save.file_location_table.global_data_table_3_count = entries as u32 - 1;
+        save.global_data_table_3.pop();
+        assert_eq!(save.validate(), Err(vec![ValidationError::CountMismatch {
+            table: "global data table 3",
+            count: entries as u32 - 1,
+            entries: entries - 2,
+        }]));
+    }
+
+    #[test]
+    fn counts_must_match_the_tables() {
+        let mut save = sample();
+        save.change_forms.pop();
+        save.global_data_table_1.clear();
+        assert_eq!(save.validate(), Err(vec![
+            ValidationError::CountMismatch { table: "global data table 1", count: save.file_location_table.global_data_table_1_count, entries: 0 },
+            ValidationError::CountMismatch { table: "change forms", count: 603, entries: 602 },
+        ]));
+    }
+
+    #[test]
+    fn form_id_index_must_be_in_the_form_id_array() {
+        let mut save = sample();
+        let form_id_array_len = save.form_id_array.len();
+        save.change_forms[0].form_id = FormIdType::Index(form_id_array_len as u32 - 1);
+        assert_eq!(save.validate(), Ok(()));
+
+        save.change_forms[0].form_id = FormIdType::Index(form_id_array_len as u32);
+        assert_eq!(save.validate(), Err(vec![ValidationError::FormIdIndexOutOfRange { index: form_id_array_len as u32, form_id_array_len }]));
+
+        // other form id types don't refer to the array
+        save.change_forms[0].form_id = FormIdType::Default(0xFFFFFF);
+        assert_eq!(save.validate(), Ok(()));
+    }
+
+    #[test]
+    fn screenshot_must_match_its_dimensions_and_the_header() {
+        let mut save = sample();
+        let expected = save.screenshot_data.data.len();
+        save.screenshot_data.data.pop();
+        assert_eq!(save.validate(), Err(vec![ValidationError::ScreenshotSizeMismatch { expected, actual: expected - 1 }]));
+
+        let mut save = sample();
+        let (width, height) = (save.header.shot_width, save.header.shot_height);
+        save.header.shot_width += 1;
+        assert_eq!(save.validate(), Err(vec![ValidationError::ScreenshotDimensionMismatch {
+            header: (width + 1, height),
+            screenshot: (width, height),
+        }]));
+
+        // both problems at once: new dimensions without new pixels, and a header that differs from them
+        save.screenshot_data.width += 1;
+        save.header.shot_height += 1;
+        let errors = save.validate().unwrap_err();
+        assert!(matches!(errors[..], [ValidationError::ScreenshotSizeMismatch { .. }, ValidationError::ScreenshotDimensionMismatch { .. }]));
+    }
+}
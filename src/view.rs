@@ -0,0 +1,493 @@
+//! A read-only view of a save that borrows its buffer and parses sections on access.
+
+use crate::*;
+use std::cell::OnceCell;
+use std::ops::Range;
+
+/// A save file that is only parsed as far as the accessed sections require, for tools that read
+/// a few fields from many saves. Unlike ```SaveFile```, nothing is copied out of the buffer up front:
+/// ```header()``` only reads the header, and the body is decompressed on the first access to a
+/// section of it and kept for later accesses. Uncompressed bodies are borrowed as well.
+///
+/// The change forms and the form id array are found through the file location table,
+/// so the global data in front of them is never read.
+pub struct SaveFileView<'a> {
+    buf: &'a [u8],
+    body: OnceCell<Cow<'a, [u8]>>,
+}
+
+impl<'a> SaveFileView<'a> {
+    /// Fails with ```SaveParseError::InvalidMagic``` if ```buf``` is not a save, nothing else is read.
+    pub fn new(buf: &'a [u8]) -> Result<Self, SaveParseError> {
+        if !is_skyrim_save(buf) {
+            return Err(SaveParseError::InvalidMagic);
+        }
+        Ok(SaveFileView {
+            buf,
+            body: OnceCell::new(),
+        })
+    }
+
+    pub fn header(&self) -> Result<Header, SaveParseError> {
+        parse_header_only(self.buf)
+    }
+
+    /// The pixels of the screenshot, borrowed from the buffer. The layout is described by
+    /// ```ChannelOrder::for_version()``` and the dimensions in the header.
+    pub fn screenshot(&self) -> Result<&'a [u8], SaveParseError> {
+        let mut sfr = SaveFileReader::from_slice(self.buf);
+        Ok(&self.buf[read_preamble_layout(&mut sfr)?.screenshot])
+    }
+
+    /// The names of the plugins and the light plugins, see ```SaveFile::plugin_info```.
+    pub fn plugins(&self) -> Result<(Vec<String>, Vec<String>), SaveParseError> {
+        let mut sfr = SaveFileReader::from_slice(self.body()?);
+        let form_version = sfr.read_u8()?;
+        read_plugin_info(&mut sfr, form_version)
+    }
+
+    pub fn form_version(&self) -> Result<u8, SaveParseError> {
+        Ok(SaveFileReader::from_slice(self.body()?).read_u8()?)
+    }
+
+    pub fn file_location_table(&self) -> Result<FileLocationTable, SaveParseError> {
+        let (table, _) = self.read_file_location_table()?;
+        Ok(table)
+    }
+
+    /// Iterates over the change forms without decompressing them, see ```ChangeFormEntry::decode()```.
+    pub fn change_forms(&self) -> Result<ChangeFormIter<'_>, SaveParseError> {
+        let (table, data_start) = self.read_file_location_table()?;
+        let data = self.section(&table, "change forms", table.change_forms_offset, data_start)?;
+        Ok(ChangeFormIter {
+            data,
+            position: 0,
+            remaining: table.change_form_count,
+        })
+    }
+
+    /// The form id array, to resolve the form ids of the change forms with ```FormIdType::resolve()```.
+    pub fn form_id_array(&self) -> Result<Vec<u32>, SaveParseError> {
+        let (table, data_start) = self.read_file_location_table()?;
+        let mut reader = SaveFileReader::from_slice(self.section(&table, "form id array", table.form_id_array_count_offset, data_start)?);
+        let count = reader.read_u32()?;
+        Ok(read_u32s_into_vec(&mut reader, count)?)
+    }
+
+    /// The uncompressed body, decompressed on the first call.
+    fn body(&self) -> Result<&[u8], SaveParseError> {
+        if let Some(body) = self.body.get() {
+            return Ok(body);
+        }
+        let mut sfr = SaveFileReader::from_slice(self.buf);
+        let layout = read_preamble_layout(&mut sfr)?;
+        let uncompressed_len = layout.uncompressed_len;
+        let body = match read_body(&sfr, &layout.header, uncompressed_len)? {
+            // the data is borrowed from self.buf, not from the reader
+            Cow::Borrowed(_) => Cow::Borrowed(&self.buf[sfr.get_index()..sfr.get_index() + uncompressed_len as usize]),
+            Cow::Owned(body) => Cow::Owned(body),
+        };
+        Ok(self.body.get_or_init(|| body))
+    }
+
+    /// The file location table and the position of global data table 1 in the body, which directly follows it.
+    fn read_file_location_table(&self) -> Result<(FileLocationTable, usize), SaveParseError> {
+        let mut sfr = SaveFileReader::from_slice(self.body()?);
+        let _form_version = sfr.read_u8()?;
+        let plugin_info_size = sfr.read_u32()?;
+        sfr.read_bytes(plugin_info_size as usize)?;
+        let table = crate::read_file_location_table(&mut sfr)?;
+        Ok((table, sfr.get_index()))
+    }
+
+    /// The body from ```offset``` of the file location table on, relative to the offset of
+    /// global data table 1 as in ```check_section_offset()```. Fails if the offset points outside of the body.
+    fn section(&self, table: &FileLocationTable, section: &'static str, offset: u32, data_start: usize) -> Result<&[u8], SaveParseError> {
+        let start = self.body_position(table, section, offset, data_start)?;
+        Ok(&self.body()?[start..])
+    }
+
+    /// The position in the body of ```offset``` from the file location table, see ```section()```.
+    fn body_position(&self, table: &FileLocationTable, section: &'static str, offset: u32, data_start: usize) -> Result<usize, SaveParseError> {
+        let body_len = self.body()?.len();
+        (offset as usize).checked_sub(table.global_data_table_1_offset as usize)
+            .map(|relative| data_start + relative)
+            .filter(|start| *start <= body_len)
+            .ok_or(SaveParseError::SectionOffsetMismatch {
+                section,
+                expected: offset,
+                actual: table.global_data_table_1_offset,
+            })
+    }
+
+    /// The exact bytes of ```section``` as in the save, e.g. to compare them with a re-encoded section.
+    /// The sections of the body are taken from the uncompressed body, ```Section::Body``` is the body as stored.
+    pub fn section_bytes(&self, section: Section) -> Result<&[u8], SaveParseError> {
+        let range = self.section_range(section)?;
+        match section.is_in_body() {
+            true => Ok(&self.body()?[range]),
+            false => Ok(&self.buf[range]),
+        }
+    }
+
+    /// The range of ```section``` in the file, or in the uncompressed body for sections of the body.
+    /// Sections whose size is stored in front of them include the size, as in ```SectionSizes```.
+    pub fn section_range(&self, section: Section) -> Result<Range<usize>, SaveParseError> {
+        let mut sfr = SaveFileReader::from_slice(self.buf);
+        let layout = read_preamble_layout(&mut sfr)?;
+        match section {
+            Section::Header => return Ok(layout.header_range),
+            Section::Screenshot => return Ok(layout.screenshot),
+            Section::Body => {
+                let start = sfr.get_index();
+                return match layout.header.compression_type {
+                    0 => Ok(start..(start + layout.uncompressed_len as usize).min(self.buf.len())),
+                    _ => Ok(start..self.buf.len()),
+                };
+            }
+            _ => {}
+        }
+
+        let body = self.body()?;
+        let mut sfr = SaveFileReader::from_slice(body);
+        let _form_version = sfr.read_u8()?;
+        let plugin_info_start = sfr.get_index();
+        let plugin_info_size = sfr.read_u32()?;
+        sfr.read_bytes(plugin_info_size as usize)?;
+        let table_start = sfr.get_index();
+        let table = crate::read_file_location_table(&mut sfr)?;
+        let data_start = sfr.get_index();
+        let position = |name, offset| self.body_position(&table, name, offset, data_start);
+        let form_id_array_start = position("form id array", table.form_id_array_count_offset)?;
+        let unknown_3_table_start = position("unknown table 3", table.unknown_table_3_offset)?;
+
+        let (start, end) = match section {
+            Section::PluginInfo => (plugin_info_start, table_start),
+            Section::FileLocationTable => (table_start, data_start),
+            Section::GlobalDataTable1 => (data_start, position("global data table 2", table.global_data_table_2_offset)?),
+            Section::GlobalDataTable2 => (
+                position("global data table 2", table.global_data_table_2_offset)?,
+                position("change forms", table.change_forms_offset)?,
+            ),
+            Section::ChangeForms => (
+                position("change forms", table.change_forms_offset)?,
+                position("global data table 3", table.global_data_table_3_offset)?,
+            ),
+            Section::GlobalDataTable3 => (position("global data table 3", table.global_data_table_3_offset)?, form_id_array_start),
+            Section::FormIdArray | Section::VisitedWorldspaceArray => {
+                let mut reader = SaveFileReader::from_slice(&body[form_id_array_start..]);
+                let count = reader.read_u32()?;
+                let form_id_array_end = form_id_array_start + 4 + 4 * count as usize;
+                match section {
+                    Section::FormIdArray => (form_id_array_start, form_id_array_end),
+                    _ => (form_id_array_end, unknown_3_table_start),
+                }
+            }
+            _ => {
+                // the size doesn't include itself
+                let size = SaveFileReader::from_slice(&body[unknown_3_table_start..]).read_u32()?;
+                (unknown_3_table_start, unknown_3_table_start + 4 + size as usize)
+            }
+        };
+        if start > end || end > body.len() {
+            return Err(SaveParseError::Reader(ReaderError::UnexpectedEnd {
+                index: start,
+                requested: end.saturating_sub(start),
+                buffer_len: body.len(),
+            }));
+        }
+        Ok(start..end)
+    }
+}
+
+/// The sections of a save, in the order they are stored, see ```SaveFileView::section_bytes()```.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Section {
+    /// The header without the magic and the header size.
+    Header,
+    Screenshot,
+    /// The body as stored in the file, compressed unless the compression type is 0.
+    Body,
+    PluginInfo,
+    /// The file location table including its unused space.
+    FileLocationTable,
+    GlobalDataTable1,
+    GlobalDataTable2,
+    ChangeForms,
+    GlobalDataTable3,
+    FormIdArray,
+    VisitedWorldspaceArray,
+    UnknownTable3,
+}
+
+impl Section {
+    /// Whether the section is part of the uncompressed body.
+    pub fn is_in_body(&self) -> bool {
+        !matches!(self, Section::Header | Section::Screenshot | Section::Body)
+    }
+}
+
+/// The exact bytes of ```section``` in the save ```buf```, see ```SaveFileView::section_bytes()```.
+/// Sections of compressed bodies are copied out of the decompressed body.
+pub fn section_bytes(buf: &[u8], section: Section) -> Result<Cow<'_, [u8]>, SaveParseError> {
+    let view = SaveFileView::new(buf)?;
+    let range = view.section_range(section)?;
+    if !section.is_in_body() {
+        return Ok(Cow::Borrowed(&buf[range]));
+    }
+    view.body()?;
+    match view.body.into_inner() {
+        Some(Cow::Borrowed(body)) => Ok(Cow::Borrowed(&body[range])),
+        Some(Cow::Owned(body)) => Ok(Cow::Owned(body[range].to_vec())),
+        // initialized by body() above
+        None => unreachable!(),
+    }
+}
+
+/// A change form whose data is still stored as in the save, see ```SaveFileView::change_forms()```.
+#[derive(Clone, Copy, Debug)]
+pub struct ChangeFormEntry<'a> {
+    pub header: ChangeFormHeader,
+    /// The data as stored in the save, zlib compressed if ```header.length2``` is not 0.
+    pub stored: &'a [u8],
+}
+
+impl ChangeFormEntry<'_> {
+    /// Decompresses the data into an owned ```ChangeForm```.
+    pub fn decode(&self) -> Result<ChangeForm, SaveParseError> {
+        build_change_form(&self.header, self.stored)
+    }
+}
+
+/// Iterator over the change forms of a ```SaveFileView```. Ends after the first error.
+pub struct ChangeFormIter<'a> {
+    data: &'a [u8],
+    position: usize,
+    remaining: u32,
+}
+
+impl<'a> Iterator for ChangeFormIter<'a> {
+    type Item = Result<ChangeFormEntry<'a>, SaveParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let mut reader = SaveFileReader::from_slice(&self.data[self.position..]);
+        let entry = read_change_form_header(&mut reader).and_then(|header| {
+            let start = self.position + reader.get_index();
+            reader.read_bytes(header.length1 as usize)?;
+            Ok((header, start))
+        });
+        match entry {
+            Ok((header, start)) => {
+                let end = start + header.length1 as usize;
+                self.position = end;
+                Some(Ok(ChangeFormEntry { header, stored: &self.data[start..end] }))
+            }
+            Err(e) => {
+                self.remaining = 0;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &[u8] = include_bytes!("../benches/data/sample.ess");
+
+    /// The sample with an uncompressed body, so the file location table can be patched in place.
+    fn uncompressed_sample() -> Vec<u8> {
+        let mut save = parse_save_file(SAMPLE.to_vec()).unwrap();
+        save.header.compression_type = 0;
+        save.to_bytes_patched(SAMPLE).unwrap()
+    }
+
+    /// Overwrites ```field``` of the file location table of an uncompressed save.
+    fn patch_table(buf: &mut [u8], field: &str, value: u32) {
+        let (_, spans) = parse_save_file_with_spans(buf, &ParseOptions::default()).unwrap();
+        let span = spans.iter().find(|span| span.path == format!("file_location_table.{}", field)).unwrap();
+        let body_start = SaveFileView::new(buf).unwrap().section_range(Section::Body).unwrap().start;
+        let position = body_start + span.range.start;
+        buf[position..position + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn view_matches_the_parsed_save() {
+        let save = parse_save_file(SAMPLE.to_vec()).unwrap();
+        let view = SaveFileView::new(SAMPLE).unwrap();
+
+        assert_eq!(view.header().unwrap(), save.header);
+        assert_eq!(view.screenshot().unwrap(), save.screenshot_data.data);
+        assert_eq!(view.form_version().unwrap(), save.form_version);
+        assert_eq!(view.plugins().unwrap(), (save.plugin_info.clone(), save.light_plugin_info.clone()));
+        assert_eq!(view.file_location_table().unwrap(), save.file_location_table);
+        assert_eq!(view.form_id_array().unwrap(), save.form_id_array);
+
+        let change_forms: Vec<ChangeForm> = view.change_forms().unwrap().map(|entry| entry.unwrap().decode().unwrap()).collect();
+        assert_eq!(change_forms.len(), 603);
+        assert!(change_forms == save.change_forms);
+    }
+
+    #[test]
+    fn body_is_read_on_first_access() {
+        let view = SaveFileView::new(SAMPLE).unwrap();
+        view.header().unwrap();
+        view.screenshot().unwrap();
+        assert!(view.body.get().is_none());
+
+        view.form_version().unwrap();
+        // the sample is LZ4 compressed
+        assert!(matches!(view.body.get(), Some(Cow::Owned(_))));
+        let body = view.body().unwrap().as_ptr();
+        view.form_id_array().unwrap();
+        assert_eq!(view.body().unwrap().as_ptr(), body);
+
+        let buf = uncompressed_sample();
+        let view = SaveFileView::new(&buf).unwrap();
+        view.form_version().unwrap();
+        assert!(matches!(view.body.get(), Some(Cow::Borrowed(_))));
+        assert_eq!(view.form_id_array().unwrap(), parse_save_file(SAMPLE.to_vec()).unwrap().form_id_array);
+    }
+
+    #[test]
+    fn offsets_outside_of_the_body_are_an_error() {
+        let buf = uncompressed_sample();
+        let table = SaveFileView::new(&buf).unwrap().file_location_table().unwrap();
+
+        for offset in [u32::MAX, table.global_data_table_1_offset - 1] {
+            let mut buf = buf.clone();
+            patch_table(&mut buf, "change_forms_offset", offset);
+            let view = SaveFileView::new(&buf).unwrap();
+            assert!(matches!(view.change_forms(), Err(SaveParseError::SectionOffsetMismatch { section: "change forms", .. })));
+
+            patch_table(&mut buf, "form_id_array_count_offset", offset);
+            let view = SaveFileView::new(&buf).unwrap();
+            assert!(matches!(view.form_id_array(), Err(SaveParseError::SectionOffsetMismatch { section: "form id array", .. })));
+        }
+    }
+
+    #[test]
+    fn change_form_iteration_ends_after_an_error() {
+        let mut buf = uncompressed_sample();
+        let table = SaveFileView::new(&buf).unwrap().file_location_table().unwrap();
+        // the change forms are followed by global data table 3, which doesn't parse as change forms
+        // to its end
+        patch_table(&mut buf, "change_form_count", table.change_form_count + 1000);
+        let view = SaveFileView::new(&buf).unwrap();
+        let entries: Vec<_> = view.change_forms().unwrap().collect();
+        assert!(entries.len() > 603 && entries.len() < 1603);
+        assert!(entries[..603].iter().all(Result::is_ok));
+        assert!(entries.last().unwrap().is_err());
+    }
+
+    #[test]
+    fn invalid_magic_is_rejected() {
+        assert!(matches!(SaveFileView::new(b"TESV_SAVEGAM"), Err(SaveParseError::InvalidMagic)));
+    }
+
+    const BODY_SECTIONS: [Section; 9] = [
+        Section::PluginInfo,
+        Section::FileLocationTable,
+        Section::GlobalDataTable1,
+        Section::GlobalDataTable2,
+        Section::ChangeForms,
+        Section::GlobalDataTable3,
+        Section::FormIdArray,
+        Section::VisitedWorldspaceArray,
+        Section::UnknownTable3,
+    ];
+
+    #[test]
+    fn section_bytes_have_the_parsed_sizes() {
+        let save = parse_save_file(SAMPLE.to_vec()).unwrap();
+        let view = SaveFileView::new(SAMPLE).unwrap();
+        let sizes = save.section_sizes;
+        let expected = [
+            sizes.plugin_info,
+            sizes.file_location_table,
+            sizes.global_data_table_1,
+            sizes.global_data_table_2,
+            sizes.change_forms,
+            sizes.global_data_table_3,
+            sizes.form_id_array,
+            sizes.visited_worldspace_array,
+            sizes.unknown_3_table,
+        ];
+        for (section, size) in BODY_SECTIONS.iter().zip(expected) {
+            assert_eq!(view.section_bytes(*section).unwrap().len(), size, "{:?}", section);
+            assert_eq!(section_bytes(SAMPLE, *section).unwrap(), view.section_bytes(*section).unwrap(), "{:?}", section);
+        }
+
+        // the sections follow each other and, behind the form version, cover the whole body
+        let body = decompress_body(SAMPLE).unwrap();
+        let ranges: Vec<Range<usize>> = BODY_SECTIONS.iter().map(|section| view.section_range(*section).unwrap()).collect();
+        assert_eq!(ranges[0].start, 1);
+        assert!(ranges.windows(2).all(|pair| pair[0].end == pair[1].start));
+        assert_eq!(ranges[8].end, body.len() - save.trailing_bytes.len());
+        let concatenated: Vec<u8> = BODY_SECTIONS.iter().flat_map(|section| view.section_bytes(*section).unwrap().to_vec()).collect();
+        assert_eq!(concatenated, body[1..ranges[8].end]);
+
+        assert_eq!(view.section_bytes(Section::Screenshot).unwrap(), save.screenshot_data.data);
+        assert_eq!(view.section_bytes(Section::Body).unwrap().len(), save.body_compressed_len as usize);
+        assert!(matches!(section_bytes(SAMPLE, Section::Header).unwrap(), Cow::Borrowed(_)));
+        assert!(matches!(section_bytes(SAMPLE, Section::ChangeForms).unwrap(), Cow::Owned(_)));
+    }
+
+    #[test]
+    fn sections_of_an_uncompressed_body_are_borrowed() {
+        let buf = uncompressed_sample();
+        let save = parse_save_file(buf.clone()).unwrap();
+        assert!(matches!(section_bytes(&buf, Section::ChangeForms).unwrap(), Cow::Borrowed(_)));
+        assert_eq!(section_bytes(&buf, Section::ChangeForms).unwrap().len(), save.section_sizes.change_forms);
+        assert_eq!(section_bytes(&buf, Section::Body).unwrap().len(), save.body_uncompressed_len as usize);
+    }
+
+    #[test]
+    fn corrupt_unknown_table_3_offset_is_an_error() {
+        let buf = uncompressed_sample();
+        let view = SaveFileView::new(&buf).unwrap();
+        let table = view.file_location_table().unwrap();
+        let data_start = view.section_range(Section::GlobalDataTable1).unwrap().start;
+        let body_len = view.body().unwrap().len();
+        let offset_of = |position: usize| table.global_data_table_1_offset + (position - data_start) as u32;
+        let form_id_array_end = view.section_range(Section::FormIdArray).unwrap().end;
+
+        // past the end of the body
+        for offset in [u32::MAX, offset_of(body_len + 1)] {
+            let mut buf = buf.clone();
+            patch_table(&mut buf, "unknown_table_3_offset", offset);
+            let view = SaveFileView::new(&buf).unwrap();
+            assert!(matches!(view.section_bytes(Section::UnknownTable3), Err(SaveParseError::SectionOffsetMismatch { section: "unknown table 3", .. })));
+            assert!(view.section_bytes(Section::VisitedWorldspaceArray).is_err());
+            // the sections in front of the body don't depend on the table
+            assert!(view.section_bytes(Section::Header).is_ok());
+        }
+
+        // no room for the size
+        for offset in [offset_of(body_len), offset_of(body_len - 2)] {
+            let mut buf = buf.clone();
+            patch_table(&mut buf, "unknown_table_3_offset", offset);
+            let view = SaveFileView::new(&buf).unwrap();
+            assert!(matches!(view.section_bytes(Section::UnknownTable3), Err(SaveParseError::Reader(ReaderError::UnexpectedEnd { .. }))));
+        }
+
+        // in front of the end of the form id array, the visited worldspace array would end before it starts
+        let mut patched = buf.clone();
+        patch_table(&mut patched, "unknown_table_3_offset", offset_of(form_id_array_end - 1));
+        let view = SaveFileView::new(&patched).unwrap();
+        assert!(matches!(view.section_bytes(Section::VisitedWorldspaceArray), Err(SaveParseError::Reader(ReaderError::UnexpectedEnd { .. }))));
+
+        // the size points past the end of the body
+        let view = SaveFileView::new(&buf).unwrap();
+        let start = view.section_range(Section::Body).unwrap().start + view.section_range(Section::UnknownTable3).unwrap().start;
+        let mut patched = buf.clone();
+        patched[start..start + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let view = SaveFileView::new(&patched).unwrap();
+        assert!(matches!(view.section_bytes(Section::UnknownTable3), Err(SaveParseError::Reader(ReaderError::UnexpectedEnd { .. }))));
+    }
+}
@@ -0,0 +1,196 @@
+//! Writing the data types of a save, see ```SaveFileWriter```.
+//!
+//! The counts in front of arrays are stored with different encodings, and writing one with the wrong
+//! encoding shifts everything behind it. Counts of the file itself are fixed width:
+//! - u8: the plugin count
+//! - u16: the light plugin count, string lengths and the Papyrus string count
+//! - u32: the counts of the file location table, the form id array, the visited worldspace array
+//!   and unknown table 3, the lengths of global data entries, the misc stats, the shown help messages
+//!   of the interface and the Papyrus script and instance counts
+//! - u8, u16 or u32 as given by the flags of the change form: the lengths of change forms
+//!
+//! Counts inside global data and change form data are mostly vsvals: global variables, created objects
+//! and their effects, image space modifiers, the last used items of the interface, tracks of the audio,
+//! sky cells, crimes, actor causes, magic favorites, NPC skills, spell lists and the vsval prefixed RefID lists.
+//! The parsers in ```global_data``` and ```change_form_data``` are the reference for the rest.
+//! Use ```SaveFileWriter::write_count()``` with the ```CountEncoding``` the field was read with.
+
+use crate::fundamental_types::*;
+use crate::SaveWriteError;
+use std::convert::TryFrom;
+
+/// How the count of an array is stored, see the ```writer``` module.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CountEncoding {
+    U8,
+    U16,
+    U32,
+    /// The smallest vsval the count fits into, as the game writes them.
+    VSVal,
+}
+
+/// Writes the data types used in a savegame into a buffer, the counterpart of ```SaveFileReader```.
+/// All values are written little endian.
+#[derive(Default)]
+pub struct SaveFileWriter {
+    buffer: Vec<u8>,
+}
+
+impl SaveFileWriter {
+    pub fn new() -> Self {
+        SaveFileWriter {
+            buffer: Vec::new(),
+        }
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_i32(&mut self, value: i32) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.buffer.push(value);
+    }
+
+    /// Writes a vsval with the width of the given variant.
+    /// Fails if the value does not fit into the variant's width (6, 14 or 22 bits).
+    pub fn write_vsval(&mut self, value: &VSVal) -> Result<(), SaveWriteError> {
+        match *value {
+            VSVal::U8(x) if x < 1 << 6 => self.write_u8(x << 2),
+            VSVal::U16(x) if x < 1 << 14 => self.write_u16(x << 2 | 1),
+            VSVal::U32(x) if x < 1 << 22 => self.buffer.extend_from_slice(&(x << 2 | 2).to_le_bytes()[..3]),
+            _ => return Err(SaveWriteError::VSValOutOfRange(value.clone())),
+        }
+        Ok(())
+    }
+
+    /// Writes the count of an array with the given encoding.
+    /// Fails if the count does not fit, instead of writing a truncated count.
+    pub fn write_count(&mut self, count: usize, encoding: CountEncoding) -> Result<(), SaveWriteError> {
+        let out_of_range = || SaveWriteError::CountOutOfRange { count, encoding };
+        match encoding {
+            CountEncoding::U8 => self.write_u8(u8::try_from(count).map_err(|_| out_of_range())?),
+            CountEncoding::U16 => self.write_u16(u16::try_from(count).map_err(|_| out_of_range())?),
+            CountEncoding::U32 => self.write_u32(u32::try_from(count).map_err(|_| out_of_range())?),
+            CountEncoding::VSVal => match u32::try_from(count) {
+                Ok(value) if value < 1 << 22 => self.write_vsval(&VSVal::smallest(value))?,
+                _ => return Err(out_of_range()),
+            },
+        }
+        Ok(())
+    }
+
+    /// Writes a string prefixed with its length as u16.
+    pub fn write_w_string(&mut self, value: &str) -> Result<(), SaveWriteError> {
+        let length = u16::try_from(value.len()).map_err(|_| SaveWriteError::StringTooLong(value.len()))?;
+        self.write_u16(length);
+        self.write_bytes(value.as_bytes());
+        Ok(())
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    pub fn get_buffer(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    pub fn get_buffer_len(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+pub fn write_filetime(w: &mut SaveFileWriter, filetime: &FileTime) {
+    w.write_u32(filetime.dw_low_date_time);
+    w.write_u32(filetime.dw_high_date_time);
+}
+
+/// Writes a form id as the 3 byte RefID, the counterpart of ```read_ref_id()```.
+pub fn write_ref_id(w: &mut SaveFileWriter, form_id: FormIdType) -> Result<(), SaveWriteError> {
+    let (kind, value) = match form_id {
+        // read_ref_id() stores the index - 1
+        FormIdType::Index(index) => (0, index.checked_add(1).ok_or(SaveWriteError::FormIdOutOfRange(form_id))?),
+        FormIdType::Default(id) => (1, id),
+        FormIdType::Created(id) => (2, id),
+        FormIdType::Unknown(id) => (3, id),
+    };
+    // zero is always stored as plain 0, see RefId::get_form_id()
+    let kind = if value == 0 { 0 } else { kind };
+    if value >= 1 << 22 {
+        return Err(SaveWriteError::FormIdOutOfRange(form_id));
+    }
+    w.write_bytes(&[(kind << 6) | (value >> 16) as u8, (value >> 8) as u8, value as u8]);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SaveFileReader;
+
+    /// The largest and smallest value of every width, and one past the largest 22 bit value.
+    const BOUNDARIES: [u32; 7] = [0, 63, 64, 16383, 16384, 4194303, 4194304];
+
+    fn read_back(bytes: &[u8]) -> (u32, usize) {
+        let mut r = SaveFileReader::from_slice(bytes);
+        let value = r.read_vsval().unwrap().into();
+        (value, r.get_index())
+    }
+
+    #[test]
+    fn write_count_round_trips_at_the_vsval_boundaries() {
+        for (value, width) in BOUNDARIES[..6].iter().zip([1, 1, 2, 2, 3, 3]) {
+            let mut w = SaveFileWriter::new();
+            w.write_count(*value as usize, CountEncoding::VSVal).unwrap();
+            assert_eq!(w.get_buffer_len(), width);
+            assert_eq!(read_back(&w.get_buffer()), (*value, width));
+        }
+        let mut w = SaveFileWriter::new();
+        assert!(matches!(
+            w.write_count(BOUNDARIES[6] as usize, CountEncoding::VSVal),
+            Err(SaveWriteError::CountOutOfRange { count: 4194304, encoding: CountEncoding::VSVal })
+        ));
+        assert_eq!(w.get_buffer_len(), 0);
+    }
+
+    #[test]
+    fn write_vsval_round_trips_at_the_boundaries_of_its_width() {
+        let cases = [
+            (VSVal::U8(0), true),
+            (VSVal::U8(63), true),
+            (VSVal::U8(64), false),
+            (VSVal::U16(64), true),
+            (VSVal::U16(16383), true),
+            (VSVal::U16(16384), false),
+            (VSVal::U32(16384), true),
+            (VSVal::U32(4194303), true),
+            (VSVal::U32(4194304), false),
+        ];
+        for (vsval, fits) in cases {
+            let mut w = SaveFileWriter::new();
+            match w.write_vsval(&vsval) {
+                Ok(()) => {
+                    assert!(fits, "{:?} was written", vsval);
+                    let len = w.get_buffer_len();
+                    assert_eq!(read_back(&w.get_buffer()), (u32::from(vsval), len));
+                }
+                Err(e) => {
+                    assert!(!fits, "{:?} failed", vsval);
+                    assert!(matches!(e, SaveWriteError::VSValOutOfRange(_)));
+                }
+            }
+        }
+    }
+}